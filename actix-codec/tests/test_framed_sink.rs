@@ -11,7 +11,7 @@ use std::{
 use actix_codec::*;
 use bytes::{Buf as _, BufMut as _, BytesMut};
 use futures_sink::Sink;
-use tokio_test::{assert_ready, task};
+use tokio_test::task;
 
 macro_rules! bilateral {
     ($($x:expr,)*) => {{
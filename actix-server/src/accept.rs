@@ -1,12 +1,15 @@
-use std::{io, thread, time::Duration};
+use std::{io, sync::Arc, thread, time::Duration};
 
 use actix_rt::time::Instant;
 use mio::{Interest, Poll, Token as MioToken};
 use tracing::{debug, error, info};
 
 use crate::{
+    access::ListenerAccess,
     availability::Availability,
+    selector::{WorkerSelector, Workers},
     socket::MioListener,
+    stats::ListenerCounters,
     waker_queue::{WakerInterest, WakerQueue, WAKER_TOKEN},
     worker::{Conn, ServerWorker, WorkerHandleAccept, WorkerHandleServer},
     ServerBuilder, ServerHandle,
@@ -14,24 +17,48 @@ use crate::{
 
 const TIMEOUT_DURATION_ON_ERROR: Duration = Duration::from_millis(510);
 
-struct ServerSocketInfo {
+pub(crate) struct ServerSocketInfo {
     token: usize,
 
     lst: MioListener,
 
+    counters: Arc<ListenerCounters>,
+
+    /// Access control shared by the listeners with the same name.
+    access: Arc<ListenerAccess>,
+
     /// Timeout is used to mark the deadline when this socket's listener should be registered again
     /// after an error.
     timeout: Option<actix_rt::time::Instant>,
 }
 
+impl ServerSocketInfo {
+    pub(crate) fn new(
+        token: usize,
+        lst: MioListener,
+        counters: Arc<ListenerCounters>,
+        access: Arc<ListenerAccess>,
+    ) -> Self {
+        Self {
+            token,
+            lst,
+            counters,
+            access,
+            timeout: None,
+        }
+    }
+}
+
 /// Poll instance of the server.
 pub(crate) struct Accept {
     poll: Poll,
     waker_queue: WakerQueue,
     handles: Vec<WorkerHandleAccept>,
     srv: ServerHandle,
-    next: usize,
+    selector: Box<dyn WorkerSelector>,
     avail: Availability,
+    /// Indexes of workers that missed heartbeats and are not sent connections.
+    stalled: Vec<usize>,
     /// use the smallest duration from sockets timeout.
     timeout: Option<Duration>,
     paused: bool,
@@ -39,10 +66,12 @@ pub(crate) struct Accept {
 
 impl Accept {
     pub(crate) fn start(
-        sockets: Vec<(usize, MioListener)>,
+        sockets: Vec<ServerSocketInfo>,
+        selector: Box<dyn WorkerSelector>,
         builder: &ServerBuilder,
     ) -> io::Result<(WakerQueue, Vec<WorkerHandleServer>, thread::JoinHandle<()>)> {
-        let handle_server = ServerHandle::new(builder.cmd_tx.clone());
+        let handle_server =
+            ServerHandle::new(builder.cmd_tx.clone(), builder.worker_events.clone());
 
         // construct poll instance and its waker
         let poll = Poll::new()?;
@@ -59,7 +88,21 @@ impl Accept {
                     .collect::<Vec<_>>();
 
                 // start worker using service factories
-                ServerWorker::start(idx, factories, waker_queue.clone(), builder.worker_config)
+                let udp_factories = builder
+                    .udp_factories
+                    .iter()
+                    .map(|factory| factory.clone_factory())
+                    .collect();
+
+                ServerWorker::start(
+                    idx,
+                    factories,
+                    udp_factories,
+                    &builder.reuse_port_listeners,
+                    waker_queue.clone(),
+                    builder.worker_config,
+                    builder.worker_hooks.clone(),
+                )
             })
             .collect::<io::Result<Vec<_>>>()?
             .into_iter()
@@ -71,6 +114,7 @@ impl Accept {
             sockets,
             handles_accept,
             handle_server,
+            selector,
         )?;
 
         let accept_handle = thread::Builder::new()
@@ -84,24 +128,16 @@ impl Accept {
     fn new_with_sockets(
         poll: Poll,
         waker_queue: WakerQueue,
-        sockets: Vec<(usize, MioListener)>,
+        mut sockets: Vec<ServerSocketInfo>,
         accept_handles: Vec<WorkerHandleAccept>,
         server_handle: ServerHandle,
-    ) -> io::Result<(Accept, Box<[ServerSocketInfo]>)> {
-        let sockets = sockets
-            .into_iter()
-            .map(|(token, mut lst)| {
-                // Start listening for incoming connections
-                poll.registry()
-                    .register(&mut lst, MioToken(token), Interest::READABLE)?;
-
-                Ok(ServerSocketInfo {
-                    token,
-                    lst,
-                    timeout: None,
-                })
-            })
-            .collect::<io::Result<_>>()?;
+        selector: Box<dyn WorkerSelector>,
+    ) -> io::Result<(Accept, Vec<ServerSocketInfo>)> {
+        for info in &mut sockets {
+            // Start listening for incoming connections
+            poll.registry()
+                .register(&mut info.lst, MioToken(info.token), Interest::READABLE)?;
+        }
 
         let mut avail = Availability::default();
 
@@ -113,8 +149,9 @@ impl Accept {
             waker_queue,
             handles: accept_handles,
             srv: server_handle,
-            next: 0,
+            selector,
             avail,
+            stalled: Vec::new(),
             timeout: None,
             paused: false,
         };
@@ -123,7 +160,7 @@ impl Accept {
     }
 
     /// blocking wait for readiness events triggered by mio
-    fn poll_with(&mut self, sockets: &mut [ServerSocketInfo]) {
+    fn poll_with(&mut self, sockets: &mut Vec<ServerSocketInfo>) {
         let mut events = mio::Events::with_capacity(256);
 
         loop {
@@ -146,7 +183,11 @@ impl Accept {
                     }
                     _ => {
                         let token = usize::from(token);
-                        self.accept(sockets, token);
+
+                        // not all tokens belong to sockets served here; see `ServerBuilder::reuse_port`
+                        if let Some(pos) = sockets.iter().position(|info| info.token == token) {
+                            self.accept(sockets, pos);
+                        }
                     }
                 }
             }
@@ -156,7 +197,7 @@ impl Accept {
         }
     }
 
-    fn handle_waker(&mut self, sockets: &mut [ServerSocketInfo]) -> bool {
+    fn handle_waker(&mut self, sockets: &mut Vec<ServerSocketInfo>) -> bool {
         // This is a loop because interests for command from previous version was
         // a loop that would try to drain the command channel. It's yet unknown
         // if it's necessary/good practice to actively drain the waker queue.
@@ -168,10 +209,18 @@ impl Accept {
             #[allow(clippy::significant_drop_in_scrutinee)]
             match guard.pop_front() {
                 // Worker notified it became available.
-                Some(WakerInterest::WorkerAvailable(idx)) => {
+                Some(WakerInterest::WorkerAvailable(idx, generation)) => {
                     drop(guard);
 
-                    self.avail.set_available(idx, true);
+                    // worker may have been removed, replaced or stalled in the meantime
+                    if self
+                        .handles
+                        .iter()
+                        .any(|handle| handle.idx() == idx && handle.generation() == generation)
+                        && !self.stalled.contains(&idx)
+                    {
+                        self.avail.set_available(idx, true);
+                    }
 
                     if !self.paused {
                         self.accept_all(sockets);
@@ -182,6 +231,7 @@ impl Accept {
                 Some(WakerInterest::Worker(handle)) => {
                     drop(guard);
 
+                    self.stalled.retain(|stalled| *stalled != handle.idx());
                     self.avail.set_available(handle.idx(), true);
                     self.handles.push(handle);
 
@@ -222,6 +272,87 @@ impl Accept {
                     return true;
                 }
 
+                #[cfg(unix)]
+                Some(WakerInterest::Detach) => return true,
+
+                // A worker is being scaled down so stop sending it connections.
+                Some(WakerInterest::RemoveWorker(idx, tx)) => {
+                    drop(guard);
+
+                    self.avail.set_available(idx, false);
+                    self.stalled.retain(|stalled| *stalled != idx);
+
+                    if let Some(pos) = self.handles.iter().position(|handle| handle.idx() == idx) {
+                        let _ = tx.send(self.handles.remove(pos));
+                    }
+                }
+
+                // A worker missed its heartbeats so stop sending it connections.
+                Some(WakerInterest::WorkerStalled(idx)) => {
+                    drop(guard);
+
+                    if !self.stalled.contains(&idx) {
+                        self.stalled.push(idx);
+                    }
+
+                    self.avail.set_available(idx, false);
+                }
+
+                // A stalled worker is beating again.
+                Some(WakerInterest::WorkerRecovered(idx)) => {
+                    drop(guard);
+
+                    self.stalled.retain(|stalled| *stalled != idx);
+
+                    // a full worker notifies once it is available again
+                    let available = self
+                        .handles
+                        .iter()
+                        .find(|handle| handle.idx() == idx)
+                        .map_or(false, |handle| !handle.is_full());
+
+                    if available {
+                        self.avail.set_available(idx, true);
+
+                        if !self.paused {
+                            self.accept_all(sockets);
+                        }
+                    }
+                }
+
+                // Listeners were added to the running server.
+                Some(WakerInterest::Bind(new_sockets)) => {
+                    drop(guard);
+
+                    for mut info in new_sockets {
+                        if !self.paused {
+                            self.register_logged(&mut info);
+                        }
+
+                        sockets.push(info);
+                    }
+                }
+
+                // Listeners were removed from the running server.
+                Some(WakerInterest::Unbind(tokens, tx)) => {
+                    drop(guard);
+
+                    sockets.retain_mut(|info| {
+                        if !tokens.contains(&info.token) {
+                            return true;
+                        }
+
+                        // sockets with a timeout or paused are already deregistered
+                        if !self.paused && info.timeout.is_none() {
+                            self.deregister_logged(info);
+                        }
+
+                        false
+                    });
+
+                    let _ = tx.send(());
+                }
+
                 // waker queue is drained
                 None => {
                     // Reset the WakerQueue before break so it does not grow infinitely
@@ -332,71 +463,73 @@ impl Accept {
             .for_each(|(_, info)| self.deregister_logged(info));
     }
 
-    // Send connection to worker and handle error.
-    fn send_connection(&mut self, conn: Conn) -> Result<(), Conn> {
-        let next = self.next();
-        match next.send(conn) {
+    // Send connection to worker at `pos` and handle error.
+    fn send_connection(&mut self, pos: usize, conn: Conn) -> Result<(), Conn> {
+        let handle = &self.handles[pos];
+        match handle.send(conn) {
             Ok(_) => {
                 // Increment counter of WorkerHandle.
                 // Set worker to unavailable with it hit max (Return false).
-                if !next.inc_counter() {
-                    let idx = next.idx();
+                if !handle.inc_counter() {
+                    let idx = handle.idx();
                     self.avail.set_available(idx, false);
                 }
-                self.set_next();
                 Ok(())
             }
             Err(conn) => {
                 // Worker thread is error and could be gone.
                 // Remove worker handle and notify `ServerBuilder`.
-                self.remove_next();
-
-                if self.handles.is_empty() {
-                    error!("no workers");
-                    // All workers are gone and Conn is nowhere to be sent.
-                    // Treat this situation as Ok and drop Conn.
-                    return Ok(());
-                } else if self.handles.len() <= self.next {
-                    self.next = 0;
-                }
+                self.remove_worker(pos);
 
                 Err(conn)
             }
         }
     }
 
-    fn accept_one(&mut self, mut conn: Conn) {
+    /// Send connection to a worker. Returns false if it had to be dropped instead.
+    fn accept_one(&mut self, mut conn: Conn) -> bool {
         loop {
-            let next = self.next();
-            let idx = next.idx();
+            let pos = self.select();
 
-            if self.avail.get_available(idx) {
-                match self.send_connection(conn) {
-                    Ok(_) => return,
-                    Err(c) => conn = c,
-                }
-            } else {
-                self.avail.set_available(idx, false);
-                self.set_next();
-
-                if !self.avail.available() {
-                    while let Err(c) = self.send_connection(conn) {
-                        conn = c;
-                    }
-                    return;
+            match self.send_connection(pos, conn) {
+                Ok(_) => return true,
+                Err(_) if self.handles.is_empty() => {
+                    // All workers are gone and Conn is nowhere to be sent.
+                    error!("no workers");
+                    return false;
                 }
+                Err(c) => conn = c,
             }
         }
     }
 
-    fn accept(&mut self, sockets: &mut [ServerSocketInfo], token: usize) {
+    fn accept(&mut self, sockets: &mut [ServerSocketInfo], pos: usize) {
         while self.avail.available() {
-            let info = &mut sockets[token];
+            let info = &mut sockets[pos];
 
             match info.lst.accept() {
                 Ok(io) => {
-                    let conn = Conn { io, token };
-                    self.accept_one(conn);
+                    let permit = match io.peer_ip().map(|ip| info.access.check(ip)) {
+                        Some(Ok(permit)) => permit,
+                        Some(Err(())) => {
+                            info.counters.rejected();
+                            continue;
+                        }
+                        None => None,
+                    };
+
+                    let conn = Conn {
+                        io,
+                        token: info.token,
+                        permit,
+                        accepted_at: std::time::Instant::now(),
+                    };
+
+                    if self.accept_one(conn) {
+                        info.counters.accepted();
+                    } else {
+                        info.counters.rejected();
+                    }
                 }
                 Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return,
                 Err(ref err) if connection_error(err) => continue,
@@ -419,28 +552,25 @@ impl Accept {
     }
 
     fn accept_all(&mut self, sockets: &mut [ServerSocketInfo]) {
-        sockets
-            .iter_mut()
-            .map(|info| info.token)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .for_each(|idx| self.accept(sockets, idx))
-    }
-
-    #[inline(always)]
-    fn next(&self) -> &WorkerHandleAccept {
-        &self.handles[self.next]
+        (0..sockets.len()).for_each(|pos| self.accept(sockets, pos))
     }
 
-    /// Set next worker handle that would accept connection.
-    #[inline(always)]
-    fn set_next(&mut self) {
-        self.next = (self.next + 1) % self.handles.len();
+    /// Pick the position of the worker handle that would accept next connection.
+    fn select(&mut self) -> usize {
+        let workers = Workers::new(&self.handles, &self.avail);
+        let len = workers.len();
+        let pos = self.selector.select(&workers) % len;
+
+        // fall back to next available worker if selected one is not
+        (0..len)
+            .map(|offset| (pos + offset) % len)
+            .find(|pos| workers.is_available(*pos))
+            .unwrap_or(pos)
     }
 
-    /// Remove next worker handle that fail to accept connection.
-    fn remove_next(&mut self) {
-        let handle = self.handles.swap_remove(self.next);
+    /// Remove worker handle that fail to accept connection.
+    fn remove_worker(&mut self, pos: usize) {
+        let handle = self.handles.swap_remove(pos);
         let idx = handle.idx();
         // A message is sent to `ServerBuilder` future to notify it a new worker
         // should be made.
@@ -455,7 +585,7 @@ impl Accept {
 /// All other errors will incur a timeout before next `accept()` call is attempted. The timeout is
 /// useful to handle resource exhaustion errors like `ENFILE` and `EMFILE`. Otherwise, it could
 /// enter into a temporary spin loop.
-fn connection_error(e: &io::Error) -> bool {
+pub(crate) fn connection_error(e: &io::Error) -> bool {
     e.kind() == io::ErrorKind::ConnectionRefused
         || e.kind() == io::ErrorKind::ConnectionAborted
         || e.kind() == io::ErrorKind::ConnectionReset
@@ -0,0 +1,235 @@
+//! IP access control applied when connections are accepted.
+
+use std::{
+    io,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+};
+
+use tracing::debug;
+
+use crate::limits::{IpLimiter, IpPermit};
+
+/// Network written in CIDR notation, e.g. `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(cidr: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid CIDR network: {cidr:?}"),
+            )
+        };
+
+        let (addr, prefix) = match cidr.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (cidr, None),
+        };
+
+        let addr = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max,
+        };
+
+        if prefix > max {
+            return Err(invalid());
+        }
+
+        Ok(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(ip) if self.addr.is_ipv4() => match ip.to_ipv4_mapped() {
+                Some(ip) => IpAddr::V4(ip),
+                None => return false,
+            },
+            ip => ip,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// IP allow and deny lists of a listener.
+///
+/// A peer is rejected if its address is in a denied network, or if any network is allowed and its
+/// address is in none of them. Without any network, all peers are accepted.
+///
+/// Set with [`ServerBuilder::ip_filter()`](crate::ServerBuilder::ip_filter()) and replaced at
+/// runtime with [`ServerHandle::set_ip_filter()`](crate::ServerHandle::set_ip_filter()).
+///
+/// # Examples
+/// ```
+/// use actix_server::IpFilter;
+///
+/// # fn filter() -> std::io::Result<IpFilter> {
+/// // accept internal networks only, except for one subnet
+/// let filter = IpFilter::new()
+///     .allow("10.0.0.0/8")?
+///     .allow("fd00::/8")?
+///     .deny("10.66.0.0/16")?;
+/// # Ok(filter)
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<Cidr>,
+    deny: Vec<Cidr>,
+}
+
+impl IpFilter {
+    /// Creates a filter accepting all peers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows peers in network `cidr`, given in CIDR notation or as a single address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if `cidr` is not a valid network.
+    pub fn allow(mut self, cidr: &str) -> io::Result<Self> {
+        self.allow.push(Cidr::parse(cidr)?);
+        Ok(self)
+    }
+
+    /// Denies peers in network `cidr`, given in CIDR notation or as a single address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if `cidr` is not a valid network.
+    pub fn deny(mut self, cidr: &str) -> io::Result<Self> {
+        self.deny.push(Cidr::parse(cidr)?);
+        Ok(self)
+    }
+
+    /// Returns true if a peer with address `ip` is accepted.
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        !self.deny.iter().any(|net| net.contains(ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip)))
+    }
+}
+
+/// Access control shared by the listeners with the same name.
+#[derive(Debug)]
+pub(crate) struct ListenerAccess {
+    name: String,
+    filter: RwLock<IpFilter>,
+    limiter: Option<Arc<IpLimiter>>,
+}
+
+impl ListenerAccess {
+    pub(crate) fn new(name: String, filter: IpFilter, limiter: Option<Arc<IpLimiter>>) -> Self {
+        Self {
+            name,
+            filter: RwLock::new(filter),
+            limiter,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn set_filter(&self, filter: IpFilter) {
+        *self.filter.write().unwrap() = filter;
+    }
+
+    /// Checks whether a connection from `ip` is accepted, logging why it is not.
+    ///
+    /// An accepted connection may need to hold a permit until it is closed.
+    pub(crate) fn check(&self, ip: IpAddr) -> Result<Option<IpPermit>, ()> {
+        if !self.filter.read().unwrap().is_allowed(ip) {
+            debug!(r#"connection from {} to "{}" is denied"#, ip, self.name);
+            return Err(());
+        }
+
+        match self.limiter {
+            Some(ref limiter) => match limiter.check(ip) {
+                Some(permit) => Ok(Some(permit)),
+                None => {
+                    debug!(
+                        r#"connection from {} to "{}" is over IP limits"#,
+                        ip, self.name
+                    );
+                    Err(())
+                }
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_cidr() {
+        let net = Cidr::parse("10.1.0.0/16").unwrap();
+        assert!(net.contains(ip("10.1.255.1")));
+        assert!(net.contains(ip("::ffff:10.1.0.1")));
+        assert!(!net.contains(ip("10.2.0.1")));
+        assert!(!net.contains(ip("::1")));
+
+        let net = Cidr::parse("2001:db8::/32").unwrap();
+        assert!(net.contains(ip("2001:db8:ffff::1")));
+        assert!(!net.contains(ip("2001:db9::1")));
+
+        assert!(Cidr::parse("127.0.0.1").unwrap().contains(ip("127.0.0.1")));
+        assert!(!Cidr::parse("127.0.0.1").unwrap().contains(ip("127.0.0.2")));
+        assert!(Cidr::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+
+        for invalid in [
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0/8",
+            "10.0.0.0/",
+            "localhost",
+        ] {
+            let err = Cidr::parse(invalid).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn allow_and_deny() {
+        assert!(IpFilter::new().is_allowed(ip("1.2.3.4")));
+
+        let filter = IpFilter::new()
+            .allow("10.0.0.0/8")
+            .unwrap()
+            .deny("10.66.0.0/16")
+            .unwrap();
+        assert!(filter.is_allowed(ip("10.1.2.3")));
+        assert!(!filter.is_allowed(ip("10.66.2.3")));
+        assert!(!filter.is_allowed(ip("192.168.1.1")));
+
+        let filter = IpFilter::new().deny("192.168.0.0/16").unwrap();
+        assert!(filter.is_allowed(ip("10.1.2.3")));
+        assert!(!filter.is_allowed(ip("192.168.1.1")));
+    }
+}
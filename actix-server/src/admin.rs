@@ -0,0 +1,384 @@
+//! Administrative control socket.
+//!
+//! Operators connect to a Unix socket and send one command per line; every command is answered
+//! with a single line. Supported commands are:
+//! - `pause` and `resume`: pause or resume accepting connections, answered with `ok`;
+//! - `stop` and `stop force`: begin a graceful or forced shutdown, answered with `ok`;
+//! - `stats`: answered with a snapshot of the server statistics as a JSON object.
+//!
+//! Unknown commands are answered with `error` followed by a description.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead as _, BufReader, Write as _},
+    os::unix::{
+        fs::{DirBuilderExt as _, MetadataExt as _, PermissionsExt as _},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process, thread,
+    time::Duration,
+};
+
+use mio::{Events, Interest, Poll, Token, Waker};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use tracing::{error, info, warn};
+
+use crate::{server::ServerCommand, ServerStats};
+
+/// Permissions of the socket file; only the owner of the server process may connect.
+const SOCKET_MODE: u32 = 0o600;
+
+/// Permissions of the directory the socket is bound in before it is moved to its path.
+const BIND_DIR_MODE: u32 = 0o700;
+
+/// Time a client may take to send a command before it is disconnected.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+const LISTENER: Token = Token(0);
+const WAKER: Token = Token(1);
+
+/// Admin socket bound by the server builder.
+#[derive(Debug)]
+pub(crate) struct AdminListener {
+    path: PathBuf,
+    lst: UnixListener,
+    file_id: FileId,
+}
+
+impl AdminListener {
+    /// Binds admin socket at `path`, atomically replacing an existing socket file.
+    ///
+    /// The socket is bound in a private directory next to `path`, so that it is never reachable
+    /// with wider permissions, and then renamed to `path`.
+    pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "admin socket path has no file name",
+            )
+        })?;
+
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut dir_name = file_name.to_owned();
+        dir_name.push(format!(".{}.tmp", process::id()));
+        let dir = parent.join(dir_name);
+        let tmp_path = dir.join(file_name);
+
+        // a directory left behind by a crashed process with the same PID
+        if dir.exists() {
+            let _ = fs::remove_file(&tmp_path);
+            fs::remove_dir(&dir)?;
+        }
+
+        fs::DirBuilder::new().mode(BIND_DIR_MODE).create(&dir)?;
+
+        let res = bind_and_move(&tmp_path, path);
+
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        let _ = fs::remove_dir(&dir);
+
+        let (lst, file_id) = res?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            lst,
+            file_id,
+        })
+    }
+
+    /// Starts serving commands from a dedicated thread.
+    pub(crate) fn start(self, cmd_tx: UnboundedSender<ServerCommand>) -> io::Result<AdminSocket> {
+        let poll = Poll::new()?;
+        let waker = Waker::new(poll.registry(), WAKER)?;
+
+        self.lst.set_nonblocking(true)?;
+        let mut mio_lst = mio::net::UnixListener::from_std(self.lst.try_clone()?);
+        poll.registry()
+            .register(&mut mio_lst, LISTENER, Interest::READABLE)?;
+
+        info!("serving admin commands on {}", self.path.display());
+
+        thread::Builder::new()
+            .name("actix-server admin".to_owned())
+            .spawn(move || {
+                // keeps the registration alive while serving
+                let _mio_lst = mio_lst;
+                serve(self.lst, poll, cmd_tx)
+            })?;
+
+        Ok(AdminSocket {
+            path: self.path,
+            file_id: self.file_id,
+            waker,
+        })
+    }
+}
+
+/// Binds a socket at `tmp_path` and renames it to `path`.
+fn bind_and_move(tmp_path: &Path, path: &Path) -> io::Result<(UnixListener, FileId)> {
+    let lst = UnixListener::bind(tmp_path)?;
+    fs::set_permissions(tmp_path, fs::Permissions::from_mode(SOCKET_MODE))?;
+    let file_id = FileId::of(tmp_path)?;
+    fs::rename(tmp_path, path)?;
+    Ok((lst, file_id))
+}
+
+/// Running admin socket; closed when dropped.
+#[derive(Debug)]
+pub(crate) struct AdminSocket {
+    path: PathBuf,
+    file_id: FileId,
+    waker: Waker,
+}
+
+impl Drop for AdminSocket {
+    fn drop(&mut self) {
+        // the admin thread exits on wake up, closing the socket
+        if let Err(err) = self.waker.wake() {
+            error!("can not stop admin thread: {}", err);
+        }
+
+        // the path may have been taken over by another server, e.g. the successor of a handoff
+        if FileId::of(&self.path).ok() == Some(self.file_id) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Device and inode numbers identifying a socket file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    dev: u64,
+    ino: u64,
+}
+
+impl FileId {
+    fn of(path: &Path) -> io::Result<Self> {
+        let meta = fs::symlink_metadata(path)?;
+
+        Ok(Self {
+            dev: meta.dev(),
+            ino: meta.ino(),
+        })
+    }
+}
+
+fn serve(lst: UnixListener, mut poll: Poll, cmd_tx: UnboundedSender<ServerCommand>) {
+    let mut events = Events::with_capacity(8);
+    let mut timeout = None;
+
+    loop {
+        if let Err(err) = poll.poll(&mut events, timeout) {
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+
+            error!("error polling admin socket: {}", err);
+            return;
+        }
+
+        if events.iter().any(|event| event.token() == WAKER) {
+            return;
+        }
+
+        timeout = None;
+
+        loop {
+            match lst.accept() {
+                Ok((conn, _)) => {
+                    // a slow client must not hold up others
+                    let cmd_tx = cmd_tx.clone();
+                    let res = thread::Builder::new()
+                        .name("actix-server admin connection".to_owned())
+                        .spawn(move || {
+                            if let Err(err) = handle_conn(conn, &cmd_tx) {
+                                warn!("admin connection failed: {}", err);
+                            }
+                        });
+
+                    if let Err(err) = res {
+                        error!("can not spawn admin connection thread: {}", err);
+                    }
+                }
+
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+
+                Err(err) => {
+                    error!("error accepting admin connection: {}", err);
+                    // avoid spinning on errors such as running out of FDs, retrying once
+                    // the poll times out
+                    timeout = Some(Duration::from_millis(100));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn handle_conn(conn: UnixStream, cmd_tx: &UnboundedSender<ServerCommand>) -> io::Result<()> {
+    // accepted sockets may inherit non-blocking mode from the listener
+    conn.set_nonblocking(false)?;
+    conn.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut writer = &conn;
+
+    for line in BufReader::new(&conn).lines() {
+        let mut res = execute(line?.trim(), cmd_tx);
+        res.push('\n');
+        writer.write_all(res.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Runs a single command and returns its answer.
+fn execute(cmd: &str, cmd_tx: &UnboundedSender<ServerCommand>) -> String {
+    let res = match cmd.split_whitespace().collect::<Vec<_>>()[..] {
+        ["pause"] => request(cmd_tx, ServerCommand::Pause).map(|()| "ok".to_owned()),
+        ["resume"] => request(cmd_tx, ServerCommand::Resume).map(|()| "ok".to_owned()),
+        ["stats"] => request(cmd_tx, ServerCommand::Stats).map(|stats| stats_json(&stats)),
+
+        ["stop"] => stop(cmd_tx, true),
+        ["stop", "force"] => stop(cmd_tx, false),
+
+        [] => return "error empty command".to_owned(),
+        _ => {
+            return format!(
+                "error unknown command {:?}; expected pause, resume, stop, stop force or stats",
+                cmd
+            )
+        }
+    };
+
+    res.unwrap_or_else(|| "error server is not running".to_owned())
+}
+
+/// Sends a command to the server and waits for its answer.
+fn request<T>(
+    cmd_tx: &UnboundedSender<ServerCommand>,
+    cmd: impl FnOnce(oneshot::Sender<T>) -> ServerCommand,
+) -> Option<T> {
+    let (tx, rx) = oneshot::channel();
+    cmd_tx.send(cmd(tx)).ok()?;
+    rx.blocking_recv().ok()
+}
+
+/// Begins shutdown without waiting for it, as the server closes the admin socket when stopping.
+fn stop(cmd_tx: &UnboundedSender<ServerCommand>, graceful: bool) -> Option<String> {
+    cmd_tx
+        .send(ServerCommand::Stop {
+            graceful,
+            completion: None,
+            force_system_stop: false,
+        })
+        .ok()
+        .map(|()| "ok".to_owned())
+}
+
+/// Formats statistics as a single-line JSON object.
+fn stats_json(stats: &ServerStats) -> String {
+    let mut json = format!(r#"{{"paused":{},"workers":["#, stats.paused);
+
+    for (i, wrk) in stats.workers.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(
+            json,
+            r#"{}{{"idx":{},"connections":{},"restarts":{},"stalled":{}}}"#,
+            sep, wrk.idx, wrk.connections, wrk.restarts, wrk.stalled
+        );
+    }
+
+    json.push_str(r#"],"listeners":["#);
+
+    for (i, lst) in stats.listeners.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(json, r#"{}{{"token":{},"name":"#, sep, lst.token);
+        json_string(&mut json, &lst.name);
+        let _ = write!(
+            json,
+            r#","accepted":{},"rejected":{}}}"#,
+            lst.accepted, lst.rejected
+        );
+    }
+
+    json.push_str("]}");
+    json
+}
+
+/// Appends `val` to `json` as a quoted and escaped JSON string.
+fn json_string(json: &mut String, val: &str) {
+    json.push('"');
+
+    for ch in val.chars() {
+        match ch {
+            '"' => json.push_str(r#"\""#),
+            '\\' => json.push_str(r"\\"),
+            ch if ch.is_control() => {
+                let _ = write!(json, r"\u{:04x}", ch as u32);
+            }
+            ch => json.push(ch),
+        }
+    }
+
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ListenerStats, WorkerStats};
+
+    #[test]
+    fn stats_as_json() {
+        let stats = ServerStats {
+            paused: true,
+            workers: vec![
+                WorkerStats {
+                    idx: 0,
+                    connections: 2,
+                    restarts: 0,
+                    stalled: false,
+                },
+                WorkerStats {
+                    idx: 1,
+                    connections: 0,
+                    restarts: 3,
+                    stalled: true,
+                },
+            ],
+            listeners: vec![ListenerStats {
+                token: 0,
+                name: "say \"hi\"\n".to_owned(),
+                accepted: 10,
+                rejected: 1,
+            }],
+        };
+
+        assert_eq!(
+            stats_json(&stats),
+            concat!(
+                r#"{"paused":true,"workers":[{"idx":0,"connections":2,"restarts":0,"#,
+                r#""stalled":false},{"idx":1,"connections":0,"restarts":3,"stalled":true}],"#,
+                r#""listeners":[{"token":0,"#,
+                r#""name":"say \"hi\"\u000a","accepted":10,"rejected":1}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_command() {
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        assert!(execute("restart", &cmd_tx).starts_with("error unknown command"));
+        assert_eq!(execute("", &cmd_tx), "error empty command");
+        assert_eq!(execute("stop", &cmd_tx), "ok");
+    }
+}
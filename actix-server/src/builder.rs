@@ -1,13 +1,28 @@
-use std::{io, num::NonZeroUsize, time::Duration};
+use std::{future::Future, io, num::NonZeroUsize, sync::Arc, time::Duration};
 
 use actix_rt::net::TcpStream;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{
+    broadcast,
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
 
 use crate::{
+    access::IpFilter,
+    connection::Connection,
+    limits::IpLimits,
+    outcome::{ConnectionOutcome, OutcomeHook},
+    restart::{RestartPolicy, WorkerEvent},
+    selector::{RoundRobin, WorkerSelector},
     server::ServerCommand,
     service::{InternalServiceFactory, ServerServiceFactory, StreamNewService},
-    socket::{create_mio_tcp_listener, MioListener, MioTcpListener, StdTcpListener, ToSocketAddrs},
-    worker::ServerWorkerConfig,
+    signals::{Signal, SignalAction},
+    socket::{
+        create_mio_tcp_listener, FromStream, Listener, MioListener, MioTcpListener,
+        ReusePortListener, StdTcpListener, ToSocketAddrs,
+    },
+    socket_options::SocketOptions,
+    udp::{bind_udp_addr, InternalUdpServiceFactory, UdpNewService, UdpServiceFactory},
+    worker::{ServerWorkerConfig, WorkerHooks},
     Server,
 };
 
@@ -29,6 +44,9 @@ pub enum MpTcp {
     NoFallback,
 }
 
+/// Number of worker events kept for receivers that lag behind.
+const WORKER_EVENTS_CAPACITY: usize = 64;
+
 /// [Server] builder.
 pub struct ServerBuilder {
     pub(crate) threads: usize,
@@ -36,12 +54,30 @@ pub struct ServerBuilder {
     pub(crate) backlog: u32,
     pub(crate) factories: Vec<Box<dyn InternalServiceFactory>>,
     pub(crate) sockets: Vec<(usize, String, MioListener)>,
+    pub(crate) udp_factories: Vec<Box<dyn InternalUdpServiceFactory>>,
     pub(crate) mptcp: MpTcp,
+    pub(crate) reuse_port: bool,
+    pub(crate) reuse_port_listeners: Vec<(usize, ReusePortListener)>,
     pub(crate) exit: bool,
     pub(crate) listen_os_signals: bool,
+    pub(crate) signal_actions: Vec<(Signal, SignalAction)>,
     pub(crate) cmd_tx: UnboundedSender<ServerCommand>,
     pub(crate) cmd_rx: UnboundedReceiver<ServerCommand>,
+    pub(crate) worker_events: broadcast::Sender<WorkerEvent>,
     pub(crate) worker_config: ServerWorkerConfig,
+    pub(crate) worker_hooks: WorkerHooks,
+    pub(crate) selector: Box<dyn WorkerSelector>,
+    pub(crate) ip_limits: Vec<(String, IpLimits)>,
+    pub(crate) ip_filters: Vec<(String, IpFilter)>,
+    pub(crate) outcome_hook: Option<OutcomeHook>,
+    pub(crate) stall_timeout: Option<Duration>,
+    pub(crate) replace_stalled: bool,
+    #[cfg(unix)]
+    pub(crate) handoff: crate::handoff::HandoffConfig,
+    #[cfg(unix)]
+    pub(crate) systemd_notify: bool,
+    #[cfg(unix)]
+    pub(crate) admin: Option<crate::admin::AdminListener>,
 }
 
 impl Default for ServerBuilder {
@@ -54,19 +90,46 @@ impl ServerBuilder {
     /// Create new Server builder instance
     pub fn new() -> ServerBuilder {
         let (cmd_tx, cmd_rx) = unbounded_channel();
+        let (worker_events, _) = broadcast::channel(WORKER_EVENTS_CAPACITY);
+
+        // handoff and activation variables are removed from the environment if set, which must
+        // happen before other threads of such a process read it
+        #[cfg(unix)]
+        {
+            crate::handoff::take_env();
+            crate::systemd::take_env();
+        }
 
         ServerBuilder {
             threads: std::thread::available_parallelism().map_or(2, NonZeroUsize::get),
             token: 0,
             factories: Vec::new(),
             sockets: Vec::new(),
+            udp_factories: Vec::new(),
             backlog: 2048,
             mptcp: MpTcp::Disabled,
+            reuse_port: false,
+            reuse_port_listeners: Vec::new(),
             exit: false,
             listen_os_signals: true,
+            signal_actions: crate::signals::default_actions(),
             cmd_tx,
             cmd_rx,
+            worker_events,
             worker_config: ServerWorkerConfig::default(),
+            worker_hooks: WorkerHooks::default(),
+            selector: Box::new(RoundRobin::default()),
+            ip_limits: Vec::new(),
+            ip_filters: Vec::new(),
+            outcome_hook: None,
+            stall_timeout: None,
+            replace_stalled: false,
+            #[cfg(unix)]
+            handoff: crate::handoff::HandoffConfig::default(),
+            #[cfg(unix)]
+            systemd_notify: false,
+            #[cfg(unix)]
+            admin: None,
         }
     }
 
@@ -150,6 +213,151 @@ impl ServerBuilder {
         self
     }
 
+    /// Sets the strategy used to pick the worker that receives each accepted connection.
+    ///
+    /// Built-in strategies are [`RoundRobin`](crate::RoundRobin),
+    /// [`LeastConnections`](crate::LeastConnections) and
+    /// [`PowerOfTwoChoices`](crate::PowerOfTwoChoices). Workers that reached their
+    /// [connection limit](Self::max_concurrent_connections()) are skipped by all of them.
+    ///
+    /// By default connections are handed to workers in round-robin order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_server::{LeastConnections, ServerBuilder};
+    /// let builder = ServerBuilder::new().worker_selector(LeastConnections::default());
+    /// ```
+    pub fn worker_selector<S: WorkerSelector>(mut self, selector: S) -> Self {
+        self.selector = Box::new(selector);
+        self
+    }
+
+    /// Sets per-IP connection limits of the listeners named `name`.
+    ///
+    /// Limits apply to the listeners with that name whether they are bound before or after this
+    /// call, including ones bound at runtime with [`ServerHandle::bind()`]. Connection counts and
+    /// rates are shared by all these listeners. Unix domain socket listeners are not limited.
+    ///
+    /// Connections over a limit are closed right after they are accepted, without reaching any
+    /// worker, and are counted as rejected in [listener statistics].
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_server::{IpLimits, ServerBuilder};
+    /// let builder = ServerBuilder::new().ip_limits("api", IpLimits::new().max_connections(64));
+    /// ```
+    ///
+    /// [`ServerHandle::bind()`]: crate::ServerHandle::bind()
+    /// [listener statistics]: crate::ListenerStats
+    pub fn ip_limits(mut self, name: impl AsRef<str>, limits: IpLimits) -> Self {
+        let name = name.as_ref();
+        self.ip_limits.retain(|(lst_name, _)| lst_name != name);
+        self.ip_limits.push((name.to_owned(), limits));
+        self
+    }
+
+    /// Sets IP allow and deny lists of the listeners named `name`.
+    ///
+    /// Like [IP limits](Self::ip_limits()), the filter applies to the listeners with that name
+    /// whether they are bound before or after this call, including ones bound at runtime. Unix
+    /// domain socket listeners are not filtered. The filter can be replaced while the server is
+    /// running with [`ServerHandle::set_ip_filter()`].
+    ///
+    /// Denied connections are closed right after they are accepted, without reaching any worker,
+    /// and are counted as rejected in [listener statistics].
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_server::{IpFilter, ServerBuilder};
+    /// # fn build() -> std::io::Result<ServerBuilder> {
+    /// let builder = ServerBuilder::new().ip_filter("admin", IpFilter::new().allow("10.0.0.0/8")?);
+    /// # Ok(builder)
+    /// # }
+    /// ```
+    ///
+    /// [`ServerHandle::set_ip_filter()`]: crate::ServerHandle::set_ip_filter()
+    /// [listener statistics]: crate::ListenerStats
+    pub fn ip_filter(mut self, name: impl AsRef<str>, filter: IpFilter) -> Self {
+        let name = name.as_ref();
+        self.ip_filters.retain(|(lst_name, _)| lst_name != name);
+        self.ip_filters.push((name.to_owned(), filter));
+        self
+    }
+
+    /// Sets hook called with the listener name and outcome of every connection handled by a
+    /// stream service.
+    ///
+    /// The hook is called on the worker that handled the connection, once its service future
+    /// completes, fails or panics. Panics are caught when a hook is set; they no longer take down
+    /// the task of the connection silently. Applies to all listeners, including ones bound at
+    /// runtime.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// use actix_server::{ConnectionOutcome, ServerBuilder};
+    ///
+    /// static FAILED: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let builder = ServerBuilder::new().on_connection_outcome(|listener, outcome| {
+    ///     if let ConnectionOutcome::Error(err) = outcome {
+    ///         FAILED.fetch_add(1, Ordering::Relaxed);
+    ///         eprintln!("connection to {listener} failed: {err}");
+    ///     }
+    /// });
+    /// ```
+    pub fn on_connection_outcome<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &ConnectionOutcome<'_>) + Send + Sync + 'static,
+    {
+        self.outcome_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets async callback run on every worker before its services are created.
+    ///
+    /// The callback receives the index of the worker and runs on the worker's thread, so it can
+    /// set up worker-local state, e.g. thread-locals or connection pools, for services to use.
+    /// Runs for workers started after a restart or by scaling up, too.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cell::Cell;
+    ///
+    /// use actix_server::ServerBuilder;
+    ///
+    /// thread_local! {
+    ///     static WORKER_IDX: Cell<usize> = Cell::new(0);
+    /// }
+    ///
+    /// let builder = ServerBuilder::new()
+    ///     .on_worker_start(|idx| async move { WORKER_IDX.with(|cell| cell.set(idx)) })
+    ///     .on_worker_stop(|idx| async move { println!("worker {idx} stopped") });
+    /// ```
+    pub fn on_worker_start<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(usize) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.worker_hooks.start = Some(Arc::new(move |idx| Box::pin(f(idx))));
+        self
+    }
+
+    /// Sets async callback run on every worker once it has shut down.
+    ///
+    /// The callback receives the index of the worker and runs on the worker's thread after its
+    /// services have drained their connections and were dropped. It is not run for workers that
+    /// died, e.g. by a panic.
+    pub fn on_worker_stop<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(usize) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.worker_hooks.stop = Some(Arc::new(move |idx| Box::pin(f(idx))));
+        self
+    }
+
     #[doc(hidden)]
     #[deprecated(since = "2.0.0", note = "Renamed to `max_concurrent_connections`.")]
     pub fn maxconn(self, num: usize) -> Self {
@@ -170,6 +378,35 @@ impl ServerBuilder {
         self
     }
 
+    /// Sets action taken when the process receives `signal`.
+    ///
+    /// By default, `SIGTERM` starts a graceful shutdown and `SIGINT` and `SIGQUIT` start a forced
+    /// one; other signals are not listened to. Use [`SignalAction::Ignore`] to override a default
+    /// action. On Windows, only Ctrl-C ([`Signal::Int`]) is listened to.
+    ///
+    /// Has no effect if OS signal handling is [disabled](Self::disable_signals()).
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_server::{ServerBuilder, Signal, SignalAction};
+    ///
+    /// let builder = ServerBuilder::new()
+    ///     .signal(Signal::Int, SignalAction::GracefulStop)
+    ///     .signal(Signal::Hup, SignalAction::callback(|_| println!("reloading certificates")))
+    ///     .signal(
+    ///         Signal::Usr1,
+    ///         SignalAction::callback(|srv| {
+    ///             let srv = srv.clone();
+    ///             actix_rt::spawn(async move { println!("{:?}", srv.stats().await) });
+    ///         }),
+    ///     );
+    /// ```
+    pub fn signal(mut self, signal: Signal, action: SignalAction) -> Self {
+        self.signal_actions.retain(|(sig, _)| *sig != signal);
+        self.signal_actions.push((signal, action));
+        self
+    }
+
     /// Timeout for graceful workers shutdown in seconds.
     ///
     /// After receiving a stop signal, workers have this much time to finish serving requests.
@@ -182,6 +419,41 @@ impl ServerBuilder {
         self
     }
 
+    /// Sets policy for restarting workers that died and services that failed on a worker.
+    ///
+    /// By default, workers and services are restarted immediately, without limit. Restarts can
+    /// be observed with [`ServerHandle::worker_events()`](crate::ServerHandle::worker_events()).
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.worker_config.restart_policy(policy);
+        self
+    }
+
+    /// Detects workers whose event loop has been blocked for longer than `timeout`.
+    ///
+    /// Workers send heartbeats from their event loop, so a worker blocked by a long-running
+    /// synchronous task stops beating even though it looks healthy otherwise. A worker that has not
+    /// beaten for `timeout` is considered stalled: it is logged, reported as
+    /// [`WorkerEvent::Stalled`], and no new connections are sent to it until it beats again. See
+    /// [`replace_stalled_workers()`](Self::replace_stalled_workers()) for replacing it instead.
+    ///
+    /// Stall detection is disabled by default.
+    pub fn worker_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.worker_config.heartbeat_interval(timeout / 4);
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Replaces stalled workers with new ones, see
+    /// [`worker_stall_timeout()`](Self::worker_stall_timeout()).
+    ///
+    /// A stalled worker is treated as if it died and is restarted according to the
+    /// [restart policy](Self::restart_policy()). It is told to stop immediately, which only takes
+    /// effect if its event loop is unblocked; its connections are not moved to the new worker.
+    pub fn replace_stalled_workers(mut self) -> Self {
+        self.replace_stalled = true;
+        self
+    }
+
     /// Adds new service to the server.
     ///
     /// Note that, if a DNS lookup is required, resolving hostnames is a blocking operation.
@@ -205,24 +477,102 @@ impl ServerBuilder {
     /// Returns an `io::Error` if:
     /// - `addrs` cannot be resolved into one or more socket addresses;
     /// - all the resolved socket addresses are already bound.
-    pub fn bind<F, U, N>(mut self, name: N, addrs: U, factory: F) -> io::Result<Self>
+    pub fn bind<F, U, N>(self, name: N, addrs: U, factory: F) -> io::Result<Self>
     where
         F: ServerServiceFactory<TcpStream>,
         U: ToSocketAddrs,
         N: AsRef<str>,
     {
-        let sockets = bind_addr(addrs, self.backlog, &self.mptcp)?;
+        self.bind_with_options(name, addrs, SocketOptions::new(), factory)
+    }
+
+    /// Adds new service to the server, setting socket `options` on its listeners and on the
+    /// connections they accept.
+    ///
+    /// Otherwise behaves like [`bind()`](Self::bind()).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - `addrs` cannot be resolved into one or more socket addresses;
+    /// - all the resolved socket addresses are already bound, or `options` can not be set on any
+    ///   of their listeners.
+    pub fn bind_with_options<F, U, N>(
+        self,
+        name: N,
+        addrs: U,
+        options: SocketOptions,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<TcpStream>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        self.bind_stream(name, addrs, options, factory)
+    }
+
+    /// Adds new service to the server that receives each connection as a [`Connection`] carrying
+    /// its metadata.
+    ///
+    /// Otherwise behaves like [`bind()`](Self::bind()).
+    ///
+    /// [`Connection`]: crate::Connection
+    pub fn bind_connection<F, U, N>(self, name: N, addrs: U, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<Connection<TcpStream>>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        self.bind_stream(name, addrs, SocketOptions::new(), factory)
+    }
+
+    fn bind_stream<F, U, N, Io>(
+        mut self,
+        name: N,
+        addrs: U,
+        options: SocketOptions,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<Io>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+        Io: FromStream + 'static,
+    {
+        if self.reuse_port {
+            let listeners = reserve_addr(addrs, self.backlog, &self.mptcp, &options)?;
+
+            tracing::trace!("reserving server addresses: {listeners:?}");
+
+            for lst in listeners {
+                let token = self.next_token();
+
+                self.factories.push(StreamNewService::create_with_options(
+                    name.as_ref().to_string(),
+                    token,
+                    factory.clone(),
+                    options.connection().clone(),
+                ));
+
+                self.reuse_port_listeners.push((token, lst));
+            }
+
+            return Ok(self);
+        }
+
+        let sockets = bind_addr(addrs, self.backlog, &self.mptcp, &options)?;
 
         tracing::trace!("binding server to: {sockets:?}");
 
         for lst in sockets {
             let token = self.next_token();
 
-            self.factories.push(StreamNewService::create(
+            self.factories.push(StreamNewService::create_with_options(
                 name.as_ref().to_string(),
                 token,
                 factory.clone(),
-                lst.local_addr()?,
+                options.connection().clone(),
             ));
 
             self.sockets
@@ -232,6 +582,50 @@ impl ServerBuilder {
         Ok(self)
     }
 
+    /// Adds new datagram service to the server, bound to UDP sockets on `addrs`.
+    ///
+    /// Unlike stream services, which are called once per connection, a datagram service is called
+    /// once per worker with a [`UdpSocket`] and is expected to receive and answer datagrams until
+    /// the returned future completes. All workers receive from the same bound socket, so each
+    /// datagram is delivered to a single worker.
+    ///
+    /// Datagram services keep running while their worker shuts down gracefully and are dropped once
+    /// it stops, or right away on a forced shutdown. Pausing the server does not affect them.
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is number of [`workers`](Self::workers()) × number of sockets resolved by
+    /// `addrs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - `addrs` cannot be resolved into one or more socket addresses;
+    /// - all the resolved socket addresses are already bound.
+    ///
+    /// [`UdpSocket`]: actix_rt::net::UdpSocket
+    pub fn bind_udp<F, U, N>(mut self, name: N, addrs: U, factory: F) -> io::Result<Self>
+    where
+        F: UdpServiceFactory,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        let sockets = bind_udp_addr(addrs)?;
+
+        tracing::trace!("binding UDP server to: {sockets:?}");
+
+        for socket in sockets {
+            self.udp_factories.push(UdpNewService::create(
+                name.as_ref().to_string(),
+                factory.clone(),
+                socket,
+            )?);
+        }
+
+        Ok(self)
+    }
+
     /// Adds service to the server using a socket listener already bound.
     ///
     /// # Worker Count
@@ -248,14 +642,12 @@ impl ServerBuilder {
         F: ServerServiceFactory<TcpStream>,
     {
         lst.set_nonblocking(true)?;
-        let addr = lst.local_addr()?;
 
         let token = self.next_token();
         self.factories.push(StreamNewService::create(
             name.as_ref().to_string(),
             token,
             factory,
-            addr,
         ));
 
         self.sockets
@@ -264,9 +656,41 @@ impl ServerBuilder {
         Ok(self)
     }
 
+    /// Adds service to the server using a listener of a custom socket type.
+    ///
+    /// Connections accepted by `lst` are distributed to workers like those of any other listener,
+    /// and the service receives them as [`Listener::Stream`]. Socket options are not applied to
+    /// them, and custom listeners can not be handed off to a new process.
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is: number of [`workers`](Self::workers()).
+    pub fn listen_custom<L, F, N>(mut self, name: N, lst: L, factory: F) -> Self
+    where
+        L: Listener,
+        F: ServerServiceFactory<L::Stream>,
+        N: AsRef<str>,
+    {
+        let token = self.next_token();
+        self.factories.push(StreamNewService::create(
+            name.as_ref().to_string(),
+            token,
+            factory,
+        ));
+
+        self.sockets
+            .push((token, name.as_ref().to_string(), MioListener::from(lst)));
+
+        self
+    }
+
     /// Starts processing incoming connections and return server controller.
     pub fn run(self) -> Server {
-        if self.sockets.is_empty() {
+        if self.sockets.is_empty()
+            && self.reuse_port_listeners.is_empty()
+            && self.udp_factories.is_empty()
+        {
             panic!("Server should have at least one bound socket");
         } else {
             tracing::info!("starting {} workers", self.threads);
@@ -294,6 +718,31 @@ impl ServerBuilder {
         F: ServerServiceFactory<actix_rt::net::UnixStream>,
         N: AsRef<str>,
         U: AsRef<std::path::Path>,
+    {
+        self.bind_uds_stream(name, addr, factory)
+    }
+
+    /// Adds new service to the server using a UDS address that receives each connection as a
+    /// [`Connection`] carrying its metadata, including the credentials of the peer process.
+    ///
+    /// Otherwise behaves like [`bind_uds()`](Self::bind_uds()).
+    ///
+    /// [`Connection`]: crate::Connection
+    pub fn bind_uds_connection<F, U, N>(self, name: N, addr: U, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<Connection<actix_rt::net::UnixStream>>,
+        N: AsRef<str>,
+        U: AsRef<std::path::Path>,
+    {
+        self.bind_uds_stream(name, addr, factory)
+    }
+
+    fn bind_uds_stream<F, U, N, Io>(self, name: N, addr: U, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<Io>,
+        N: AsRef<str>,
+        U: AsRef<std::path::Path>,
+        Io: FromStream + 'static,
     {
         // The path must not exist when we try to bind.
         // Try to remove it to avoid bind error.
@@ -305,7 +754,7 @@ impl ServerBuilder {
         }
 
         let lst = crate::socket::StdUnixListener::bind(addr)?;
-        self.listen_uds(name, lst, factory)
+        self.listen_uds_stream(name, lst, factory)
     }
 
     /// Adds new service to the server using a UDS (unix domain socket) listener already bound.
@@ -317,7 +766,7 @@ impl ServerBuilder {
     /// The `factory` will be instantiated multiple times in most scenarios. The number of
     /// instantiations is: number of [`workers`](Self::workers()).
     pub fn listen_uds<F, N: AsRef<str>>(
-        mut self,
+        self,
         name: N,
         lst: crate::socket::StdUnixListener,
         factory: F,
@@ -325,18 +774,27 @@ impl ServerBuilder {
     where
         F: ServerServiceFactory<actix_rt::net::UnixStream>,
     {
-        use std::net::{IpAddr, Ipv4Addr};
+        self.listen_uds_stream(name, lst, factory)
+    }
 
+    fn listen_uds_stream<F, N, Io>(
+        mut self,
+        name: N,
+        lst: crate::socket::StdUnixListener,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<Io>,
+        N: AsRef<str>,
+        Io: FromStream + 'static,
+    {
         lst.set_nonblocking(true)?;
 
         let token = self.next_token();
-        let addr = crate::socket::StdSocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
-
         self.factories.push(StreamNewService::create(
             name.as_ref().to_string(),
             token,
             factory,
-            addr,
         ));
 
         self.sockets
@@ -344,19 +802,352 @@ impl ServerBuilder {
 
         Ok(self)
     }
+
+    /// Enables listener handoff when the process receives `SIGUSR2`.
+    ///
+    /// See [`ServerHandle::handoff()`](crate::ServerHandle::handoff()) for how the handoff works.
+    /// Has no effect if OS signal handling is [disabled](Self::disable_signals()).
+    pub fn handoff_signal(self) -> Self {
+        self.signal(Signal::Usr2, SignalAction::Handoff)
+    }
+
+    /// Sets the command used to spawn the new process during a listener handoff.
+    ///
+    /// By default, the current executable is run again with the same arguments.
+    pub fn handoff_command<F>(mut self, command: F) -> Self
+    where
+        F: Fn() -> std::process::Command + Send + Sync + 'static,
+    {
+        self.handoff.command = std::sync::Arc::new(command);
+        self
+    }
+
+    /// Timeout for the new process to become ready during a listener handoff, in seconds.
+    ///
+    /// If the new process does not report readiness in time, it is killed and the handoff fails.
+    ///
+    /// By default handoff timeout sets to 30 seconds.
+    pub fn handoff_timeout(mut self, sec: u64) -> Self {
+        self.handoff.ready_timeout = Duration::from_secs(sec);
+        self
+    }
+
+    /// Adds service to the server using TCP listeners inherited from the previous process.
+    ///
+    /// Adopts every listener that the previous process handed off under `name`. See
+    /// [`ServerHandle::handoff()`](crate::ServerHandle::handoff()).
+    ///
+    /// Inherited listeners are taken from the environment when the first `ServerBuilder` of the
+    /// process is created. The variables describing them are removed from the environment then,
+    /// so the new process must create its first builder before it spawns any threads.
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is: number of [`workers`](Self::workers()) × number of inherited listeners.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - no listener named `name` was inherited;
+    /// - an inherited listener is not a TCP listener.
+    pub fn listen_inherited<F, N>(self, name: N, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<TcpStream>,
+        N: AsRef<str>,
+    {
+        let listeners = crate::handoff::take_inherited(name.as_ref())?;
+        self.listen_mio_tcp(name.as_ref(), listeners, factory)
+    }
+
+    /// Adds service to the server using UDS listeners inherited from the previous process.
+    ///
+    /// Adopts every listener that the previous process handed off under `name`. See
+    /// [`ServerHandle::handoff()`](crate::ServerHandle::handoff()).
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is: number of [`workers`](Self::workers()) × number of inherited listeners.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - no listener named `name` was inherited;
+    /// - an inherited listener is not a UDS listener.
+    pub fn listen_uds_inherited<F, N>(self, name: N, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<actix_rt::net::UnixStream>,
+        N: AsRef<str>,
+    {
+        let listeners = crate::handoff::take_inherited(name.as_ref())?;
+        self.listen_mio_uds(name.as_ref(), listeners, factory)
+    }
+
+    /// Adds service to the server using TCP sockets passed by systemd socket activation.
+    ///
+    /// Adopts every activated socket whose FD name (set with `FileDescriptorName=` in the
+    /// `.socket` unit) is `name`. Sockets without an explicit name are named `unknown` by systemd.
+    ///
+    /// Activated sockets are taken from the environment when the first `ServerBuilder` of the
+    /// process is created. The variables describing them are removed from the environment then,
+    /// so the activated process must create its first builder before it spawns any threads.
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is: number of [`workers`](Self::workers()) × number of activated sockets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - no socket named `name` was passed by systemd;
+    /// - an activated socket is not a TCP listener.
+    pub fn listen_activated<F, N>(self, name: N, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<TcpStream>,
+        N: AsRef<str>,
+    {
+        let listeners = crate::systemd::take_activated(name.as_ref())?;
+        self.listen_mio_tcp(name.as_ref(), listeners, factory)
+    }
+
+    /// Adds service to the server using UDS sockets passed by systemd socket activation.
+    ///
+    /// Adopts every activated socket whose FD name (set with `FileDescriptorName=` in the
+    /// `.socket` unit) is `name`. Sockets without an explicit name are named `unknown` by systemd.
+    ///
+    /// # Worker Count
+    ///
+    /// The `factory` will be instantiated multiple times in most scenarios. The number of
+    /// instantiations is: number of [`workers`](Self::workers()) × number of activated sockets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if:
+    /// - no socket named `name` was passed by systemd;
+    /// - an activated socket is not a UDS listener.
+    pub fn listen_uds_activated<F, N>(self, name: N, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<actix_rt::net::UnixStream>,
+        N: AsRef<str>,
+    {
+        let listeners = crate::systemd::take_activated(name.as_ref())?;
+        self.listen_mio_uds(name.as_ref(), listeners, factory)
+    }
+
+    /// Enables systemd service notifications.
+    ///
+    /// When `NOTIFY_SOCKET` is set, the server notifies the service manager with:
+    /// - `READY=1` (and `MAINPID`) once all workers have started their services;
+    /// - `STOPPING=1` when shutdown begins;
+    /// - `WATCHDOG=1` at half the `WATCHDOG_USEC` interval, but only while every worker's event
+    ///   loop keeps responding. A blocked worker withholds pings so the service manager can act.
+    ///
+    /// Use `Type=notify` in the service unit. When also using listener
+    /// [handoff](crate::ServerHandle::handoff()), set `NotifyAccess=all` so that the new process can
+    /// announce itself as the main process.
+    pub fn systemd_notify(mut self) -> Self {
+        self.systemd_notify = true;
+        self
+    }
+
+    /// Serves administrative commands on a Unix socket at `path`.
+    ///
+    /// Clients send one command per line and receive a single line in answer:
+    /// - `pause` and `resume` pause or resume accepting connections;
+    /// - `stop` and `stop force` begin a graceful or forced shutdown, like
+    ///   [`ServerHandle::stop()`](crate::ServerHandle::stop());
+    /// - `stats` answers with the [server statistics](crate::ServerStats) as a JSON object.
+    ///
+    /// Successful commands other than `stats` are answered with `ok`, failed ones with `error`
+    /// followed by a description. Access is guarded by file permissions: the socket is only
+    /// accessible to the owner of the server process. An existing file at `path` is replaced and
+    /// the socket file is removed once the server stops, unless it has been replaced in turn, e.g.
+    /// by the new process of a [handoff](crate::ServerHandle::handoff()). Each client is served on
+    /// its own thread.
+    ///
+    /// # Examples
+    /// ```sh
+    /// $ echo stats | nc -U /run/app/admin.sock
+    /// {"paused":false,"workers":[{"idx":0,"connections":3,"restarts":0,"stalled":false}],"listeners":[...]}
+    /// ```
+    pub fn admin_socket(mut self, path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        self.admin = Some(crate::admin::AdminListener::bind(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Makes every worker bind and accept from its own listeners using `SO_REUSEPORT`.
+    ///
+    /// By default, a single accept thread accepts connections and hands them to workers. With this
+    /// option, addresses passed to subsequent [`bind()`](Self::bind()) calls are instead bound by
+    /// each worker with `SO_REUSEPORT` set, letting the kernel spread incoming connections across
+    /// workers. This removes the accept thread from the connection path, at the cost of the kernel
+    /// hashing connections to workers without regard to how busy they are.
+    ///
+    /// Listeners passed to [`listen()`](Self::listen()), UDS listeners and inherited or activated
+    /// listeners are unaffected and still served from the accept thread.
+    ///
+    /// Pausing, resuming, connection limits and graceful shutdown behave as they do for listeners
+    /// served from the accept thread. Listeners bound by workers can not be handed off to a new
+    /// process.
+    ///
+    /// The kernel queues connections on the listener of a specific worker. A worker that stops
+    /// gracefully, e.g. when scaling down, still serves the connections queued on its listeners,
+    /// but they are reset if it is stopped forcibly or dies.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn reuse_port(mut self) -> Self {
+        self.reuse_port = true;
+        self
+    }
+
+    /// Adds service to the server using TCP listeners adopted from outside this process.
+    fn listen_mio_tcp<F>(
+        mut self,
+        name: &str,
+        listeners: Vec<MioListener>,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<TcpStream>,
+    {
+        for lst in listeners {
+            if !matches!(lst, MioListener::Tcp(_)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(r#"listener "{name}" is not a TCP listener"#),
+                ));
+            }
+
+            let token = self.next_token();
+            self.factories.push(StreamNewService::create(
+                name.to_owned(),
+                token,
+                factory.clone(),
+            ));
+
+            self.sockets.push((token, name.to_owned(), lst));
+        }
+
+        Ok(self)
+    }
+
+    /// Adds service to the server using UDS listeners adopted from outside this process.
+    fn listen_mio_uds<F>(
+        mut self,
+        name: &str,
+        listeners: Vec<MioListener>,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<actix_rt::net::UnixStream>,
+    {
+        for lst in listeners {
+            if !matches!(lst, MioListener::Uds(_)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(r#"listener "{name}" is not a UDS listener"#),
+                ));
+            }
+
+            let token = self.next_token();
+            self.factories.push(StreamNewService::create(
+                name.to_owned(),
+                token,
+                factory.clone(),
+            ));
+
+            self.sockets.push((token, name.to_owned(), lst));
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+impl ServerBuilder {
+    /// Adds new service to the server that receives each connection as a `tokio-uring` stream.
+    ///
+    /// Every worker binds its own listeners with `SO_REUSEPORT` set, as with
+    /// [`reuse_port()`](Self::reuse_port()), and accepts from them with a multishot io-uring
+    /// accept request: once submitted, the request keeps accepting connections without further
+    /// syscalls. Reads and writes on the stream are also submitted to the io-uring of the worker
+    /// instead of going through readiness polling.
+    ///
+    /// The request is cancelled while the worker is paused or has reached its connection limit,
+    /// and submitted again once it accepts connections again. On graceful shutdown, connections
+    /// accepted or queued until the request is cancelled are still served.
+    ///
+    /// Otherwise behaves like [`bind()`](Self::bind()).
+    pub fn bind_uring<F, U, N>(mut self, name: N, addrs: U, factory: F) -> io::Result<Self>
+    where
+        F: ServerServiceFactory<tokio_uring::net::TcpStream>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        let listeners = reserve_addr(addrs, self.backlog, &self.mptcp, &SocketOptions::new())?;
+
+        tracing::trace!("reserving server addresses: {listeners:?}");
+
+        for mut lst in listeners {
+            let token = self.next_token();
+
+            self.factories.push(StreamNewService::create(
+                name.as_ref().to_string(),
+                token,
+                factory.clone(),
+            ));
+
+            lst.uring = true;
+            self.reuse_port_listeners.push((token, lst));
+        }
+
+        Ok(self)
+    }
+}
+
+/// Reserves addresses for workers to bind to in `SO_REUSEPORT` mode.
+fn reserve_addr<S: ToSocketAddrs>(
+    addr: S,
+    backlog: u32,
+    mptcp: &MpTcp,
+    options: &SocketOptions,
+) -> io::Result<Vec<ReusePortListener>> {
+    let mut opt_err = None;
+    let mut listeners = Vec::new();
+
+    for addr in addr.to_socket_addrs()? {
+        match ReusePortListener::reserve(addr, backlog, mptcp, options) {
+            Ok(lst) => listeners.push(lst),
+            Err(err) => opt_err = Some(err),
+        }
+    }
+
+    if !listeners.is_empty() {
+        Ok(listeners)
+    } else if let Some(err) = opt_err.take() {
+        Err(err)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Can not bind to address.",
+        ))
+    }
 }
 
 pub(super) fn bind_addr<S: ToSocketAddrs>(
     addr: S,
     backlog: u32,
     mptcp: &MpTcp,
+    options: &SocketOptions,
 ) -> io::Result<Vec<MioTcpListener>> {
     let mut opt_err = None;
     let mut success = false;
     let mut sockets = Vec::new();
 
     for addr in addr.to_socket_addrs()? {
-        match create_mio_tcp_listener(addr, backlog, mptcp) {
+        match create_mio_tcp_listener(addr, backlog, mptcp, options) {
             Ok(lst) => {
                 success = true;
                 sockets.push(lst);
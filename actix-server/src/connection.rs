@@ -0,0 +1,297 @@
+//! Connection metadata passed to services that opt in to it.
+
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::socket::{FromStream, MioStream};
+
+/// Address of either end of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionAddr {
+    /// Address of a TCP connection.
+    Tcp(SocketAddr),
+
+    /// Path of a Unix domain socket; `None` if the socket is unnamed or in the abstract
+    /// namespace, as client sockets usually are.
+    #[cfg(unix)]
+    Uds(Option<std::path::PathBuf>),
+
+    /// Address could not be determined.
+    Unknown,
+}
+
+/// Credentials of the process on the other end of a Unix domain socket connection.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PeerCred {
+    /// Process ID of the peer.
+    pub pid: i32,
+
+    /// User ID of the peer.
+    pub uid: u32,
+
+    /// Group ID of the peer.
+    pub gid: u32,
+}
+
+/// Metadata of an accepted connection.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    listener_name: Arc<str>,
+    listener_token: usize,
+    worker: usize,
+    accepted_at: Instant,
+    local_addr: ConnectionAddr,
+    peer_addr: ConnectionAddr,
+    #[cfg(unix)]
+    peer_cred: Option<PeerCred>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn new(
+        stream: &MioStream,
+        listener_name: Arc<str>,
+        listener_token: usize,
+        worker: usize,
+        accepted_at: Instant,
+    ) -> Self {
+        let (local_addr, peer_addr) = match *stream {
+            MioStream::Tcp(ref stream) => (
+                stream
+                    .local_addr()
+                    .map_or(ConnectionAddr::Unknown, ConnectionAddr::Tcp),
+                stream
+                    .peer_addr()
+                    .map_or(ConnectionAddr::Unknown, ConnectionAddr::Tcp),
+            ),
+            #[cfg(unix)]
+            MioStream::Uds(ref stream) => {
+                (uds_addr(stream.local_addr()), uds_addr(stream.peer_addr()))
+            }
+            MioStream::Custom(ref stream) => (stream.local_addr(), stream.peer_addr()),
+        };
+
+        Self {
+            listener_name,
+            listener_token,
+            worker,
+            accepted_at,
+            local_addr,
+            peer_addr,
+            #[cfg(unix)]
+            peer_cred: peer_cred(stream),
+        }
+    }
+
+    /// Returns name of the listener that accepted the connection.
+    pub fn listener_name(&self) -> &str {
+        &self.listener_name
+    }
+
+    /// Returns token of the listener that accepted the connection.
+    ///
+    /// Every socket bound for a service has its own token, e.g. a service bound to `localhost`
+    /// may have listeners for both `127.0.0.1` and `::1`.
+    pub fn listener_token(&self) -> usize {
+        self.listener_token
+    }
+
+    /// Returns index of the worker handling the connection.
+    pub fn worker(&self) -> usize {
+        self.worker
+    }
+
+    /// Returns when the connection was accepted.
+    pub fn accepted_at(&self) -> Instant {
+        self.accepted_at
+    }
+
+    /// Returns local address of the connection.
+    pub fn local_addr(&self) -> &ConnectionAddr {
+        &self.local_addr
+    }
+
+    /// Returns address of the peer.
+    pub fn peer_addr(&self) -> &ConnectionAddr {
+        &self.peer_addr
+    }
+
+    /// Returns credentials of the peer of a Unix domain socket connection (`SO_PEERCRED`).
+    ///
+    /// Always `None` for TCP connections and on platforms other than Linux.
+    #[cfg(unix)]
+    pub fn peer_cred(&self) -> Option<PeerCred> {
+        self.peer_cred
+    }
+}
+
+/// Stream of an accepted connection along with its metadata.
+///
+/// Services receive it instead of a bare stream when bound with
+/// [`ServerBuilder::bind_connection()`](crate::ServerBuilder::bind_connection()) or
+/// [`ServerBuilder::bind_uds_connection()`](crate::ServerBuilder::bind_uds_connection()).
+///
+/// # Examples
+/// ```
+/// use actix_rt::net::TcpStream;
+/// use actix_server::{Connection, Server};
+/// use actix_service::fn_service;
+///
+/// # fn run() -> std::io::Result<()> {
+/// let srv = Server::build().bind_connection("app", ("127.0.0.1", 8080), || {
+///     fn_service(|conn: Connection<TcpStream>| async move {
+///         let info = conn.info();
+///         println!("{:?} connected to {}", info.peer_addr(), info.listener_name());
+///         Ok::<_, ()>(())
+///     })
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Connection<Io> {
+    io: Io,
+    info: ConnectionInfo,
+}
+
+impl<Io> Connection<Io> {
+    /// Returns metadata of the connection.
+    pub fn info(&self) -> &ConnectionInfo {
+        &self.info
+    }
+
+    /// Returns a shared reference to the underlying stream.
+    pub fn get_ref(&self) -> &Io {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut Io {
+        &mut self.io
+    }
+
+    /// Returns the underlying stream, dropping the metadata.
+    pub fn into_inner(self) -> Io {
+        self.io
+    }
+
+    /// Returns the underlying stream and the metadata of the connection.
+    pub fn into_parts(self) -> (Io, ConnectionInfo) {
+        (self.io, self.info)
+    }
+}
+
+impl<Io: FromStream> FromStream for Connection<Io> {
+    fn from_mio(_: MioStream) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "connection metadata is not available",
+        ))
+    }
+
+    fn from_mio_with_info(
+        sock: MioStream,
+        info: impl FnOnce(&MioStream) -> ConnectionInfo,
+    ) -> io::Result<Self> {
+        let info = info(&sock);
+        let io = Io::from_mio(sock)?;
+        Ok(Self { io, info })
+    }
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for Connection<Io> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_read(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for Connection<Io> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(unix)]
+fn uds_addr(addr: io::Result<mio::net::SocketAddr>) -> ConnectionAddr {
+    match addr {
+        Ok(addr) => ConnectionAddr::Uds(addr.as_pathname().map(ToOwned::to_owned)),
+        Err(_) => ConnectionAddr::Unknown,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn peer_cred(stream: &MioStream) -> Option<PeerCred> {
+    use std::os::unix::io::AsRawFd as _;
+
+    let stream = match *stream {
+        MioStream::Uds(ref stream) => stream,
+        MioStream::Tcp(_) | MioStream::Custom(_) => return None,
+    };
+
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: `cred` and `len` are valid for writes and `len` holds the size of `cred`.
+    let res = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    (res == 0).then_some(PeerCred {
+        pid: cred.pid,
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn peer_cred(_: &MioStream) -> Option<PeerCred> {
+    None
+}
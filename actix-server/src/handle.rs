@@ -1,24 +1,51 @@
-use std::future::Future;
+use std::{future::Future, io};
 
-use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use actix_rt::net::TcpStream;
+use tokio::sync::{broadcast, mpsc::UnboundedSender, oneshot};
 
-use crate::server::ServerCommand;
+use crate::{
+    server::{ServerCommand, MAX_WORKERS},
+    service::{ServerServiceFactory, StreamNewService},
+    socket::ToSocketAddrs,
+    IpFilter, ServerStats, SocketOptions, WorkerEvent,
+};
 
 /// Server handle.
 #[derive(Debug, Clone)]
 pub struct ServerHandle {
     cmd_tx: UnboundedSender<ServerCommand>,
+    worker_events: broadcast::Sender<WorkerEvent>,
 }
 
 impl ServerHandle {
-    pub(crate) fn new(cmd_tx: UnboundedSender<ServerCommand>) -> Self {
-        ServerHandle { cmd_tx }
+    pub(crate) fn new(
+        cmd_tx: UnboundedSender<ServerCommand>,
+        worker_events: broadcast::Sender<WorkerEvent>,
+    ) -> Self {
+        ServerHandle {
+            cmd_tx,
+            worker_events,
+        }
     }
 
     pub(crate) fn worker_faulted(&self, idx: usize) {
         let _ = self.cmd_tx.send(ServerCommand::WorkerFaulted(idx));
     }
 
+    #[cfg(unix)]
+    pub(crate) fn handoff_completed(
+        &self,
+        result: io::Result<()>,
+        completion: Option<oneshot::Sender<io::Result<()>>>,
+        force_system_stop: bool,
+    ) {
+        let _ = self.cmd_tx.send(ServerCommand::HandoffCompleted {
+            result,
+            completion,
+            force_system_stop,
+        });
+    }
+
     /// Pause accepting incoming connections.
     ///
     /// May drop socket pending connection. All open connections remain active.
@@ -39,8 +66,190 @@ impl ServerHandle {
         }
     }
 
+    /// Subscribes to events of workers dying and being restarted.
+    ///
+    /// Only events sent after subscribing are received. A receiver that falls behind by more than
+    /// 64 events skips the oldest ones.
+    pub fn worker_events(&self) -> broadcast::Receiver<WorkerEvent> {
+        self.worker_events.subscribe()
+    }
+
+    /// Returns a snapshot of server statistics.
+    ///
+    /// Resolves to `None` if the server is not running.
+    pub fn stats(&self) -> impl Future<Output = Option<ServerStats>> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.cmd_tx.send(ServerCommand::Stats(tx));
+        async { rx.await.ok() }
+    }
+
+    /// Adds new service to the running server.
+    ///
+    /// Works like [`ServerBuilder::bind()`](crate::ServerBuilder::bind()), using the server's
+    /// backlog and MPTCP settings. The `factory` is instantiated on every worker before
+    /// connections are accepted on the new listeners. Listeners added at runtime are always served
+    /// from the accept thread, even in [`reuse_port`] mode.
+    ///
+    /// Note that, if a DNS lookup is required, resolving hostnames is a blocking operation.
+    ///
+    /// # Errors
+    ///
+    /// Resolves to an `io::Error` if:
+    /// - `addrs` cannot be resolved into one or more socket addresses;
+    /// - all the resolved socket addresses are already bound;
+    /// - the service can not be started on every worker;
+    /// - the server is not running.
+    ///
+    /// [`reuse_port`]: crate::ServerBuilder::reuse_port()
+    pub fn bind<F, U, N>(
+        &self,
+        name: N,
+        addrs: U,
+        factory: F,
+    ) -> impl Future<Output = io::Result<()>>
+    where
+        F: ServerServiceFactory<TcpStream>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        self.bind_with_options(name, addrs, SocketOptions::new(), factory)
+    }
+
+    /// Adds new service to the running server, setting socket `options` on its listeners and on
+    /// the connections they accept.
+    ///
+    /// Otherwise behaves like [`bind()`](Self::bind()).
+    pub fn bind_with_options<F, U, N>(
+        &self,
+        name: N,
+        addrs: U,
+        options: SocketOptions,
+        factory: F,
+    ) -> impl Future<Output = io::Result<()>>
+    where
+        F: ServerServiceFactory<TcpStream>,
+        U: ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        let (tx, rx) = oneshot::channel();
+        let name = name.as_ref().to_owned();
+        let conn = options.connection().clone();
+
+        let sent = addrs.to_socket_addrs().map(|addrs| {
+            let _ = self.cmd_tx.send(ServerCommand::Bind {
+                name: name.clone(),
+                addrs: addrs.collect(),
+                options,
+                factory: Box::new(move |token| {
+                    StreamNewService::create_with_options(
+                        name.clone(),
+                        token,
+                        factory.clone(),
+                        conn.clone(),
+                    )
+                }),
+                completion: tx,
+            });
+        });
+
+        async {
+            sent?;
+            rx.await.unwrap_or_else(|_| Err(not_running()))
+        }
+    }
+
+    /// Removes all listeners named `name` from the running server.
+    ///
+    /// Listeners are closed and their services are stopped on every worker. Connections already
+    /// being handled by these services are left to finish; other services are unaffected.
+    ///
+    /// # Errors
+    ///
+    /// Resolves to a `NotFound` error if there is no listener named `name`, or another
+    /// `io::Error` if the server is not running.
+    pub fn unbind(&self, name: impl AsRef<str>) -> impl Future<Output = io::Result<()>> {
+        let (tx, rx) = oneshot::channel();
+
+        let _ = self.cmd_tx.send(ServerCommand::Unbind {
+            name: name.as_ref().to_owned(),
+            completion: tx,
+        });
+
+        async { rx.await.unwrap_or_else(|_| Err(not_running())) }
+    }
+
+    /// Changes the number of workers of the running server to `num`.
+    ///
+    /// New workers start all services before they receive connections. When scaling down, the
+    /// workers with the highest indexes are removed: they stop receiving connections and then shut
+    /// down gracefully, finishing the connections they are handling in the background using the
+    /// [shutdown timeout]. Resolves once added workers are running and removed workers no longer
+    /// receive connections.
+    ///
+    /// # Errors
+    ///
+    /// Resolves to an `InvalidInput` error if `num` is 0 or greater than 512, or another
+    /// `io::Error` if a worker can not be started or the server is not running.
+    ///
+    /// [shutdown timeout]: crate::ServerBuilder::shutdown_timeout()
+    pub fn set_workers(&self, num: usize) -> impl Future<Output = io::Result<()>> {
+        let (tx, rx) = oneshot::channel();
+
+        let valid = if num == 0 || num > MAX_WORKERS {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("number of workers must be between 1 and {MAX_WORKERS}"),
+            ))
+        } else {
+            let _ = self.cmd_tx.send(ServerCommand::SetWorkers {
+                num,
+                completion: tx,
+            });
+            Ok(())
+        };
+
+        async {
+            valid?;
+            rx.await.unwrap_or_else(|_| Err(not_running()))
+        }
+    }
+
+    /// Replaces IP allow and deny lists of the listeners named `name`.
+    ///
+    /// The new filter applies to connections accepted after this resolves; open connections are
+    /// not affected. See [`ServerBuilder::ip_filter()`](crate::ServerBuilder::ip_filter()).
+    ///
+    /// # Errors
+    ///
+    /// Resolves to a `NotFound` error if there is no listener named `name`, or another
+    /// `io::Error` if the server is not running.
+    pub fn set_ip_filter(
+        &self,
+        name: impl AsRef<str>,
+        filter: IpFilter,
+    ) -> impl Future<Output = io::Result<()>> {
+        let (tx, rx) = oneshot::channel();
+
+        let _ = self.cmd_tx.send(ServerCommand::SetIpFilter {
+            name: name.as_ref().to_owned(),
+            filter,
+            completion: tx,
+        });
+
+        async { rx.await.unwrap_or_else(|_| Err(not_running())) }
+    }
+
     /// Stop incoming connection processing, stop all workers and exit.
     pub fn stop(&self, graceful: bool) -> impl Future<Output = ()> {
+        let rx = self.stop_completed(graceful);
+
+        async {
+            let _ = rx.await;
+        }
+    }
+
+    /// Starts shutdown, returning a receiver of whether it finished all connections in time.
+    pub(crate) fn stop_completed(&self, graceful: bool) -> oneshot::Receiver<bool> {
         let (tx, rx) = oneshot::channel();
 
         let _ = self.cmd_tx.send(ServerCommand::Stop {
@@ -49,8 +258,37 @@ impl ServerHandle {
             force_system_stop: false,
         });
 
-        async {
-            let _ = rx.await;
-        }
+        rx
     }
+
+    /// Hand listening sockets off to a new process, then stop gracefully.
+    ///
+    /// A new process is spawned using the [handoff command] with the listener FDs left open and
+    /// described in its environment. It adopts them by name using
+    /// [`ServerBuilder::listen_inherited()`] or [`ServerBuilder::listen_uds_inherited()`] and
+    /// reports readiness once all its workers have started. This server then stops accepting
+    /// connections and shuts down gracefully, so no connection is refused during the upgrade.
+    ///
+    /// Resolves once the new process is ready, or with an error if it could not be spawned or did
+    /// not become ready within the [handoff timeout]. The server keeps running if handoff fails.
+    ///
+    /// [handoff command]: crate::ServerBuilder::handoff_command()
+    /// [handoff timeout]: crate::ServerBuilder::handoff_timeout()
+    /// [`ServerBuilder::listen_inherited()`]: crate::ServerBuilder::listen_inherited()
+    /// [`ServerBuilder::listen_uds_inherited()`]: crate::ServerBuilder::listen_uds_inherited()
+    #[cfg(unix)]
+    pub fn handoff(&self) -> impl Future<Output = io::Result<()>> {
+        let (tx, rx) = oneshot::channel();
+
+        let _ = self.cmd_tx.send(ServerCommand::Handoff {
+            completion: Some(tx),
+            force_system_stop: false,
+        });
+
+        async { rx.await.unwrap_or_else(|_| Err(not_running())) }
+    }
+}
+
+fn not_running() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "server is not running")
 }
@@ -0,0 +1,305 @@
+//! Listener handoff between an old and a new server process.
+//!
+//! The old process passes its listening sockets to the new one by leaving duplicated FDs open
+//! across `exec` and describing them in the `ACTIX_SERVER_LISTENERS` environment variable as
+//! `name=fd` pairs separated by `;`. A Unix socket whose FD is given in `ACTIX_SERVER_READY_FD` is
+//! written to by the new process once all of its workers have started.
+//!
+//! Both variables are read when the first `ServerBuilder` is created and, if set, removed. Removing
+//! them races with other threads reading the environment, so a process started by a handoff must
+//! create its first `ServerBuilder` before it spawns any threads. Processes that do not take part
+//! in a handoff never have their environment modified.
+
+use std::{
+    env, fmt,
+    io::{self, Write as _},
+    os::unix::{
+        io::{AsRawFd as _, BorrowedFd, FromRawFd as _, RawFd},
+        net::UnixStream as StdUnixStream,
+    },
+    process::{Child, Command},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_rt::time::timeout;
+use socket2::SockRef;
+use tracing::{trace, warn};
+
+use crate::socket::{mio_listener_from_raw_fd, MioListener};
+
+/// Environment variable describing inherited listeners.
+pub(crate) const LISTENERS_ENV: &str = "ACTIX_SERVER_LISTENERS";
+
+/// Environment variable holding the FD used to report readiness to the old process.
+pub(crate) const READY_FD_ENV: &str = "ACTIX_SERVER_READY_FD";
+
+/// State inherited from the old process.
+struct Inherited {
+    /// Listeners not yet adopted by a `ServerBuilder`.
+    listeners: Vec<(String, RawFd)>,
+
+    /// Socket to report readiness on, until it is written to.
+    ready: Option<StdUnixStream>,
+}
+
+/// Inherited state, parsed from the environment by [`take_env()`].
+static INHERITED: Mutex<Option<Inherited>> = Mutex::new(None);
+
+/// Handoff settings passed down from server builder.
+#[derive(Clone)]
+pub(crate) struct HandoffConfig {
+    pub(crate) command: Arc<dyn Fn() -> Command + Send + Sync>,
+    pub(crate) ready_timeout: Duration,
+}
+
+impl Default for HandoffConfig {
+    fn default() -> Self {
+        Self {
+            command: Arc::new(current_exe_command),
+            ready_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl fmt::Debug for HandoffConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandoffConfig")
+            .field("ready_timeout", &self.ready_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Re-runs the current executable with the same arguments.
+fn current_exe_command() -> Command {
+    let exe = env::current_exe()
+        .ok()
+        .or_else(|| env::args_os().next().map(Into::into))
+        .unwrap_or_default();
+
+    let mut cmd = Command::new(exe);
+    cmd.args(env::args_os().skip(1));
+    cmd
+}
+
+/// Reads and removes the handoff environment variables if set, once per process.
+pub(crate) fn take_env() {
+    INHERITED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(inherited_from_env);
+}
+
+/// Takes all inherited listeners registered under `name`.
+///
+/// Returns a `NotFound` error if there are none.
+pub(crate) fn take_inherited(name: &str) -> io::Result<Vec<MioListener>> {
+    let mut inherited = INHERITED.lock().unwrap();
+    let inherited = inherited.get_or_insert_with(inherited_from_env);
+
+    let mut fds = Vec::new();
+    inherited.listeners.retain(|(fd_name, fd)| {
+        if fd_name == name {
+            fds.push(*fd);
+            false
+        } else {
+            true
+        }
+    });
+
+    if fds.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(r#"no inherited listener named "{name}""#),
+        ));
+    }
+
+    // all FDs are taken over before checking for errors, so that none is leaked if one fails
+    fds.into_iter()
+        // SAFETY: FDs listed in the environment were left open for us by the old process and are
+        // removed from the inherited list above so they are only ever adopted once.
+        .map(|fd| unsafe { mio_listener_from_raw_fd(fd) })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn inherited_from_env() -> Inherited {
+    let spec = env::var(LISTENERS_ENV).ok();
+    let ready_fd = env::var(READY_FD_ENV).ok();
+
+    // do not leak our listener FDs description to processes we spawn
+    for var in [LISTENERS_ENV, READY_FD_ENV] {
+        if env::var_os(var).is_some() {
+            env::remove_var(var);
+        }
+    }
+
+    let ready = ready_fd.and_then(|fd| match fd.parse::<RawFd>() {
+        Ok(fd) => {
+            // SAFETY: FD was left open for us by the old process for the sole purpose of
+            // reporting readiness, and the variable naming it is removed above.
+            let stream = unsafe { StdUnixStream::from_raw_fd(fd) };
+
+            // processes we spawn must not keep the old process from seeing us exit
+            if let Err(err) = SockRef::from(&stream).set_cloexec(true) {
+                warn!("can not set close-on-exec on {}: {err}", READY_FD_ENV);
+            }
+
+            Some(stream)
+        }
+        Err(_) => {
+            warn!("ignoring malformed {}: {:?}", READY_FD_ENV, fd);
+            None
+        }
+    });
+
+    let listeners = spec.as_deref().map_or_else(Vec::new, parse_listeners);
+
+    Inherited { listeners, ready }
+}
+
+fn parse_listeners(spec: &str) -> Vec<(String, RawFd)> {
+    spec.split(';')
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .rsplit_once('=')
+                .and_then(|(name, fd)| Some((name.to_owned(), fd.parse().ok()?)));
+
+            if parsed.is_none() {
+                warn!("ignoring malformed {} entry: {:?}", LISTENERS_ENV, entry);
+            }
+
+            parsed
+        })
+        .collect()
+}
+
+/// Tells the old process, if any, that this process is ready to take over.
+pub(crate) fn notify_ready() {
+    let stream = INHERITED
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|inherited| inherited.ready.take());
+
+    let mut stream = match stream {
+        Some(stream) => stream,
+        None => return,
+    };
+
+    match stream.write_all(b"1") {
+        Ok(_) => trace!("notified old process of readiness"),
+        Err(err) => warn!("can not notify old process of readiness: {err}"),
+    }
+}
+
+/// New process spawned to take over the listeners.
+pub(crate) struct Successor {
+    child: Child,
+    ready_rx: StdUnixStream,
+}
+
+/// Spawns a new process that inherits `listeners`.
+///
+/// Listener FDs are duplicated before this returns, so the listeners may be closed afterwards.
+pub(crate) fn spawn_successor(
+    config: &HandoffConfig,
+    listeners: &[(String, RawFd)],
+) -> io::Result<Successor> {
+    let mut spec = String::new();
+    let mut dups = Vec::with_capacity(listeners.len());
+
+    for (name, fd) in listeners {
+        if name.contains(';') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(r#"listener name "{name}" can not be handed off"#),
+            ));
+        }
+
+        // SAFETY: listener FDs are owned by the accept thread which outlives the server future.
+        let fd = unsafe { BorrowedFd::borrow_raw(*fd) };
+        let dup = SockRef::from(&fd).try_clone()?;
+        dup.set_cloexec(false)?;
+
+        if !spec.is_empty() {
+            spec.push(';');
+        }
+        spec.push_str(&format!("{}={}", name, dup.as_raw_fd()));
+
+        dups.push(dup);
+    }
+
+    let (ready_rx, ready_tx) = StdUnixStream::pair()?;
+    SockRef::from(&ready_tx).set_cloexec(false)?;
+
+    let mut cmd = (config.command)();
+    cmd.env(LISTENERS_ENV, spec)
+        .env(READY_FD_ENV, ready_tx.as_raw_fd().to_string());
+
+    ready_rx.set_nonblocking(true)?;
+
+    let child = cmd.spawn()?;
+
+    // the new process holds its own copies now
+    drop(dups);
+    drop(ready_tx);
+
+    Ok(Successor { child, ready_rx })
+}
+
+impl Successor {
+    /// Waits until the new process reports readiness.
+    ///
+    /// If it does not within `ready_timeout`, or exits before, it is killed and reaped.
+    pub(crate) async fn ready(self, ready_timeout: Duration) -> io::Result<()> {
+        let Successor {
+            mut child,
+            ready_rx,
+        } = self;
+
+        let err = match timeout(ready_timeout, wait_ready(ready_rx)).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(err)) => err,
+            Err(_) => io::Error::new(
+                io::ErrorKind::TimedOut,
+                "new process did not become ready in time",
+            ),
+        };
+
+        // does nothing if it has already exited; it still needs to be reaped in that case
+        let _ = child.kill();
+
+        match tokio::task::spawn_blocking(move || child.wait()).await {
+            Ok(Ok(status)) => trace!("failed new process exited with {status}"),
+            Ok(Err(err)) => warn!("can not wait for failed new process: {err}"),
+            Err(err) => warn!("can not wait for failed new process: {err}"),
+        }
+
+        Err(err)
+    }
+}
+
+/// Resolves once the new process writes to `ready_rx`.
+async fn wait_ready(ready_rx: StdUnixStream) -> io::Result<()> {
+    let ready_rx = actix_rt::net::UnixStream::from_std(ready_rx)?;
+
+    loop {
+        ready_rx.readable().await?;
+
+        let mut buf = [0; 1];
+        match ready_rx.try_read(&mut buf) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "new process exited before becoming ready",
+                ))
+            }
+            Ok(_) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -6,26 +6,65 @@
 #![doc(html_favicon_url = "https://actix.rs/favicon.ico")]
 
 mod accept;
+mod access;
+#[cfg(unix)]
+mod admin;
 mod availability;
 mod builder;
+mod connection;
 mod handle;
+#[cfg(unix)]
+mod handoff;
 mod join_all;
+mod limits;
+mod memory;
+mod outcome;
+mod proxy_protocol;
+mod restart;
+mod selector;
 mod server;
 mod service;
+mod shutdown;
 mod signals;
 mod socket;
+mod socket_options;
+mod stats;
+#[cfg(unix)]
+mod systemd;
 mod test_server;
+mod udp;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring_accept;
 mod waker_queue;
 mod worker;
 
+#[cfg(unix)]
+pub use self::connection::PeerCred;
 #[doc(hidden)]
 pub use self::socket::FromStream;
 pub use self::{
+    access::IpFilter,
     builder::{MpTcp, ServerBuilder},
+    connection::{Connection, ConnectionAddr, ConnectionInfo},
     handle::ServerHandle,
+    limits::IpLimits,
+    memory::{MemoryConnector, MemoryListener},
+    outcome::{ConnectionOutcome, ServiceError},
+    proxy_protocol::{
+        ProxyHeader, ProxyProtocol, ProxyProtocolError, ProxyProtocolService, ProxyStream,
+        ProxyVersion, Tlv,
+    },
+    restart::{RestartLimitAction, RestartPolicy, WorkerEvent},
+    selector::{LeastConnections, PowerOfTwoChoices, RoundRobin, WorkerSelector, Workers},
     server::Server,
     service::ServerServiceFactory,
-    test_server::TestServer,
+    shutdown::{worker_shutdown, WorkerShutdown},
+    signals::{Signal, SignalAction},
+    socket::{Listener, ListenerStream},
+    socket_options::SocketOptions,
+    stats::{ListenerStats, ServerStats, WorkerStats},
+    test_server::{TestServer, TestServerBuilder, TestServerHandle},
+    udp::UdpServiceFactory,
 };
 
 /// Start server building process
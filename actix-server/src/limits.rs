@@ -0,0 +1,312 @@
+//! Per-IP connection limits applied when connections are accepted.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// Number of tracked peers above which idle ones are forgotten.
+const MIN_PRUNE_LEN: usize = 1024;
+
+/// Per-IP connection limits of a listener.
+///
+/// Set with [`ServerBuilder::ip_limits()`](crate::ServerBuilder::ip_limits()). Connections over a
+/// limit are closed as soon as they are accepted, before they reach a worker. Peers are grouped
+/// by source IP address, or by network prefix if set with [`prefix()`](Self::prefix()).
+///
+/// # Examples
+/// ```
+/// use actix_server::IpLimits;
+///
+/// // at most 16 concurrent connections and 10 new connections per second (bursts of 20) per /24
+/// // or /64 network
+/// let limits = IpLimits::new()
+///     .max_connections(16)
+///     .rate(10, 20)
+///     .prefix(24, 64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IpLimits {
+    max_connections: Option<usize>,
+    rate: Option<(u32, u32)>,
+    v4_prefix: u8,
+    v6_prefix: u8,
+}
+
+impl IpLimits {
+    /// Creates limits that do not restrict any connection.
+    pub fn new() -> Self {
+        Self {
+            max_connections: None,
+            rate: None,
+            v4_prefix: 32,
+            v6_prefix: 128,
+        }
+    }
+
+    /// Sets maximum number of concurrent connections per peer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num` is 0.
+    pub fn max_connections(mut self, num: usize) -> Self {
+        assert_ne!(num, 0, "max connections must be greater than 0");
+        self.max_connections = Some(num);
+        self
+    }
+
+    /// Sets maximum rate of new connections per peer.
+    ///
+    /// Each peer has a token bucket holding up to `burst` tokens and refilled with `per_sec`
+    /// tokens per second. Every accepted connection takes one token; connections are closed while
+    /// the bucket is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_sec` or `burst` is 0.
+    pub fn rate(mut self, per_sec: u32, burst: u32) -> Self {
+        assert_ne!(per_sec, 0, "rate must be greater than 0");
+        assert_ne!(burst, 0, "burst must be greater than 0");
+        self.rate = Some((per_sec, burst));
+        self
+    }
+
+    /// Sets network prefix lengths that group IPv4 and IPv6 peers.
+    ///
+    /// By default, each address is its own peer, i.e. prefixes are 32 and 128 bits long.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v4` is greater than 32 or `v6` is greater than 128.
+    pub fn prefix(mut self, v4: u8, v6: u8) -> Self {
+        assert!(v4 <= 32, "IPv4 prefix length must be at most 32");
+        assert!(v6 <= 128, "IPv6 prefix length must be at most 128");
+        self.v4_prefix = v4;
+        self.v6_prefix = v6;
+        self
+    }
+
+    /// Returns address of the network `ip` belongs to.
+    fn key(&self, ip: IpAddr) -> IpAddr {
+        let ip = match ip {
+            IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4),
+            ip => ip,
+        };
+
+        match ip {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.v4_prefix as u32)
+                    .unwrap_or(0);
+                IpAddr::V4((u32::from(ip) & mask).into())
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.v6_prefix as u32)
+                    .unwrap_or(0);
+                IpAddr::V6((u128::from(ip) & mask).into())
+            }
+        }
+    }
+}
+
+impl Default for IpLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tracks connections of peers of the listeners sharing a name.
+#[derive(Debug)]
+pub(crate) struct IpLimiter {
+    limits: IpLimits,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    peers: HashMap<IpAddr, Peer>,
+    next_prune: usize,
+}
+
+#[derive(Debug)]
+struct Peer {
+    connections: usize,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Peer {
+    /// Refills token bucket for the time passed since last update.
+    fn refill(&mut self, (per_sec, burst): (u32, u32), now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec as f64).min(burst as f64);
+        self.updated = now;
+    }
+}
+
+impl IpLimiter {
+    pub(crate) fn new(limits: IpLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            state: Mutex::new(LimiterState {
+                peers: HashMap::new(),
+                next_prune: MIN_PRUNE_LEN,
+            }),
+        })
+    }
+
+    /// Counts a new connection from `ip`, unless it is over a limit.
+    ///
+    /// The connection is counted until the returned permit is dropped.
+    pub(crate) fn check(self: &Arc<Self>, ip: IpAddr) -> Option<IpPermit> {
+        let key = self.limits.key(ip);
+        let now = Instant::now();
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.peers.len() >= state.next_prune {
+            self.prune(&mut state.peers, now);
+            state.next_prune = MIN_PRUNE_LEN.max(state.peers.len() * 2);
+        }
+
+        let peer = state.peers.entry(key).or_insert_with(|| Peer {
+            connections: 0,
+            tokens: self.limits.rate.map_or(0.0, |(_, burst)| burst as f64),
+            updated: now,
+        });
+
+        if matches!(self.limits.max_connections, Some(max) if peer.connections >= max) {
+            return None;
+        }
+
+        if let Some(rate) = self.limits.rate {
+            peer.refill(rate, now);
+
+            if peer.tokens < 1.0 {
+                return None;
+            }
+
+            peer.tokens -= 1.0;
+        }
+
+        peer.connections += 1;
+
+        Some(IpPermit {
+            limiter: self.clone(),
+            key,
+        })
+    }
+
+    /// Forgets peers without connections whose token bucket is full again.
+    fn prune(&self, peers: &mut HashMap<IpAddr, Peer>, now: Instant) {
+        let rate = self.limits.rate;
+
+        peers.retain(|_, peer| {
+            if peer.connections > 0 {
+                return true;
+            }
+
+            match rate {
+                Some(rate) => {
+                    peer.refill(rate, now);
+                    peer.tokens < rate.1 as f64
+                }
+                None => false,
+            }
+        });
+    }
+}
+
+/// Counts a connection towards its peer's limits until dropped.
+#[derive(Debug)]
+pub(crate) struct IpPermit {
+    limiter: Arc<IpLimiter>,
+    key: IpAddr,
+}
+
+impl Drop for IpPermit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+
+        if let Some(peer) = state.peers.get_mut(&self.key) {
+            peer.connections -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn max_connections_per_prefix() {
+        let limiter = IpLimiter::new(IpLimits::new().max_connections(2).prefix(24, 64));
+
+        let first = limiter.check(ip("10.0.0.1")).unwrap();
+        let _second = limiter.check(ip("::ffff:10.0.0.2")).unwrap();
+        assert!(limiter.check(ip("10.0.0.3")).is_none());
+
+        // other networks are counted separately
+        assert!(limiter.check(ip("10.0.1.1")).is_some());
+        assert!(limiter.check(ip("2001:db8::1")).is_some());
+
+        drop(first);
+        assert!(limiter.check(ip("10.0.0.3")).is_some());
+    }
+
+    #[test]
+    fn token_bucket() {
+        let limiter = IpLimiter::new(IpLimits::new().rate(20, 2));
+
+        assert!(limiter.check(ip("10.0.0.1")).is_some());
+        assert!(limiter.check(ip("10.0.0.1")).is_some());
+        assert!(limiter.check(ip("10.0.0.1")).is_none());
+        assert!(limiter.check(ip("10.0.0.2")).is_some());
+
+        // one token is refilled every 50ms
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.check(ip("10.0.0.1")).is_some());
+        assert!(limiter.check(ip("10.0.0.1")).is_none());
+    }
+
+    #[test]
+    fn prefix_keys() {
+        let limits = IpLimits::new().prefix(0, 48);
+        assert_eq!(limits.key(ip("192.168.1.1")), ip("0.0.0.0"));
+        assert_eq!(limits.key(ip("2001:db8:1:2::1")), ip("2001:db8:1::"));
+
+        let limits = IpLimits::new();
+        assert_eq!(limits.key(ip("::ffff:1.2.3.4")), ip("1.2.3.4"));
+        assert_eq!(limits.key(ip("::1")), ip("::1"));
+    }
+
+    #[test]
+    fn idle_peers_are_pruned() {
+        let limiter = IpLimiter::new(IpLimits::new().max_connections(1));
+        let held = limiter.check(ip("10.0.0.1")).unwrap();
+
+        for n in 0..MIN_PRUNE_LEN as u32 {
+            drop(limiter.check(IpAddr::from((0x0b00_0000 + n).to_be_bytes())));
+        }
+
+        let (len, has_held) = {
+            let state = limiter.state.lock().unwrap();
+            (state.peers.len(), state.peers.contains_key(&ip("10.0.0.1")))
+        };
+
+        // only the peer with a connection and the ones checked after pruning are left
+        assert_eq!(len, 2);
+        assert!(has_held);
+
+        drop(held);
+    }
+}
@@ -0,0 +1,258 @@
+//! In-memory transport.
+
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use mio::{event::Source, Interest, Registry, Token};
+use tokio::io::DuplexStream;
+
+use crate::{Listener, ListenerStream};
+
+/// Size of the buffer of each direction of an in-memory connection.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Listener accepting in-memory connections made with a [`MemoryConnector`].
+///
+/// Serve it with [`ServerBuilder::listen_custom()`](crate::ServerBuilder::listen_custom()); the
+/// service receives the server half of each connection as a [`DuplexStream`]. No sockets or ports
+/// are involved in the connections themselves, which makes it suitable for running many test
+/// servers in parallel.
+///
+/// # Examples
+/// ```
+/// use actix_server::{MemoryListener, Server};
+/// use actix_service::fn_service;
+/// use tokio::io::{AsyncWriteExt as _, DuplexStream};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let lst = MemoryListener::new()?;
+/// let connector = lst.connector();
+///
+/// let srv = Server::build().listen_custom("memory", lst, || {
+///     fn_service(|mut stream: DuplexStream| async move { stream.write_all(b"hello").await })
+/// });
+///
+/// // once the server is running
+/// let stream = connector.connect()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MemoryListener {
+    shared: Arc<Mutex<Shared>>,
+
+    /// Receiving end of the readiness socket pair, registered with the accept poll.
+    readiness: readiness::Receiver,
+}
+
+/// Connects to a [`MemoryListener`].
+#[derive(Debug, Clone)]
+pub struct MemoryConnector {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    /// Server halves of connections waiting to be accepted.
+    pending: VecDeque<DuplexStream>,
+
+    /// Set once the listener is dropped.
+    closed: bool,
+
+    /// Sending end of the readiness socket pair; written to for every new connection.
+    notify: readiness::Sender,
+}
+
+impl MemoryListener {
+    /// Creates new in-memory listener.
+    pub fn new() -> io::Result<Self> {
+        let (notify, readiness) = readiness::pair()?;
+
+        let shared = Shared {
+            pending: VecDeque::new(),
+            closed: false,
+            notify,
+        };
+
+        Ok(Self {
+            shared: Arc::new(Mutex::new(shared)),
+            readiness,
+        })
+    }
+
+    /// Returns a connector for this listener.
+    pub fn connector(&self) -> MemoryConnector {
+        MemoryConnector {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl MemoryConnector {
+    /// Makes new connection, returning the client half of it.
+    ///
+    /// Connections are queued until the server accepts them.
+    ///
+    /// # Errors
+    /// Returns `ConnectionRefused` error if the listener was dropped, e.g. because the server
+    /// stopped.
+    pub fn connect(&self) -> io::Result<DuplexStream> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.closed {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+
+        let (client, server) = tokio::io::duplex(BUFFER_SIZE);
+        shared.pending.push_back(server);
+        shared.notify()?;
+
+        Ok(client)
+    }
+}
+
+impl Shared {
+    /// Makes the listener readable.
+    fn notify(&mut self) -> io::Result<()> {
+        match readiness::send(&self.notify) {
+            // a full buffer is still readable
+            Err(err) if err.kind() != io::ErrorKind::WouldBlock => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Listener for MemoryListener {
+    type Stream = DuplexStream;
+
+    fn accept(&mut self) -> io::Result<DuplexStream> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if let Some(stream) = shared.pending.pop_front() {
+            return Ok(stream);
+        }
+
+        // Connections are only added while holding the lock, so draining here can not lose the
+        // readiness of one.
+        let mut buf = [0; 64];
+        while let Ok(1..) = readiness::recv(&self.readiness, &mut buf) {}
+
+        Err(io::ErrorKind::WouldBlock.into())
+    }
+}
+
+impl ListenerStream for DuplexStream {}
+
+impl Source for MemoryListener {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.readiness.register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.readiness.reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        self.readiness.deregister(registry)
+    }
+}
+
+impl Drop for MemoryListener {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+
+        // clients of connections never accepted see them closed
+        shared.pending.clear();
+    }
+}
+
+/// Socket pair that is readable while connections may be pending.
+///
+/// A socket is used rather than a [`mio::Waker`], as a poll supports only one waker, which the
+/// accept thread already owns.
+#[cfg(unix)]
+mod readiness {
+    use std::io::{self, Read as _, Write as _};
+
+    pub(super) type Sender = mio::net::UnixStream;
+    pub(super) type Receiver = mio::net::UnixStream;
+
+    pub(super) fn pair() -> io::Result<(Sender, Receiver)> {
+        mio::net::UnixStream::pair()
+    }
+
+    pub(super) fn send(tx: &Sender) -> io::Result<usize> {
+        (&*tx).write(&[1])
+    }
+
+    pub(super) fn recv(rx: &Receiver, buf: &mut [u8]) -> io::Result<usize> {
+        (&*rx).read(buf)
+    }
+}
+
+/// Socket pair that is readable while connections may be pending.
+///
+/// Without Unix sockets, a loopback UDP socket connected to itself is used.
+#[cfg(not(unix))]
+mod readiness {
+    use std::{io, net};
+
+    pub(super) type Sender = net::UdpSocket;
+    pub(super) type Receiver = mio::net::UdpSocket;
+
+    pub(super) fn pair() -> io::Result<(Sender, Receiver)> {
+        let rx = net::UdpSocket::bind((net::Ipv4Addr::LOCALHOST, 0))?;
+        rx.connect(rx.local_addr()?)?;
+        rx.set_nonblocking(true)?;
+
+        let tx = rx.try_clone()?;
+        Ok((tx, mio::net::UdpSocket::from_std(rx)))
+    }
+
+    pub(super) fn send(tx: &Sender) -> io::Result<usize> {
+        tx.send(&[1])
+    }
+
+    pub(super) fn recv(rx: &Receiver, buf: &mut [u8]) -> io::Result<usize> {
+        rx.recv(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_pending() {
+        let mut lst = MemoryListener::new().unwrap();
+        let connector = lst.connector();
+
+        assert_eq!(lst.accept().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        connector.connect().unwrap();
+        connector.connect().unwrap();
+        assert!(lst.accept().is_ok());
+        assert!(lst.accept().is_ok());
+        assert_eq!(lst.accept().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        drop(lst);
+        assert_eq!(
+            connector.connect().unwrap_err().kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+    }
+}
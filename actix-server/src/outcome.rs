@@ -0,0 +1,143 @@
+//! Outcomes of connections handled by stream services.
+
+use std::{
+    any::{type_name, Any},
+    error::Error as StdError,
+    fmt,
+    future::{poll_fn, Future},
+    io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    task::Poll,
+};
+
+/// Hook called with the listener name and outcome of every connection.
+pub(crate) type OutcomeHook = Arc<dyn Fn(&str, &ConnectionOutcome<'_>) + Send + Sync>;
+
+/// Outcome of a connection handled by a stream service.
+///
+/// Reported to the hook set with
+/// [`ServerBuilder::on_connection_outcome()`](crate::ServerBuilder::on_connection_outcome()).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConnectionOutcome<'a> {
+    /// Service future completed successfully.
+    Success,
+
+    /// Service future resolved to an error.
+    Error(ServiceError<'a>),
+
+    /// Service panicked while handling the connection.
+    ///
+    /// Holds the panic message, if the panic payload is a string.
+    Panic(Option<&'a str>),
+}
+
+/// Type-erased error returned by a stream service.
+///
+/// Its `Display` implementation shows the message of [`io::Error`]s, strings and boxed
+/// [`Error`](std::error::Error)s; other types are only described by their type name, but can be
+/// recovered with [`downcast_ref()`](Self::downcast_ref()).
+#[derive(Clone, Copy)]
+pub struct ServiceError<'a> {
+    err: &'a dyn Any,
+    type_name: &'static str,
+}
+
+impl<'a> ServiceError<'a> {
+    pub(crate) fn new<E: 'static>(err: &'a E) -> Self {
+        Self {
+            err,
+            type_name: type_name::<E>(),
+        }
+    }
+
+    /// Returns the error if it is of type `E`.
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&'a E> {
+        self.err.downcast_ref()
+    }
+
+    /// Returns name of the error type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Debug for ServiceError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceError")
+            .field("type_name", &self.type_name)
+            .field("message", &format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl fmt::Display for ServiceError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(err) = self.downcast_ref::<io::Error>() {
+            fmt::Display::fmt(err, f)
+        } else if let Some(msg) = self.downcast_ref::<String>() {
+            f.write_str(msg)
+        } else if let Some(msg) = self.downcast_ref::<&'static str>() {
+            f.write_str(msg)
+        } else if let Some(err) = self.downcast_ref::<Box<dyn StdError>>() {
+            fmt::Display::fmt(err, f)
+        } else if let Some(err) = self.downcast_ref::<Box<dyn StdError + Send + Sync>>() {
+            fmt::Display::fmt(err, f)
+        } else {
+            write!(f, "service error of type `{}`", self.type_name)
+        }
+    }
+}
+
+/// Runs `fut` to completion, catching a panic raised while polling it.
+pub(crate) async fn catch_panic<F: Future>(fut: F) -> Result<F::Output, Box<dyn Any + Send>> {
+    tokio::pin!(fut);
+
+    poll_fn(
+        |cx| match catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(payload)),
+        },
+    )
+    .await
+}
+
+/// Returns message of a panic, if its payload is a string.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&'static str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_error_message() {
+        let err = io::Error::new(io::ErrorKind::Other, "broken pipe");
+        assert_eq!(ServiceError::new(&err).to_string(), "broken pipe");
+
+        let err = String::from("bad request");
+        assert_eq!(ServiceError::new(&err).to_string(), "bad request");
+
+        let err = ServiceError::new(&());
+        assert_eq!(err.to_string(), "service error of type `()`");
+        assert!(err.downcast_ref::<()>().is_some());
+        assert!(err.downcast_ref::<String>().is_none());
+    }
+
+    #[test]
+    fn caught_panic_message() {
+        let payload = catch_unwind(|| panic!("boom")).unwrap_err();
+        assert_eq!(panic_message(&*payload), Some("boom"));
+
+        let payload = catch_unwind(|| panic!("code {}", 42)).unwrap_err();
+        assert_eq!(panic_message(&*payload), Some("code 42"));
+
+        let payload = catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+        assert_eq!(panic_message(&*payload), None);
+    }
+}
@@ -0,0 +1,632 @@
+//! PROXY protocol v1 and v2 support for accepted connections.
+//!
+//! See the [PROXY protocol specification](https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt).
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    future::poll_fn,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::timeout;
+use actix_service::{Service, ServiceFactory};
+use futures_core::future::LocalBoxFuture;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Signature starting a v2 header.
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Maximum length of a v1 header, including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Length of the fixed part of a v2 header.
+const V2_HEADER_LEN: usize = 16;
+
+/// Version of the PROXY protocol a header was sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyVersion {
+    /// Human-readable text header.
+    V1,
+
+    /// Binary header.
+    V2,
+}
+
+/// Type-length-value field of a v2 header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Tlv {
+    /// Type of the field, e.g. `0x01` for the ALPN negotiated by the proxy.
+    pub kind: u8,
+
+    /// Raw value of the field.
+    pub value: Vec<u8>,
+}
+
+/// PROXY protocol header received at the start of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProxyHeader {
+    /// Protocol version of the header.
+    pub version: ProxyVersion,
+
+    /// Address of the client that connected to the proxy.
+    ///
+    /// `None` if the proxy did not relay a connection (health checks use a v2 `LOCAL` command),
+    /// or if the address family is unknown or not IP-based.
+    pub source: Option<SocketAddr>,
+
+    /// Address the client connected to on the proxy.
+    ///
+    /// `None` in the same cases as `source`.
+    pub destination: Option<SocketAddr>,
+
+    /// Type-length-value fields of a v2 header; always empty for v1.
+    pub tlvs: Vec<Tlv>,
+}
+
+impl ProxyHeader {
+    /// Returns value of the first TLV field of type `kind`.
+    pub fn tlv(&self, kind: u8) -> Option<&[u8]> {
+        self.tlvs
+            .iter()
+            .find(|tlv| tlv.kind == kind)
+            .map(|tlv| &tlv.value[..])
+    }
+}
+
+/// Error of a service wrapped with [`ProxyProtocol`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProxyProtocolError<E> {
+    /// Complete header was not received within the timeout.
+    Timeout,
+
+    /// Header is malformed or the connection does not start with one.
+    Malformed(&'static str),
+
+    /// Reading the header failed.
+    Io(io::Error),
+
+    /// Wrapped service failed.
+    Service(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ProxyProtocolError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => f.write_str("timed out reading PROXY protocol header"),
+            Self::Malformed(reason) => write!(f, "malformed PROXY protocol header: {}", reason),
+            Self::Io(err) => write!(f, "can not read PROXY protocol header: {}", err),
+            Self::Service(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for ProxyProtocolError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Service(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Stream of a connection whose PROXY protocol header has been read.
+///
+/// Reads continue with the bytes sent after the header.
+#[derive(Debug)]
+pub struct ProxyStream<Io> {
+    io: Io,
+    header: ProxyHeader,
+    /// Bytes read past the header, served before reading from `io` again.
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<Io> ProxyStream<Io> {
+    /// Returns the received header.
+    pub fn header(&self) -> &ProxyHeader {
+        &self.header
+    }
+
+    /// Returns a shared reference to the underlying stream.
+    pub fn get_ref(&self) -> &Io {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reading from it directly skips bytes already buffered after the header.
+    pub fn get_mut(&mut self) -> &mut Io {
+        &mut self.io
+    }
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for ProxyStream<Io> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.pos < this.buf.len() {
+            let len = buf.remaining().min(this.buf.len() - this.pos);
+            buf.put_slice(&this.buf[this.pos..this.pos + len]);
+            this.pos += len;
+
+            if this.pos == this.buf.len() {
+                this.buf = Vec::new();
+                this.pos = 0;
+            }
+
+            return Poll::Ready(Ok(()));
+        }
+
+        Pin::new(&mut this.io).poll_read(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for ProxyStream<Io> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+/// Service factory wrapper that reads a PROXY protocol header from every connection before
+/// handing it to the wrapped service as a [`ProxyStream`].
+///
+/// Both v1 and v2 headers are accepted. Connections that do not start with a valid header, or
+/// whose header is not received within the timeout, are closed without calling the wrapped
+/// service. Only use this on listeners that are reachable from trusted proxies alone, as the
+/// header can not be authenticated.
+///
+/// # Examples
+/// ```
+/// use actix_rt::net::TcpStream;
+/// use actix_server::{ProxyProtocol, ProxyStream, Server};
+/// use actix_service::fn_service;
+///
+/// # fn build() -> std::io::Result<()> {
+/// Server::build().bind("behind-proxy", ("127.0.0.1", 8080), || {
+///     ProxyProtocol::new(fn_service(|stream: ProxyStream<TcpStream>| async move {
+///         println!("client address: {:?}", stream.header().source);
+///         Ok::<_, ()>(())
+///     }))
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProxyProtocol<F> {
+    factory: F,
+    timeout: Duration,
+}
+
+impl<F> ProxyProtocol<F> {
+    /// Wraps service factory `factory`.
+    pub fn new(factory: F) -> Self {
+        Self {
+            factory,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Sets time to wait for the complete header once a connection is accepted.
+    ///
+    /// By default, the timeout is 5 seconds.
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = dur;
+        self
+    }
+}
+
+impl<F, Io> ServiceFactory<Io> for ProxyProtocol<F>
+where
+    F: ServiceFactory<ProxyStream<Io>, Config = ()>,
+    F::Service: 'static,
+    F::Future: 'static,
+    Io: AsyncRead + Unpin + 'static,
+{
+    type Response = F::Response;
+    type Error = ProxyProtocolError<F::Error>;
+    type Config = ();
+    type Service = ProxyProtocolService<F::Service>;
+    type InitError = F::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, cfg: ()) -> Self::Future {
+        let fut = self.factory.new_service(cfg);
+        let timeout = self.timeout;
+
+        Box::pin(async move {
+            Ok(ProxyProtocolService {
+                service: Rc::new(fut.await?),
+                timeout,
+            })
+        })
+    }
+}
+
+/// Service created by [`ProxyProtocol`].
+#[derive(Debug)]
+pub struct ProxyProtocolService<S> {
+    service: Rc<S>,
+    timeout: Duration,
+}
+
+impl<S, Io> Service<Io> for ProxyProtocolService<S>
+where
+    S: Service<ProxyStream<Io>> + 'static,
+    Io: AsyncRead + Unpin + 'static,
+{
+    type Response = S::Response;
+    type Error = ProxyProtocolError<S::Error>;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service
+            .poll_ready(cx)
+            .map_err(ProxyProtocolError::Service)
+    }
+
+    fn call(&self, io: Io) -> Self::Future {
+        let service = self.service.clone();
+        let dur = self.timeout;
+
+        Box::pin(async move {
+            let stream = timeout(dur, read_header(io))
+                .await
+                .map_err(|_| ProxyProtocolError::Timeout)??;
+
+            service
+                .call(stream)
+                .await
+                .map_err(ProxyProtocolError::Service)
+        })
+    }
+}
+
+/// Reads from `io` until a complete header is received.
+async fn read_header<Io, E>(mut io: Io) -> Result<ProxyStream<Io>, ProxyProtocolError<E>>
+where
+    Io: AsyncRead + Unpin,
+{
+    let mut buf = Vec::with_capacity(V1_MAX_LEN);
+
+    loop {
+        if let Some((header, len)) = parse(&buf).map_err(ProxyProtocolError::Malformed)? {
+            return Ok(ProxyStream {
+                io,
+                header,
+                buf,
+                pos: len,
+            });
+        }
+
+        let mut chunk = [0; 512];
+        let mut read_buf = ReadBuf::new(&mut chunk);
+        poll_fn(|cx| Pin::new(&mut io).poll_read(cx, &mut read_buf))
+            .await
+            .map_err(ProxyProtocolError::Io)?;
+
+        if read_buf.filled().is_empty() {
+            return Err(ProxyProtocolError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        buf.extend_from_slice(read_buf.filled());
+    }
+}
+
+/// Parses a header at the start of `buf`, returning it with its length.
+///
+/// Returns `None` if more bytes are needed.
+fn parse(buf: &[u8]) -> Result<Option<(ProxyHeader, usize)>, &'static str> {
+    let len = buf.len().min(V2_SIGNATURE.len());
+
+    if buf[..len] == V2_SIGNATURE[..len] {
+        if len < V2_SIGNATURE.len() {
+            return Ok(None);
+        }
+
+        parse_v2(buf)
+    } else if b"PROXY "[..len.min(6)] == buf[..len.min(6)] {
+        parse_v1(buf)
+    } else {
+        Err("missing signature")
+    }
+}
+
+fn parse_v1(buf: &[u8]) -> Result<Option<(ProxyHeader, usize)>, &'static str> {
+    let end = match buf.windows(2).position(|window| window == b"\r\n") {
+        Some(end) if end + 2 <= V1_MAX_LEN => end,
+        Some(_) => return Err("v1 header is too long"),
+        None if buf.len() >= V1_MAX_LEN => return Err("v1 header is too long"),
+        None => return Ok(None),
+    };
+
+    let line = std::str::from_utf8(&buf[..end]).map_err(|_| "v1 header is not ASCII")?;
+    let mut parts = line.split(' ').skip(1);
+
+    let (source, destination) = match parts.next() {
+        Some("UNKNOWN") => (None, None),
+
+        Some(proto @ ("TCP4" | "TCP6")) => {
+            let addr = |port: Option<&str>, ip: Option<&str>| {
+                let ip = ip
+                    .and_then(|ip| match proto {
+                        "TCP4" => ip.parse::<Ipv4Addr>().ok().map(IpAddr::V4),
+                        _ => ip.parse::<Ipv6Addr>().ok().map(IpAddr::V6),
+                    })
+                    .ok_or("invalid v1 address")?;
+                let port = port
+                    .and_then(|port| port.parse::<u16>().ok())
+                    .ok_or("invalid v1 port")?;
+
+                Ok::<_, &'static str>(SocketAddr::new(ip, port))
+            };
+
+            let (src_ip, dst_ip) = (parts.next(), parts.next());
+            let (src_port, dst_port) = (parts.next(), parts.next());
+
+            if parts.next().is_some() {
+                return Err("unexpected v1 field");
+            }
+
+            (Some(addr(src_port, src_ip)?), Some(addr(dst_port, dst_ip)?))
+        }
+
+        _ => return Err("unknown v1 protocol"),
+    };
+
+    let header = ProxyHeader {
+        version: ProxyVersion::V1,
+        source,
+        destination,
+        tlvs: Vec::new(),
+    };
+
+    Ok(Some((header, end + 2)))
+}
+
+fn parse_v2(buf: &[u8]) -> Result<Option<(ProxyHeader, usize)>, &'static str> {
+    if buf.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+
+    let (version, command) = (buf[12] >> 4, buf[12] & 0x0f);
+    let (family, transport) = (buf[13] >> 4, buf[13] & 0x0f);
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+
+    if version != 2 {
+        return Err("unsupported v2 version");
+    }
+
+    if buf.len() < V2_HEADER_LEN + len {
+        return Ok(None);
+    }
+
+    let payload = &buf[V2_HEADER_LEN..V2_HEADER_LEN + len];
+
+    let addrs_len = match family {
+        0x0 => 0,
+        0x1 => 12,
+        0x2 => 36,
+        0x3 => 216,
+        _ => return Err("unknown v2 address family"),
+    };
+
+    if transport > 0x2 {
+        return Err("unknown v2 transport protocol");
+    }
+
+    if payload.len() < addrs_len {
+        return Err("v2 addresses are truncated");
+    }
+
+    let (source, destination) = match (command, family) {
+        // LOCAL: connection was not relayed; addresses are ignored
+        (0x0, _) => (None, None),
+
+        (0x1, 0x1) => {
+            let ip = |at: usize| IpAddr::from(<[u8; 4]>::try_from(&payload[at..at + 4]).unwrap());
+            let port = |at: usize| u16::from_be_bytes([payload[at], payload[at + 1]]);
+
+            (
+                Some(SocketAddr::new(ip(0), port(8))),
+                Some(SocketAddr::new(ip(4), port(10))),
+            )
+        }
+
+        (0x1, 0x2) => {
+            let ip = |at: usize| IpAddr::from(<[u8; 16]>::try_from(&payload[at..at + 16]).unwrap());
+            let port = |at: usize| u16::from_be_bytes([payload[at], payload[at + 1]]);
+
+            (
+                Some(SocketAddr::new(ip(0), port(32))),
+                Some(SocketAddr::new(ip(16), port(34))),
+            )
+        }
+
+        (0x1, _) => (None, None),
+
+        _ => return Err("unknown v2 command"),
+    };
+
+    let mut tlvs = Vec::new();
+    let mut rest = &payload[addrs_len..];
+
+    while !rest.is_empty() {
+        if rest.len() < 3 {
+            return Err("v2 TLV is truncated");
+        }
+
+        let value_len = u16::from_be_bytes([rest[1], rest[2]]) as usize;
+
+        if rest.len() < 3 + value_len {
+            return Err("v2 TLV is truncated");
+        }
+
+        tlvs.push(Tlv {
+            kind: rest[0],
+            value: rest[3..3 + value_len].to_vec(),
+        });
+
+        rest = &rest[3 + value_len..];
+    }
+
+    let header = ProxyHeader {
+        version: ProxyVersion::V2,
+        source,
+        destination,
+        tlvs,
+    };
+
+    Ok(Some((header, V2_HEADER_LEN + len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2(command: u8, family: u8, payload: &[u8]) -> Vec<u8> {
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.push(0x20 | command);
+        buf.push(family << 4 | 0x1);
+        buf.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn v1_tcp4() {
+        let buf = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET /";
+        let (header, len) = parse(buf).unwrap().unwrap();
+
+        assert_eq!(header.version, ProxyVersion::V1);
+        assert_eq!(header.source, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination,
+            Some("192.168.0.11:443".parse().unwrap())
+        );
+        assert_eq!(&buf[len..], b"GET /");
+    }
+
+    #[test]
+    fn v1_tcp6_and_unknown() {
+        let buf = b"PROXY TCP6 ::1 2001:db8::1 1000 80\r\n";
+        let (header, _) = parse(buf).unwrap().unwrap();
+        assert_eq!(header.source, Some("[::1]:1000".parse().unwrap()));
+        assert_eq!(
+            header.destination,
+            Some("[2001:db8::1]:80".parse().unwrap())
+        );
+
+        let (header, len) = parse(b"PROXY UNKNOWN ignored\r\n").unwrap().unwrap();
+        assert_eq!(header.source, None);
+        assert_eq!(len, 23);
+    }
+
+    #[test]
+    fn v1_incomplete_and_malformed() {
+        assert!(parse(b"").unwrap().is_none());
+        assert!(parse(b"PRO").unwrap().is_none());
+        assert!(parse(b"PROXY TCP4 1.2.3.4").unwrap().is_none());
+
+        assert!(parse(b"GET / HTTP/1.1\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 1.2.3.4 5.6.7.8 1 2 3\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 ::1 5.6.7.8 1 2\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 1.2.3.4 5.6.7.8 1 99999\r\n").is_err());
+        assert!(parse(b"PROXY UDP4 1.2.3.4 5.6.7.8 1 2\r\n").is_err());
+        assert!(parse(&[b'P', b'R', b'O', b'X', b'Y', b' '].repeat(20)).is_err());
+    }
+
+    #[test]
+    fn v2_inet_with_tlvs() {
+        let mut payload = vec![10, 0, 0, 1, 10, 0, 0, 2, 0x1f, 0x90, 0x00, 0x50];
+        payload.extend_from_slice(&[0x01, 0x00, 0x02, b'h', b'2']);
+        payload.extend_from_slice(&[0x04, 0x00, 0x00]);
+
+        let mut buf = v2(0x1, 0x1, &payload);
+        buf.extend_from_slice(b"rest");
+
+        let (header, len) = parse(&buf).unwrap().unwrap();
+        assert_eq!(header.version, ProxyVersion::V2);
+        assert_eq!(header.source, Some("10.0.0.1:8080".parse().unwrap()));
+        assert_eq!(header.destination, Some("10.0.0.2:80".parse().unwrap()));
+        assert_eq!(header.tlv(0x01), Some(&b"h2"[..]));
+        assert_eq!(header.tlv(0x04), Some(&b""[..]));
+        assert_eq!(header.tlv(0x05), None);
+        assert_eq!(&buf[len..], b"rest");
+
+        // every prefix of the header needs more bytes
+        for end in 0..len {
+            assert!(parse(&buf[..end]).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn v2_inet6_and_local() {
+        let mut payload = vec![0; 36];
+        payload[15] = 1;
+        payload[31] = 2;
+        payload[32..36].copy_from_slice(&[0, 1, 0, 2]);
+
+        let (header, _) = parse(&v2(0x1, 0x2, &payload)).unwrap().unwrap();
+        assert_eq!(header.source, Some("[::1]:1".parse().unwrap()));
+        assert_eq!(header.destination, Some("[::2]:2".parse().unwrap()));
+
+        let (header, len) = parse(&v2(0x0, 0x0, &[])).unwrap().unwrap();
+        assert_eq!(header.source, None);
+        assert_eq!(len, V2_HEADER_LEN);
+    }
+
+    #[test]
+    fn v2_malformed() {
+        // truncated addresses
+        assert!(parse(&v2(0x1, 0x1, &[0; 8])).is_err());
+
+        // truncated TLV
+        let mut payload = vec![0; 12];
+        payload.extend_from_slice(&[0x01, 0x00, 0x05, b'x']);
+        assert!(parse(&v2(0x1, 0x1, &payload)).is_err());
+
+        // unknown command and version
+        assert!(parse(&v2(0x2, 0x1, &[0; 12])).is_err());
+        let mut buf = v2(0x1, 0x1, &[0; 12]);
+        buf[12] = 0x11;
+        assert!(parse(&buf).is_err());
+    }
+}
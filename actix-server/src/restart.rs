@@ -0,0 +1,325 @@
+//! Restart policy of faulted workers and failing services.
+
+use std::{
+    collections::VecDeque,
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep_until, Instant, Sleep};
+use futures_core::ready;
+
+/// Action taken once a worker or service exceeds the restart limit of its [`RestartPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RestartLimitAction {
+    /// Stop the server gracefully.
+    StopServer,
+
+    /// Leave the worker down; the remaining workers keep serving connections.
+    ///
+    /// A replacement can be started with
+    /// [`ServerHandle::set_workers()`](crate::ServerHandle::set_workers()).
+    LeaveDown,
+
+    /// Keep restarting, still applying the backoff.
+    KeepTrying,
+}
+
+/// Policy for restarting workers that died and services that failed on a worker.
+///
+/// Restarts are delayed by an exponential backoff that resets once a restarted worker (or
+/// service) runs for longer than the maximum delay without faulting. Faults are also counted
+/// over a time window; once there are more than allowed, the [limit action] is taken.
+///
+/// The default policy restarts immediately, without limit.
+///
+/// A service that keeps failing on a worker is subject to the same policy; failing to re-create
+/// it counts as another fault. When its limit is reached, the worker exits, which is then
+/// handled as a fault of the worker.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_server::{RestartLimitAction, RestartPolicy, Server};
+///
+/// let policy = RestartPolicy::new()
+///     .backoff(Duration::from_millis(100), Duration::from_secs(10))
+///     .max_restarts(5, Duration::from_secs(60))
+///     .on_limit(RestartLimitAction::StopServer);
+///
+/// let builder = Server::build().restart_policy(policy);
+/// ```
+///
+/// [limit action]: Self::on_limit()
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_restarts: Option<(usize, Duration)>,
+    on_limit: RestartLimitAction,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RestartPolicy {
+    /// Creates policy that restarts immediately, without limit.
+    pub fn new() -> Self {
+        Self {
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            max_restarts: None,
+            on_limit: RestartLimitAction::KeepTrying,
+        }
+    }
+
+    /// Delays restarts by `initial`, doubling the delay on every consecutive fault up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Allows at most `num` restarts within any `window` of time.
+    ///
+    /// Once a fault would exceed it, the [limit action](Self::on_limit()) is taken.
+    pub fn max_restarts(mut self, num: usize, window: Duration) -> Self {
+        self.max_restarts = Some((num, window));
+        self
+    }
+
+    /// Sets action taken once the restart limit is exceeded.
+    ///
+    /// By default, restarts are kept trying.
+    pub fn on_limit(mut self, action: RestartLimitAction) -> Self {
+        self.on_limit = action;
+        self
+    }
+
+    pub(crate) fn limit_action(&self) -> RestartLimitAction {
+        self.on_limit
+    }
+
+    /// Records a fault in `history` and returns how to restart after it.
+    pub(crate) fn fault(&self, history: &mut FaultHistory, now: Instant) -> Fault {
+        // a restart that has run for longer than the max delay is considered healthy
+        if matches!(history.restarted_at, Some(at) if now.saturating_duration_since(at) > self.max_backoff)
+        {
+            history.attempt = 0;
+        }
+
+        let delay = self
+            .initial_backoff
+            .checked_mul(2u32.saturating_pow(history.attempt))
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff));
+
+        history.attempt = history.attempt.saturating_add(1);
+        history.restarted_at = Some(now + delay);
+
+        let limit_reached = match self.max_restarts {
+            Some((num, window)) => {
+                history.faults.push_back(now);
+
+                while matches!(history.faults.front(), Some(at) if now.saturating_duration_since(*at) > window)
+                {
+                    history.faults.pop_front();
+                }
+
+                history.faults.len() > num
+            }
+            None => false,
+        };
+
+        Fault {
+            delay,
+            limit_reached,
+        }
+    }
+}
+
+/// Faults of a worker or service.
+#[derive(Debug, Default)]
+pub(crate) struct FaultHistory {
+    /// Times of the faults within the restart window.
+    faults: VecDeque<Instant>,
+
+    /// Number of consecutive faults, determining the backoff.
+    attempt: u32,
+
+    /// When the last restart was due.
+    restarted_at: Option<Instant>,
+}
+
+/// How to restart after a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Fault {
+    pub(crate) delay: Duration,
+    pub(crate) limit_reached: bool,
+}
+
+/// Event of a worker's lifecycle, received from
+/// [`ServerHandle::worker_events()`](crate::ServerHandle::worker_events()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WorkerEvent {
+    /// Worker has died.
+    Died {
+        /// Index of the worker.
+        idx: usize,
+    },
+
+    /// Worker will be restarted after `delay`.
+    Restarting {
+        /// Index of the worker.
+        idx: usize,
+
+        /// Delay before the restart.
+        delay: Duration,
+    },
+
+    /// Worker has been restarted.
+    Restarted {
+        /// Index of the worker.
+        idx: usize,
+    },
+
+    /// Worker could not be restarted; this counts as another fault.
+    RestartFailed {
+        /// Index of the worker.
+        idx: usize,
+    },
+
+    /// Worker has not sent a heartbeat for `since`, longer than the stall timeout.
+    Stalled {
+        /// Index of the worker.
+        idx: usize,
+
+        /// Time since the worker's last heartbeat.
+        since: Duration,
+    },
+
+    /// Stalled worker has sent a heartbeat again.
+    Recovered {
+        /// Index of the worker.
+        idx: usize,
+    },
+
+    /// Worker has exceeded the restart limit and `action` is taken.
+    LimitReached {
+        /// Index of the worker.
+        idx: usize,
+
+        /// Action taken.
+        action: RestartLimitAction,
+    },
+}
+
+/// Workers waiting for their delayed restart.
+#[derive(Default)]
+pub(crate) struct RestartQueue {
+    pending: Vec<(Instant, usize)>,
+    timer: Option<Pin<Box<Sleep>>>,
+}
+
+impl RestartQueue {
+    /// Schedules restart of worker `idx` at `at`.
+    pub(crate) fn push(&mut self, idx: usize, at: Instant) {
+        self.pending.push((at, idx));
+    }
+
+    /// Cancels scheduled restart of worker `idx`.
+    pub(crate) fn cancel(&mut self, idx: usize) {
+        self.pending.retain(|(_, pending)| *pending != idx);
+    }
+
+    /// Resolves to the index of the next worker due for restart.
+    pub(crate) fn poll_due(&mut self, cx: &mut Context<'_>) -> Poll<usize> {
+        let pos = match (0..self.pending.len()).min_by_key(|pos| self.pending[*pos].0) {
+            Some(pos) => pos,
+            None => {
+                self.timer = None;
+                return Poll::Pending;
+            }
+        };
+
+        let at = self.pending[pos].0;
+
+        let timer = match self.timer {
+            Some(ref mut timer) => {
+                if timer.deadline() != at {
+                    timer.as_mut().reset(at);
+                }
+                timer
+            }
+            None => self.timer.insert(Box::pin(sleep_until(at))),
+        };
+
+        ready!(timer.as_mut().poll(cx));
+
+        Poll::Ready(self.pending.swap_remove(pos).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy() {
+        let policy = RestartPolicy::default();
+        let mut history = FaultHistory::default();
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            let fault = policy.fault(&mut history, now);
+            assert_eq!(fault.delay, Duration::ZERO);
+            assert!(!fault.limit_reached);
+        }
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = RestartPolicy::new().backoff(Duration::from_secs(1), Duration::from_secs(5));
+        let mut history = FaultHistory::default();
+        let now = Instant::now();
+
+        let delays = (0..5)
+            .map(|_| policy.fault(&mut history, now).delay.as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+        // worker has run for longer than the max delay since its last restart
+        let later = now + Duration::from_secs(11);
+        assert_eq!(policy.fault(&mut history, later).delay.as_secs(), 1);
+    }
+
+    #[test]
+    fn restart_limit() {
+        let policy = RestartPolicy::new().max_restarts(2, Duration::from_secs(10));
+        let mut history = FaultHistory::default();
+        let now = Instant::now();
+
+        assert!(!policy.fault(&mut history, now).limit_reached);
+        assert!(
+            !policy
+                .fault(&mut history, now + Duration::from_secs(1))
+                .limit_reached
+        );
+        assert!(
+            policy
+                .fault(&mut history, now + Duration::from_secs(2))
+                .limit_reached
+        );
+
+        // faults outside of the window are forgotten
+        let later = now + Duration::from_secs(12);
+        assert!(!policy.fault(&mut history, later).limit_reached);
+    }
+}
@@ -0,0 +1,178 @@
+//! Strategies for picking the worker that receives an accepted connection.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher as _, Hasher as _},
+};
+
+use crate::{availability::Availability, worker::WorkerHandleAccept};
+
+/// Strategy used by the accept thread to pick the worker that receives an accepted connection.
+///
+/// Set with [`ServerBuilder::worker_selector()`](crate::ServerBuilder::worker_selector()). The
+/// default strategy is [`RoundRobin`].
+pub trait WorkerSelector: Send + 'static {
+    /// Returns the position in `workers` of the worker that should receive the next connection.
+    ///
+    /// If the returned worker is unavailable, the connection goes to the next available worker
+    /// after it instead. When no worker is available, the returned worker receives the connection
+    /// regardless of its limit.
+    fn select(&mut self, workers: &Workers<'_>) -> usize;
+}
+
+/// View of the workers that can receive a connection.
+///
+/// Workers are addressed by their position, which is not stable: positions change when a faulted
+/// worker is removed or restarted.
+pub struct Workers<'a> {
+    handles: &'a [WorkerHandleAccept],
+    avail: &'a Availability,
+}
+
+impl<'a> Workers<'a> {
+    pub(crate) fn new(handles: &'a [WorkerHandleAccept], avail: &'a Availability) -> Self {
+        Self { handles, avail }
+    }
+
+    /// Returns number of workers.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns true if there are no workers.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Returns true if worker at `pos` is below its connection limit and ready for connections.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds.
+    pub fn is_available(&self, pos: usize) -> bool {
+        self.avail.get_available(self.handles[pos].idx())
+    }
+
+    /// Returns number of connections currently handled by worker at `pos`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds.
+    pub fn connections(&self, pos: usize) -> usize {
+        self.handles[pos].connections()
+    }
+}
+
+impl fmt::Debug for Workers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries((0..self.len()).map(|pos| (self.is_available(pos), self.connections(pos))))
+            .finish()
+    }
+}
+
+/// Hands connections to available workers in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl WorkerSelector for RoundRobin {
+    fn select(&mut self, workers: &Workers<'_>) -> usize {
+        let len = workers.len();
+
+        let pos = (0..len)
+            .map(|offset| (self.next + offset) % len)
+            .find(|pos| workers.is_available(*pos))
+            .unwrap_or(self.next % len);
+
+        self.next = (pos + 1) % len;
+        pos
+    }
+}
+
+/// Hands connections to the available worker currently handling the fewest connections.
+///
+/// Ties are broken in round-robin order so that idle workers share the load evenly.
+#[derive(Debug, Default)]
+pub struct LeastConnections {
+    next: usize,
+}
+
+impl WorkerSelector for LeastConnections {
+    fn select(&mut self, workers: &Workers<'_>) -> usize {
+        let len = workers.len();
+
+        let pos = (0..len)
+            .map(|offset| (self.next + offset) % len)
+            .filter(|pos| workers.is_available(*pos))
+            .min_by_key(|pos| workers.connections(*pos))
+            .unwrap_or(self.next % len);
+
+        self.next = (pos + 1) % len;
+        pos
+    }
+}
+
+/// Picks two available workers at random and hands the connection to the less loaded one.
+///
+/// Needs only two connection counts per connection, unlike [`LeastConnections`], while still
+/// avoiding most of the imbalance of a purely random choice.
+pub struct PowerOfTwoChoices {
+    state: u64,
+}
+
+impl PowerOfTwoChoices {
+    /// Creates a new strategy with a random seed.
+    pub fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+
+        Self {
+            // xorshift state must not be zero
+            state: seed | 1,
+        }
+    }
+
+    /// Returns a random position below `len`.
+    fn random(&mut self, len: usize) -> usize {
+        // xorshift64
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        (self.state % len as u64) as usize
+    }
+}
+
+impl Default for PowerOfTwoChoices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PowerOfTwoChoices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PowerOfTwoChoices").finish_non_exhaustive()
+    }
+}
+
+impl WorkerSelector for PowerOfTwoChoices {
+    fn select(&mut self, workers: &Workers<'_>) -> usize {
+        let len = workers.len();
+
+        let first = self.random(len);
+        let second = match len {
+            1 => return first,
+            // pick a different worker than the first one
+            _ => (first + 1 + self.random(len - 1)) % len,
+        };
+
+        match (workers.is_available(first), workers.is_available(second)) {
+            (true, false) => first,
+            (false, true) => second,
+            _ if workers.connections(second) < workers.connections(first) => second,
+            _ => first,
+        }
+    }
+}
@@ -1,36 +1,70 @@
 use std::{
-    future::Future,
+    collections::HashMap,
+    future::{poll_fn, Future},
     io, mem,
+    net::SocketAddr,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
     thread,
     time::Duration,
 };
 
-use actix_rt::{time::sleep, System};
+use actix_rt::{
+    time::{sleep, Instant},
+    System,
+};
 use futures_core::{future::BoxFuture, Stream};
-use futures_util::stream::StreamExt as _;
-use tokio::sync::{mpsc::UnboundedReceiver, oneshot};
-use tracing::{error, info};
+use futures_util::{
+    future::{select, Either},
+    pin_mut,
+    stream::StreamExt as _,
+};
+use tokio::sync::{broadcast, mpsc::UnboundedReceiver, oneshot};
+use tracing::{error, info, trace, warn};
 
 use crate::{
-    accept::Accept,
-    builder::ServerBuilder,
+    accept::{Accept, ServerSocketInfo},
+    access::{IpFilter, ListenerAccess},
+    builder::{bind_addr, MpTcp, ServerBuilder},
     join_all::join_all,
-    service::InternalServiceFactory,
-    signals::{SignalKind, Signals},
+    limits::IpLimiter,
+    outcome::OutcomeHook,
+    restart::{FaultHistory, RestartLimitAction, RestartQueue, WorkerEvent},
+    selector::RoundRobin,
+    service::{ClosedServiceFactory, InternalServiceFactory},
+    signals::{Signal, SignalAction, Signals},
+    socket::{MioListener, ReusePortListener},
+    socket_options::SocketOptions,
+    stats::{ListenerCounters, ServerStats, WorkerStats},
+    udp::InternalUdpServiceFactory,
     waker_queue::{WakerInterest, WakerQueue},
-    worker::{ServerWorker, ServerWorkerConfig, WorkerHandleServer},
+    worker::{
+        ServerWorker, ServerWorkerConfig, WorkerHandleAccept, WorkerHandleServer, WorkerHooks,
+    },
     ServerHandle,
 };
 
-#[derive(Debug)]
+/// Max number of workers, limited by the accept thread's availability tracking.
+pub(crate) const MAX_WORKERS: usize = 512;
+
+/// Min delay before retrying a worker that could not be restarted.
+const RESTART_FAILED_DELAY: Duration = Duration::from_secs(1);
+
+/// Creates the service factory of a listener added at runtime from its token.
+pub(crate) type MakeServiceFactory = Box<dyn Fn(usize) -> Box<dyn InternalServiceFactory> + Send>;
+
 pub(crate) enum ServerCommand {
     /// Worker failed to accept connection, indicating a probable panic.
     ///
     /// Contains index of faulted worker.
     WorkerFaulted(usize),
 
+    /// Backoff of a faulted worker has passed and it is due for restart.
+    ///
+    /// Contains index of faulted worker.
+    RestartWorker(usize),
+
     /// Pause accepting connections.
     ///
     /// Contains return channel to notify caller of successful state change.
@@ -41,13 +75,83 @@ pub(crate) enum ServerCommand {
     /// Contains return channel to notify caller of successful state change.
     Resume(oneshot::Sender<()>),
 
+    /// Take a snapshot of server statistics.
+    Stats(oneshot::Sender<ServerStats>),
+
+    /// Bind new TCP listeners and start their services on every worker.
+    Bind {
+        name: String,
+        addrs: Vec<SocketAddr>,
+        options: SocketOptions,
+        factory: MakeServiceFactory,
+
+        /// Return channel to notify caller of the bind outcome.
+        completion: oneshot::Sender<io::Result<()>>,
+    },
+
+    /// Start or stop workers until there are `num` of them.
+    SetWorkers {
+        num: usize,
+
+        /// Return channel to notify caller of the scaling outcome.
+        completion: oneshot::Sender<io::Result<()>>,
+    },
+
+    /// Replace IP filter of listeners with given name.
+    SetIpFilter {
+        name: String,
+        filter: IpFilter,
+
+        /// Return channel to notify caller of the outcome.
+        completion: oneshot::Sender<io::Result<()>>,
+    },
+
+    /// Close listeners with given name and stop their services.
+    Unbind {
+        name: String,
+
+        /// Return channel to notify caller of the unbind outcome.
+        completion: oneshot::Sender<io::Result<()>>,
+    },
+
     /// Stop accepting connections and begin shutdown procedure.
     Stop {
         /// True if shut down should be graceful.
         graceful: bool,
 
-        /// Return channel to notify caller that shutdown is complete.
-        completion: Option<oneshot::Sender<()>>,
+        /// Return channel to notify caller that shutdown is complete, and whether all connections
+        /// were finished without being dropped.
+        completion: Option<oneshot::Sender<bool>>,
+
+        /// Force System exit when true, overriding `ServerBuilder::system_exit()` if it is false.
+        force_system_stop: bool,
+    },
+
+    /// Check worker heartbeats and ping the systemd watchdog if all are alive.
+    #[cfg(unix)]
+    Watchdog,
+
+    /// Check worker heartbeats for stalled workers.
+    CheckStalls,
+
+    /// Hand listeners off to a new process and, once it is ready, shut down gracefully.
+    #[cfg(unix)]
+    Handoff {
+        /// Return channel to notify caller of the handoff outcome.
+        completion: Option<oneshot::Sender<io::Result<()>>>,
+
+        /// Force System exit when true, overriding `ServerBuilder::system_exit()` if it is false.
+        force_system_stop: bool,
+    },
+
+    /// New process of a handoff became ready or failed to.
+    #[cfg(unix)]
+    HandoffCompleted {
+        /// Outcome of waiting for the new process.
+        result: io::Result<()>,
+
+        /// Return channel to notify caller of the handoff outcome.
+        completion: Option<oneshot::Sender<io::Result<()>>>,
 
         /// Force System exit when true, overriding `ServerBuilder::system_exit()` if it is false.
         force_system_stop: bool,
@@ -60,7 +164,8 @@ pub(crate) enum ServerCommand {
 /// back-pressure logic.
 ///
 /// Creates a worker per CPU core (or the number specified in [`ServerBuilder::workers`]) and
-/// distributes connections with a round-robin strategy.
+/// distributes connections with a round-robin strategy by default. See
+/// [`ServerBuilder::worker_selector`] for other strategies.
 ///
 /// The [Server] must be awaited or polled in order to start running. It will resolve when the
 /// server has fully shut down.
@@ -69,7 +174,12 @@ pub(crate) enum ServerCommand {
 /// On UNIX systems, `SIGTERM` will start a graceful shutdown and `SIGQUIT` or `SIGINT` will start a
 /// forced shutdown. On Windows, a Ctrl-C signal will start a forced shutdown.
 ///
-/// A graceful shutdown will wait for all workers to stop first.
+/// A graceful shutdown will wait for all workers to stop first. Receiving a stop signal again in
+/// the meantime forces the shutdown.
+///
+/// Actions taken on signals, including `SIGHUP`, `SIGUSR1` and `SIGUSR2`, can be changed with
+/// [`ServerBuilder::signal`]; e.g. [`ServerBuilder::handoff_signal`] sets up `SIGUSR2` to start a
+/// listener handoff.
 ///
 /// # Examples
 /// The following is a TCP echo server. Test using `telnet 127.0.0.1 8080`.
@@ -135,7 +245,7 @@ impl Server {
 
     pub(crate) fn new(builder: ServerBuilder) -> Self {
         Server {
-            handle: ServerHandle::new(builder.cmd_tx.clone()),
+            handle: ServerHandle::new(builder.cmd_tx.clone(), builder.worker_events.clone()),
             fut: Box::pin(ServerInner::run(builder)),
         }
     }
@@ -159,20 +269,83 @@ impl Future for Server {
 
 pub struct ServerInner {
     worker_handles: Vec<WorkerHandleServer>,
+    /// Workers removed by scaling down that are still finishing their connections.
+    retiring: Vec<(WorkerHandleServer, oneshot::Receiver<bool>)>,
     accept_handle: Option<thread::JoinHandle<()>>,
     worker_config: ServerWorkerConfig,
+    worker_hooks: WorkerHooks,
     services: Vec<Box<dyn InternalServiceFactory>>,
+    udp_services: Vec<Box<dyn InternalUdpServiceFactory>>,
+    /// Listeners that every (restarted) worker binds by itself in `SO_REUSEPORT` mode.
+    reuse_port_listeners: Vec<(usize, ReusePortListener)>,
+    /// Tokens, names and counters of all listeners.
+    listeners: Vec<(usize, String, Arc<ListenerCounters>)>,
+    /// Access control of each listener name, also applied to listeners bound at runtime.
+    access: Vec<Arc<ListenerAccess>>,
+    /// Hook reporting connection outcomes, also set on listeners bound at runtime.
+    outcome_hook: Option<OutcomeHook>,
+    /// Faults of each worker index, for the restart policy.
+    worker_faults: HashMap<usize, FaultHistory>,
+    /// Faulted workers waiting for their backoff to pass.
+    restart_queue: RestartQueue,
+    worker_events: broadcast::Sender<WorkerEvent>,
+    /// Time after which a worker that has not beaten is considered stalled.
+    stall_timeout: Option<Duration>,
+    replace_stalled: bool,
+    signals: Option<Signals>,
+    signal_actions: Vec<(Signal, SignalAction)>,
+    /// Handle passed to signal callbacks.
+    handle: ServerHandle,
+    /// Settings for listeners bound at runtime.
+    backlog: u32,
+    mptcp: MpTcp,
     waker_queue: WakerQueue,
+    paused: bool,
     system_stop: bool,
     stopping: bool,
+    #[cfg(unix)]
+    handoff: crate::handoff::HandoffConfig,
+    /// Names and FDs of the listeners owned by the accept thread.
+    #[cfg(unix)]
+    listener_fds: Vec<(String, std::os::unix::io::RawFd)>,
+    /// Set once listeners were handed off to a new process.
+    #[cfg(unix)]
+    handed_off: bool,
+    /// Set while waiting for the new process of a handoff to become ready.
+    #[cfg(unix)]
+    handoff_pending: bool,
+    #[cfg(unix)]
+    notifier: Option<crate::systemd::Notifier>,
+    #[cfg(unix)]
+    watchdog_timeout: Option<Duration>,
+    #[cfg(unix)]
+    admin: Option<crate::admin::AdminSocket>,
 }
 
 impl ServerInner {
     async fn run(builder: ServerBuilder) -> io::Result<()> {
         let (mut this, mut mux) = Self::run_sync(builder)?;
 
-        while let Some(cmd) = mux.next().await {
-            this.handle_cmd(cmd).await;
+        loop {
+            let cmd = poll_fn(|cx| {
+                if let Poll::Ready(idx) = this.restart_queue.poll_due(cx) {
+                    return Poll::Ready(Some(ServerCommand::RestartWorker(idx)));
+                }
+
+                while let Some(Poll::Ready(sig)) = this.signals.as_mut().map(|s| s.poll_recv(cx)) {
+                    if let Some(cmd) = this.map_signal(sig) {
+                        return Poll::Ready(Some(cmd));
+                    }
+                }
+
+                mux.poll_next_unpin(cx)
+            })
+            .await;
+
+            match cmd {
+                Some(cmd) => this.handle_cmd(cmd).await,
+                None => break,
+            }
 
             if this.stopping {
                 break;
@@ -183,11 +356,6 @@ impl ServerInner {
     }
 
     fn run_sync(mut builder: ServerBuilder) -> io::Result<(Self, ServerEventMultiplexer)> {
-        let sockets = mem::take(&mut builder.sockets)
-            .into_iter()
-            .map(|t| (t.0, t.2))
-            .collect();
-
         // Give log information on what runtime will be used.
         let is_actix = actix_rt::System::try_current().is_some();
         let is_tokio = tokio::runtime::Handle::try_current().is_ok();
@@ -198,6 +366,12 @@ impl ServerInner {
             (_, false) => panic!("Actix or Tokio runtime not found; halting"),
         }
 
+        if let Some(ref hook) = builder.outcome_hook {
+            for factory in &mut builder.factories {
+                factory.set_outcome_hook(hook.clone());
+            }
+        }
+
         for (_, name, lst) in &builder.sockets {
             info!(
                 r#"starting service: "{}", workers: {}, listening on: {}"#,
@@ -207,21 +381,164 @@ impl ServerInner {
             );
         }
 
-        let (waker_queue, worker_handles, accept_handle) = Accept::start(sockets, &builder)?;
+        for factory in &builder.udp_factories {
+            info!(
+                r#"starting datagram service: "{}", workers: {}, listening on: {}"#,
+                factory.name(),
+                builder.threads,
+                factory.local_addr()
+            );
+        }
+
+        for (token, lst) in &builder.reuse_port_listeners {
+            info!(
+                r#"starting service: "{}", workers: {}, listening on: {} (SO_REUSEPORT)"#,
+                builder.factories[*token].name(*token),
+                builder.threads,
+                lst.addr
+            );
+        }
+
+        #[cfg(unix)]
+        let listener_fds = builder
+            .sockets
+            .iter()
+            .filter_map(|(_, name, lst)| Some((name.clone(), lst.as_raw_fd()?)))
+            .collect();
+
+        #[cfg(unix)]
+        let notifier = builder
+            .systemd_notify
+            .then(crate::systemd::Notifier::from_env)
+            .flatten();
+
+        #[cfg(unix)]
+        let watchdog_timeout = notifier
+            .as_ref()
+            .and_then(|_| crate::systemd::Notifier::watchdog_timeout());
+
+        // workers must beat well within the watchdog timeout for pings to be sent in time
+        #[cfg(unix)]
+        if let Some(timeout) = watchdog_timeout {
+            builder.worker_config.heartbeat_interval(timeout / 4);
+        }
+
+        let mut ip_limits = mem::take(&mut builder.ip_limits);
+        let mut access = Vec::new();
+
+        for (name, filter) in mem::take(&mut builder.ip_filters) {
+            let limiter = ip_limits
+                .iter()
+                .position(|(lst_name, _)| *lst_name == name)
+                .map(|pos| IpLimiter::new(ip_limits.remove(pos).1));
+            access.push(Arc::new(ListenerAccess::new(name, filter, limiter)));
+        }
+
+        for (name, limits) in ip_limits {
+            let limiter = Some(IpLimiter::new(limits));
+            access.push(Arc::new(ListenerAccess::new(
+                name,
+                IpFilter::new(),
+                limiter,
+            )));
+        }
+
+        let mut listeners = Vec::new();
+
+        let sockets = mem::take(&mut builder.sockets)
+            .into_iter()
+            .map(|(token, name, lst)| {
+                let counters = Arc::new(ListenerCounters::default());
+                let access = listener_access(&mut access, &name);
+                listeners.push((token, name, counters.clone()));
+                ServerSocketInfo::new(token, lst, counters, access)
+            })
+            .collect();
+
+        for (token, lst) in &mut builder.reuse_port_listeners {
+            let name = builder.factories[*token].name(*token).to_owned();
+            lst.access = Some(listener_access(&mut access, &name));
+            listeners.push((*token, name, lst.counters.clone()));
+        }
+
+        listeners.sort_by_key(|(token, _, _)| *token);
+
+        let selector = mem::replace(&mut builder.selector, Box::new(RoundRobin::default()));
+
+        let (waker_queue, worker_handles, accept_handle) =
+            Accept::start(sockets, selector, &builder)?;
+
+        #[cfg(unix)]
+        let admin = builder
+            .admin
+            .take()
+            .map(|admin| admin.start(builder.cmd_tx.clone()))
+            .transpose()?;
+
+        // all workers have started their services at this point
+        #[cfg(unix)]
+        crate::handoff::notify_ready();
+
+        #[cfg(unix)]
+        if let Some(notifier) = &notifier {
+            notifier.notify(&format!("READY=1\nMAINPID={}", std::process::id()));
+        }
+
+        let signals = builder
+            .listen_os_signals
+            .then(|| Signals::new(builder.signal_actions.iter().map(|(sig, _)| *sig)));
+
+        let handle = ServerHandle::new(builder.cmd_tx.clone(), builder.worker_events.clone());
 
         let mux = ServerEventMultiplexer {
-            signal_fut: (builder.listen_os_signals).then(Signals::new),
             cmd_rx: builder.cmd_rx,
+            #[cfg(unix)]
+            watchdog: watchdog_timeout.map(|timeout| actix_rt::time::interval(timeout / 2)),
+            stall_check: builder
+                .stall_timeout
+                .map(|timeout| actix_rt::time::interval(timeout / 4)),
         };
 
         let server = ServerInner {
             waker_queue,
             accept_handle: Some(accept_handle),
             worker_handles,
+            retiring: Vec::new(),
             worker_config: builder.worker_config,
+            worker_hooks: builder.worker_hooks,
             services: builder.factories,
+            udp_services: builder.udp_factories,
+            reuse_port_listeners: builder.reuse_port_listeners,
+            listeners,
+            access,
+            outcome_hook: builder.outcome_hook,
+            worker_faults: HashMap::new(),
+            restart_queue: RestartQueue::default(),
+            worker_events: builder.worker_events,
+            stall_timeout: builder.stall_timeout,
+            replace_stalled: builder.replace_stalled,
+            signals,
+            signal_actions: builder.signal_actions,
+            handle,
+            backlog: builder.backlog,
+            mptcp: builder.mptcp,
+            paused: false,
             system_stop: builder.exit,
             stopping: false,
+            #[cfg(unix)]
+            handoff: builder.handoff,
+            #[cfg(unix)]
+            listener_fds,
+            #[cfg(unix)]
+            handed_off: false,
+            #[cfg(unix)]
+            handoff_pending: false,
+            #[cfg(unix)]
+            notifier,
+            #[cfg(unix)]
+            watchdog_timeout,
+            #[cfg(unix)]
+            admin,
         };
 
         Ok((server, mux))
@@ -230,124 +547,785 @@ impl ServerInner {
     async fn handle_cmd(&mut self, item: ServerCommand) {
         match item {
             ServerCommand::Pause(tx) => {
+                self.paused = true;
                 self.waker_queue.wake(WakerInterest::Pause);
+                self.worker_handles
+                    .iter()
+                    .for_each(WorkerHandleServer::pause);
                 let _ = tx.send(());
             }
 
             ServerCommand::Resume(tx) => {
+                self.paused = false;
                 self.waker_queue.wake(WakerInterest::Resume);
+                self.worker_handles
+                    .iter()
+                    .for_each(WorkerHandleServer::resume);
                 let _ = tx.send(());
             }
 
+            ServerCommand::Stats(tx) => {
+                let _ = tx.send(self.stats());
+            }
+
+            ServerCommand::Bind {
+                name,
+                addrs,
+                options,
+                factory,
+                completion,
+            } => {
+                let res = self.bind(name, addrs, &options, factory).await;
+                let _ = completion.send(res);
+            }
+
+            ServerCommand::SetIpFilter {
+                name,
+                filter,
+                completion,
+            } => {
+                let res = match self.access.iter().find(|acc| acc.name() == name) {
+                    Some(acc) => {
+                        info!(r#"replacing IP filter of "{}""#, name);
+                        acc.set_filter(filter);
+                        Ok(())
+                    }
+                    None => Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!(r#"no listener named "{name}""#),
+                    )),
+                };
+
+                let _ = completion.send(res);
+            }
+
+            ServerCommand::Unbind { name, completion } => {
+                let res = self.unbind(&name).await;
+                let _ = completion.send(res);
+            }
+
+            ServerCommand::SetWorkers { num, completion } => {
+                let res = self.set_workers(num).await;
+                let _ = completion.send(res);
+            }
+
             ServerCommand::Stop {
                 graceful,
                 completion,
                 force_system_stop,
             } => {
-                self.stopping = true;
+                self.stop(graceful, completion, force_system_stop).await;
+            }
 
-                // Signal accept thread to stop.
-                // Signal is non-blocking; we wait for thread to stop later.
-                self.waker_queue.wake(WakerInterest::Stop);
+            #[cfg(unix)]
+            ServerCommand::Watchdog => self.ping_watchdog(),
 
-                // send stop signal to workers
-                let workers_stop = self
-                    .worker_handles
-                    .iter()
-                    .map(|worker| worker.stop(graceful))
-                    .collect::<Vec<_>>();
+            ServerCommand::CheckStalls => self.check_stalls().await,
 
-                if graceful {
-                    // wait for all workers to shut down
-                    let _ = join_all(workers_stop).await;
+            #[cfg(unix)]
+            ServerCommand::Handoff {
+                completion,
+                force_system_stop,
+            } => {
+                if self.handoff_pending {
+                    warn!("listener handoff is already in progress");
+
+                    if let Some(tx) = completion {
+                        let _ = tx.send(Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            "listener handoff is already in progress",
+                        )));
+                    }
+
+                    return;
                 }
 
-                // wait for accept thread stop
-                self.accept_handle
-                    .take()
-                    .unwrap()
-                    .join()
-                    .expect("Accept thread must not panic in any case");
+                info!("handing off listeners to new process");
+
+                match crate::handoff::spawn_successor(&self.handoff, &self.listener_fds) {
+                    Ok(successor) => {
+                        self.handoff_pending = true;
+
+                        // other commands are handled while waiting for the new process
+                        let handle = self.handle.clone();
+                        let ready_timeout = self.handoff.ready_timeout;
+
+                        tokio::spawn(async move {
+                            let result = successor.ready(ready_timeout).await;
+                            handle.handoff_completed(result, completion, force_system_stop);
+                        });
+                    }
+
+                    Err(err) => {
+                        error!("listener handoff failed: {}", err);
 
-                if let Some(tx) = completion {
-                    let _ = tx.send(());
+                        if let Some(tx) = completion {
+                            let _ = tx.send(Err(err));
+                        }
+                    }
                 }
+            }
+
+            #[cfg(unix)]
+            ServerCommand::HandoffCompleted {
+                result,
+                completion,
+                force_system_stop,
+            } => {
+                self.handoff_pending = false;
+
+                match result {
+                    Ok(()) => {
+                        info!("new process is ready; starting graceful shutdown");
+                        self.handed_off = true;
+
+                        if let Some(tx) = completion {
+                            let _ = tx.send(Ok(()));
+                        }
+
+                        self.stop(true, None, force_system_stop).await;
+                    }
+
+                    Err(err) => {
+                        error!("listener handoff failed: {}", err);
 
-                if self.system_stop || force_system_stop {
-                    sleep(Duration::from_millis(300)).await;
-                    System::try_current().as_ref().map(System::stop);
+                        if let Some(tx) = completion {
+                            let _ = tx.send(Err(err));
+                        }
+                    }
                 }
             }
 
             ServerCommand::WorkerFaulted(idx) => {
-                // TODO: maybe just return with warning log if not found ?
-                assert!(self.worker_handles.iter().any(|wrk| wrk.idx == idx));
+                // worker may have been removed by scaling down in the meantime
+                if !self.worker_handles.iter().any(|wrk| wrk.idx == idx) {
+                    warn!("worker {} has died after it was removed", idx);
+                    return;
+                }
 
-                error!("worker {} has died; restarting", idx);
+                error!("worker {} has died", idx);
+                self.emit(WorkerEvent::Died { idx });
 
-                let factories = self
-                    .services
-                    .iter()
-                    .map(|service| service.clone_factory())
-                    .collect();
-
-                match ServerWorker::start(
-                    idx,
-                    factories,
-                    self.waker_queue.clone(),
-                    self.worker_config,
-                ) {
-                    Ok((handle_accept, handle_server)) => {
-                        *self
-                            .worker_handles
-                            .iter_mut()
-                            .find(|wrk| wrk.idx == idx)
-                            .unwrap() = handle_server;
-
-                        self.waker_queue.wake(WakerInterest::Worker(handle_accept));
+                self.worker_faulted(idx, false).await;
+            }
+
+            ServerCommand::RestartWorker(idx) => {
+                if self.worker_handles.iter().any(|wrk| wrk.idx == idx) && !self.restart_worker(idx)
+                {
+                    self.worker_faulted(idx, true).await;
+                }
+            }
+        }
+    }
+
+    /// Restarts a faulted worker according to the restart policy.
+    ///
+    /// `start_failed` is true if the fault is a failed restart.
+    async fn worker_faulted(&mut self, idx: usize, mut start_failed: bool) {
+        let policy = self.worker_config.get_restart_policy();
+
+        loop {
+            let history = self.worker_faults.entry(idx).or_default();
+            let fault = policy.fault(history, Instant::now());
+
+            if fault.limit_reached {
+                let action = policy.limit_action();
+                error!("worker {} exceeded its restart limit; {:?}", idx, action);
+                self.emit(WorkerEvent::LimitReached { idx, action });
+
+                match action {
+                    RestartLimitAction::StopServer => {
+                        self.stop(true, None, false).await;
+                        return;
                     }
 
-                    Err(err) => error!("can not restart worker {}: {}", idx, err),
-                };
+                    RestartLimitAction::LeaveDown => {
+                        self.worker_handles.retain(|wrk| wrk.idx != idx);
+                        self.worker_faults.remove(&idx);
+                        return;
+                    }
+
+                    RestartLimitAction::KeepTrying => {}
+                }
+            }
+
+            // services that fail to start are not retried right away so the server is not blocked
+            let delay = if start_failed {
+                fault.delay.max(RESTART_FAILED_DELAY)
+            } else {
+                fault.delay
+            };
+
+            info!("restarting worker {} in {:?}", idx, delay);
+            self.emit(WorkerEvent::Restarting { idx, delay });
+
+            if !delay.is_zero() {
+                self.restart_queue.push(idx, Instant::now() + delay);
+                return;
             }
+
+            if self.restart_worker(idx) {
+                return;
+            }
+
+            start_failed = true;
         }
     }
 
-    fn map_signal(signal: SignalKind) -> ServerCommand {
-        match signal {
-            SignalKind::Int => {
-                info!("SIGINT received; starting forced shutdown");
-                ServerCommand::Stop {
-                    graceful: false,
-                    completion: None,
-                    force_system_stop: true,
+    /// Starts worker `idx` in place of its faulted instance.
+    ///
+    /// Returns false if it could not be started.
+    fn restart_worker(&mut self, idx: usize) -> bool {
+        match self.start_worker(idx) {
+            Ok((handle_accept, mut handle_server)) => {
+                if self.paused {
+                    handle_server.pause();
+                }
+
+                let wrk = self
+                    .worker_handles
+                    .iter_mut()
+                    .find(|wrk| wrk.idx == idx)
+                    .unwrap();
+
+                handle_server.restarts = wrk.restarts + 1;
+                *wrk = handle_server;
+
+                self.waker_queue.wake(WakerInterest::Worker(handle_accept));
+                self.emit(WorkerEvent::Restarted { idx });
+
+                true
+            }
+
+            Err(err) => {
+                error!("can not restart worker {}: {}", idx, err);
+                self.emit(WorkerEvent::RestartFailed { idx });
+
+                false
+            }
+        }
+    }
+
+    fn emit(&self, event: WorkerEvent) {
+        // no receivers is not an error
+        let _ = self.worker_events.send(event);
+    }
+
+    /// Binds listeners to `addrs` and starts their services on every worker before accepting on
+    /// them.
+    async fn bind(
+        &mut self,
+        name: String,
+        addrs: Vec<SocketAddr>,
+        options: &SocketOptions,
+        make_factory: MakeServiceFactory,
+    ) -> io::Result<()> {
+        let sockets = bind_addr(&addrs[..], self.backlog, &self.mptcp, options)?;
+        let mut bound = Vec::with_capacity(sockets.len());
+
+        for lst in sockets {
+            let token = self.services.len();
+            let mut factory = make_factory(token);
+
+            if let Some(ref hook) = self.outcome_hook {
+                factory.set_outcome_hook(hook.clone());
+            }
+
+            let added = join_all(
+                self.worker_handles
+                    .iter()
+                    .map(|wrk| wrk.add_service(factory.clone_factory()))
+                    .collect(),
+            )
+            .await;
+
+            self.services.push(factory);
+            bound.push((token, MioListener::Tcp(lst)));
+
+            if !added.into_iter().all(|res| res.unwrap_or(false)) {
+                let tokens = bound.iter().map(|(token, _)| *token).collect::<Vec<_>>();
+                self.remove_services(&tokens).await;
+
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(r#"can not start service "{name}" on all workers"#),
+                ));
+            }
+        }
+
+        let sockets = bound
+            .into_iter()
+            .map(|(token, lst)| {
+                info!(
+                    r#"starting service: "{}", workers: {}, listening on: {}"#,
+                    name,
+                    self.worker_handles.len(),
+                    lst.local_addr()
+                );
+
+                #[cfg(unix)]
+                if let Some(fd) = lst.as_raw_fd() {
+                    self.listener_fds.push((name.clone(), fd));
                 }
+
+                let counters = Arc::new(ListenerCounters::default());
+                let access = listener_access(&mut self.access, &name);
+                self.listeners.push((token, name.clone(), counters.clone()));
+                ServerSocketInfo::new(token, lst, counters, access)
+            })
+            .collect();
+
+        self.waker_queue.wake(WakerInterest::Bind(sockets));
+
+        Ok(())
+    }
+
+    /// Closes listeners named `name` and stops their services on every worker.
+    async fn unbind(&mut self, name: &str) -> io::Result<()> {
+        let tokens = self
+            .listeners
+            .iter()
+            .filter(|(_, lst_name, _)| lst_name == name)
+            .map(|(token, _, _)| *token)
+            .collect::<Vec<_>>();
+
+        if tokens.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(r#"no listener named "{name}""#),
+            ));
+        }
+
+        info!(r#"stopping service: "{}""#, name);
+
+        // stop accepting before services go away
+        let (tx, rx) = oneshot::channel();
+        self.waker_queue
+            .wake(WakerInterest::Unbind(tokens.clone(), tx));
+        let _ = rx.await;
+
+        self.listeners
+            .retain(|(token, _, _)| !tokens.contains(token));
+        self.reuse_port_listeners
+            .retain(|(token, _)| !tokens.contains(token));
+        #[cfg(unix)]
+        self.listener_fds.retain(|(lst_name, _)| lst_name != name);
+
+        self.remove_services(&tokens).await;
+
+        Ok(())
+    }
+
+    /// Replaces services with closed ones on every worker, keeping other services' tokens stable.
+    async fn remove_services(&mut self, tokens: &[usize]) {
+        for &token in tokens {
+            let name = self.services[token].name(token).to_owned();
+            self.services[token] = ClosedServiceFactory::create(name, token);
+
+            join_all(
+                self.worker_handles
+                    .iter()
+                    .map(|wrk| wrk.remove_service(token))
+                    .collect(),
+            )
+            .await;
+        }
+    }
+
+    /// Starts a worker running all services.
+    fn start_worker(&self, idx: usize) -> io::Result<(WorkerHandleAccept, WorkerHandleServer)> {
+        let factories = self
+            .services
+            .iter()
+            .map(|service| service.clone_factory())
+            .collect();
+
+        let udp_factories = self
+            .udp_services
+            .iter()
+            .map(|factory| factory.clone_factory())
+            .collect();
+
+        ServerWorker::start(
+            idx,
+            factories,
+            udp_factories,
+            &self.reuse_port_listeners,
+            self.waker_queue.clone(),
+            self.worker_config,
+            self.worker_hooks.clone(),
+        )
+    }
+
+    /// Starts new workers or retires the ones with the highest indexes until there are `num`.
+    ///
+    /// Retired workers stop receiving connections before this returns and finish the ones they
+    /// have in the background.
+    async fn set_workers(&mut self, num: usize) -> io::Result<()> {
+        // drop workers that have finished retiring
+        self.retiring.retain_mut(|(_, rx)| {
+            matches!(rx.try_recv(), Err(oneshot::error::TryRecvError::Empty))
+        });
+
+        while self.worker_handles.len() < num {
+            let idx = (0..MAX_WORKERS)
+                .find(|idx| {
+                    !self.worker_handles.iter().any(|wrk| wrk.idx == *idx)
+                        && !self.retiring.iter().any(|(wrk, _)| wrk.idx == *idx)
+                })
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        "no worker index is free; wait for retiring workers to stop",
+                    )
+                })?;
+
+            let (handle_accept, handle_server) = self.start_worker(idx)?;
+
+            if self.paused {
+                handle_server.pause();
             }
 
-            SignalKind::Term => {
-                info!("SIGTERM received; starting graceful shutdown");
-                ServerCommand::Stop {
+            info!("starting worker {}", idx);
+
+            self.worker_handles.push(handle_server);
+            self.waker_queue.wake(WakerInterest::Worker(handle_accept));
+        }
+
+        while self.worker_handles.len() > num {
+            let pos = (0..self.worker_handles.len())
+                .max_by_key(|pos| self.worker_handles[*pos].idx)
+                .unwrap();
+            let wrk = self.worker_handles.swap_remove(pos);
+
+            info!("retiring worker {}", wrk.idx);
+            self.worker_faults.remove(&wrk.idx);
+            self.restart_queue.cancel(wrk.idx);
+
+            // stop sending connections before the worker is told to stop
+            let (tx, rx) = oneshot::channel();
+            self.waker_queue
+                .wake(WakerInterest::RemoveWorker(wrk.idx, tx));
+            let handle_accept = rx.await;
+
+            // worker exits right away once all its handles are gone, so it must see the stop first
+            let stopped = wrk.stop(true);
+            drop(handle_accept);
+            self.retiring.push((wrk, stopped));
+        }
+
+        Ok(())
+    }
+
+    fn stats(&self) -> ServerStats {
+        let mut workers = self
+            .worker_handles
+            .iter()
+            .map(|wrk| WorkerStats {
+                idx: wrk.idx,
+                connections: wrk.connections(),
+                restarts: wrk.restarts,
+                stalled: wrk.stalled,
+            })
+            .collect::<Vec<_>>();
+
+        workers.sort_by_key(|wrk| wrk.idx);
+
+        let listeners = self
+            .listeners
+            .iter()
+            .map(|(token, name, counters)| counters.snapshot(*token, name.clone()))
+            .collect();
+
+        ServerStats {
+            paused: self.paused,
+            workers,
+            listeners,
+        }
+    }
+
+    async fn stop(
+        &mut self,
+        graceful: bool,
+        completion: Option<oneshot::Sender<bool>>,
+        force_system_stop: bool,
+    ) {
+        self.stopping = true;
+
+        // close admin socket so no more commands are taken
+        #[cfg(unix)]
+        drop(self.admin.take());
+
+        // the new process is now the one serving on behalf of the service
+        #[cfg(unix)]
+        if let (Some(notifier), false) = (&self.notifier, self.handed_off) {
+            notifier.notify("STOPPING=1");
+        }
+
+        // Signal accept thread to stop.
+        // Signal is non-blocking; we wait for thread to stop later.
+        self.waker_queue.wake(self.accept_stop_interest());
+
+        // send stop signal to workers
+        let mut workers_stop = self
+            .worker_handles
+            .iter()
+            .map(|worker| worker.stop(graceful))
+            .collect::<Vec<_>>();
+
+        // retiring workers are already stopping gracefully
+        let retiring = self
+            .retiring
+            .drain(..)
+            .map(|(worker, stopped)| {
+                if graceful {
+                    workers_stop.push(stopped);
+                } else {
+                    worker.stop(false);
+                }
+
+                worker
+            })
+            .collect::<Vec<_>>();
+
+        let mut completed = false;
+
+        if graceful {
+            // wait for all workers to shut down, unless another stop signal forces the shutdown
+            let stopped = join_all(workers_stop);
+            let forced = poll_fn(|cx| self.poll_stop_signal(cx));
+            pin_mut!(stopped, forced);
+
+            match select(stopped, forced).await {
+                Either::Left((stopped, _)) => {
+                    completed = stopped.into_iter().all(|res| res.unwrap_or(false));
+                }
+
+                Either::Right(_) => {
+                    self.worker_handles
+                        .iter()
+                        .chain(&retiring)
+                        .for_each(|worker| drop(worker.stop(false)));
+                }
+            }
+        }
+
+        // wait for accept thread stop
+        self.accept_handle
+            .take()
+            .unwrap()
+            .join()
+            .expect("Accept thread must not panic in any case");
+
+        if let Some(tx) = completion {
+            let _ = tx.send(completed);
+        }
+
+        if self.system_stop || force_system_stop {
+            sleep(Duration::from_millis(300)).await;
+            System::try_current().as_ref().map(System::stop);
+        }
+    }
+
+    /// Stops sending connections to workers that missed their heartbeats for longer than the
+    /// stall timeout, replacing them if configured to, and resumes once they beat again.
+    async fn check_stalls(&mut self) {
+        let timeout = match self.stall_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+
+        let mut stalled = Vec::new();
+        let mut recovered = Vec::new();
+
+        for wrk in &mut self.worker_handles {
+            let since = wrk.since_heartbeat();
+
+            if since > timeout && !wrk.stalled {
+                wrk.stalled = true;
+                stalled.push((wrk.idx, since));
+            } else if since <= timeout && wrk.stalled && !self.replace_stalled {
+                // replaced workers are not taken back
+                wrk.stalled = false;
+                recovered.push(wrk.idx);
+            }
+        }
+
+        for idx in recovered {
+            info!("worker {} has recovered", idx);
+            self.waker_queue.wake(WakerInterest::WorkerRecovered(idx));
+            self.emit(WorkerEvent::Recovered { idx });
+        }
+
+        for (idx, since) in stalled {
+            warn!(
+                "worker {} has not responded for {:?}; marking it stalled",
+                idx, since
+            );
+            self.waker_queue.wake(WakerInterest::WorkerStalled(idx));
+            self.emit(WorkerEvent::Stalled { idx, since });
+
+            if self.replace_stalled && !self.stopping {
+                error!("replacing stalled worker {}", idx);
+
+                // stop sending connections before the worker is told to stop
+                let (tx, rx) = oneshot::channel();
+                self.waker_queue.wake(WakerInterest::RemoveWorker(idx, tx));
+                let handle_accept = rx.await;
+
+                if let Some(wrk) = self.worker_handles.iter().find(|wrk| wrk.idx == idx) {
+                    // only takes effect once its event loop is unblocked
+                    drop(wrk.stop(false));
+                }
+                drop(handle_accept);
+
+                self.worker_faulted(idx, false).await;
+            }
+        }
+    }
+
+    /// Pings the systemd watchdog unless a worker has stopped beating.
+    #[cfg(unix)]
+    fn ping_watchdog(&self) {
+        let (notifier, timeout) = match (&self.notifier, self.watchdog_timeout) {
+            (Some(notifier), Some(timeout)) => (notifier, timeout),
+            _ => return,
+        };
+
+        let stalled = self
+            .worker_handles
+            .iter()
+            .find(|worker| worker.since_heartbeat() > timeout / 2);
+
+        match stalled {
+            Some(worker) => warn!(
+                "worker {} has not responded for {:?}; withholding watchdog ping",
+                worker.idx,
+                worker.since_heartbeat()
+            ),
+            None => notifier.notify("WATCHDOG=1"),
+        }
+    }
+
+    /// Interest that makes the accept thread stop.
+    ///
+    /// Listeners handed off to a new process are left registered so that UDS paths are kept.
+    fn accept_stop_interest(&self) -> WakerInterest {
+        #[cfg(unix)]
+        if self.handed_off {
+            return WakerInterest::Detach;
+        }
+
+        WakerInterest::Stop
+    }
+
+    fn signal_action(&self, signal: Signal) -> Option<&SignalAction> {
+        signal_action(&self.signal_actions, signal)
+    }
+
+    /// Returns command for the action set for `signal`, running it right away if it is not one.
+    fn map_signal(&self, signal: Signal) -> Option<ServerCommand> {
+        match self.signal_action(signal)? {
+            SignalAction::GracefulStop => {
+                info!("{} received; starting graceful shutdown", signal);
+                Some(ServerCommand::Stop {
                     graceful: true,
                     completion: None,
                     force_system_stop: true,
-                }
+                })
             }
 
-            SignalKind::Quit => {
-                info!("SIGQUIT received; starting forced shutdown");
-                ServerCommand::Stop {
+            SignalAction::ForceStop => {
+                info!("{} received; starting forced shutdown", signal);
+                Some(ServerCommand::Stop {
                     graceful: false,
                     completion: None,
                     force_system_stop: true,
+                })
+            }
+
+            SignalAction::Pause => {
+                info!("{} received; pausing accepting connections", signal);
+                Some(ServerCommand::Pause(oneshot::channel().0))
+            }
+
+            SignalAction::Resume => {
+                info!("{} received; resuming accepting connections", signal);
+                Some(ServerCommand::Resume(oneshot::channel().0))
+            }
+
+            #[cfg(unix)]
+            SignalAction::Handoff => {
+                info!("{} received; starting listener handoff", signal);
+                Some(ServerCommand::Handoff {
+                    completion: None,
+                    force_system_stop: true,
+                })
+            }
+
+            SignalAction::Callback(callback) => {
+                trace!("{} received; running callback", signal);
+                callback(&self.handle);
+                None
+            }
+
+            SignalAction::Ignore => {
+                trace!("{} received; ignoring", signal);
+                None
+            }
+        }
+    }
+
+    /// Resolves once a stop signal is received, ignoring other signals.
+    fn poll_stop_signal(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let signals = match self.signals {
+            Some(ref mut signals) => signals,
+            None => return Poll::Pending,
+        };
+
+        while let Poll::Ready(signal) = signals.poll_recv(cx) {
+            match signal_action(&self.signal_actions, signal) {
+                Some(SignalAction::GracefulStop | SignalAction::ForceStop) => {
+                    info!(
+                        "{} received during graceful shutdown; forcing shutdown",
+                        signal
+                    );
+                    return Poll::Ready(());
                 }
+
+                _ => trace!("{} received during shutdown; ignoring", signal),
             }
         }
+
+        Poll::Pending
+    }
+}
+
+fn signal_action(actions: &[(Signal, SignalAction)], signal: Signal) -> Option<&SignalAction> {
+    actions
+        .iter()
+        .find(|(sig, _)| *sig == signal)
+        .map(|(_, action)| action)
+}
+
+/// Returns access control of the listeners named `name`, adding one accepting all peers if there
+/// is none yet.
+fn listener_access(access: &mut Vec<Arc<ListenerAccess>>, name: &str) -> Arc<ListenerAccess> {
+    match access.iter().find(|acc| acc.name() == name) {
+        Some(acc) => acc.clone(),
+        None => {
+            let acc = Arc::new(ListenerAccess::new(name.to_owned(), IpFilter::new(), None));
+            access.push(acc.clone());
+            acc
+        }
     }
 }
 
 struct ServerEventMultiplexer {
     cmd_rx: UnboundedReceiver<ServerCommand>,
-    signal_fut: Option<Signals>,
+    #[cfg(unix)]
+    watchdog: Option<actix_rt::time::Interval>,
+    stall_check: Option<actix_rt::time::Interval>,
 }
 
 impl Stream for ServerEventMultiplexer {
@@ -356,10 +1334,16 @@ impl Stream for ServerEventMultiplexer {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = Pin::into_inner(self);
 
-        if let Some(signal_fut) = &mut this.signal_fut {
-            if let Poll::Ready(signal) = Pin::new(signal_fut).poll(cx) {
-                this.signal_fut = None;
-                return Poll::Ready(Some(ServerInner::map_signal(signal)));
+        #[cfg(unix)]
+        if let Some(watchdog) = &mut this.watchdog {
+            if watchdog.poll_tick(cx).is_ready() {
+                return Poll::Ready(Some(ServerCommand::Watchdog));
+            }
+        }
+
+        if let Some(stall_check) = &mut this.stall_check {
+            if stall_check.poll_tick(cx).is_ready() {
+                return Poll::Ready(Some(ServerCommand::CheckStalls));
             }
         }
 
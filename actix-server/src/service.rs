@@ -1,7 +1,8 @@
 use std::{
     marker::PhantomData,
-    net::SocketAddr,
+    sync::Arc,
     task::{Context, Poll},
+    time::Instant,
 };
 
 use actix_service::{Service, ServiceFactory as BaseServiceFactory};
@@ -10,8 +11,11 @@ use futures_core::future::LocalBoxFuture;
 use tracing::error;
 
 use crate::{
+    outcome::{catch_panic, panic_message, ConnectionOutcome, OutcomeHook, ServiceError},
     socket::{FromStream, MioStream},
+    socket_options::ConnectionOptions,
     worker::WorkerCounterGuard,
+    ConnectionInfo,
 };
 
 #[doc(hidden)]
@@ -27,11 +31,15 @@ pub(crate) trait InternalServiceFactory: Send {
     fn clone_factory(&self) -> Box<dyn InternalServiceFactory>;
 
     fn create(&self) -> LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>>;
+
+    /// Makes services created from now on report connection outcomes to `hook`.
+    fn set_outcome_hook(&mut self, _hook: OutcomeHook) {}
 }
 
+/// Service called with accepted connections and the time they were accepted at.
 pub(crate) type BoxedServerService = Box<
     dyn Service<
-        (WorkerCounterGuard, MioStream),
+        (WorkerCounterGuard, MioStream, Instant),
         Response = (),
         Error = (),
         Future = Ready<Result<(), ()>>,
@@ -40,24 +48,39 @@ pub(crate) type BoxedServerService = Box<
 
 pub(crate) struct StreamService<S, I> {
     service: S,
+    name: Arc<str>,
+    token: usize,
+    conn: ConnectionOptions,
+    outcome: Option<OutcomeHook>,
     _phantom: PhantomData<I>,
 }
 
 impl<S, I> StreamService<S, I> {
-    pub(crate) fn new(service: S) -> Self {
+    pub(crate) fn new(
+        service: S,
+        name: Arc<str>,
+        token: usize,
+        conn: ConnectionOptions,
+        outcome: Option<OutcomeHook>,
+    ) -> Self {
         StreamService {
             service,
+            name,
+            token,
+            conn,
+            outcome,
             _phantom: PhantomData,
         }
     }
 }
 
-impl<S, I> Service<(WorkerCounterGuard, MioStream)> for StreamService<S, I>
+// services are boxed as `BoxedServerService` and so are `'static` anyway; requiring it here makes
+// their errors `'static` for the outcome hook without a bound on the error type itself
+impl<S, I> Service<(WorkerCounterGuard, MioStream, Instant)> for StreamService<S, I>
 where
-    S: Service<I>,
+    S: Service<I> + 'static,
     S::Future: 'static,
-    S::Error: 'static,
-    I: FromStream,
+    I: FromStream + 'static,
 {
     type Response = ();
     type Error = ();
@@ -67,14 +90,57 @@ where
         self.service.poll_ready(ctx).map_err(|_| ())
     }
 
-    fn call(&self, (guard, req): (WorkerCounterGuard, MioStream)) -> Self::Future {
-        ready(match FromStream::from_mio(req) {
+    fn call(
+        &self,
+        (guard, req, accepted_at): (WorkerCounterGuard, MioStream, Instant),
+    ) -> Self::Future {
+        if let Err(err) = self.conn.apply(&req) {
+            error!("can not set connection socket options: {err}");
+            return ready(Err(()));
+        }
+
+        let info = |stream: &MioStream| {
+            ConnectionInfo::new(
+                stream,
+                self.name.clone(),
+                self.token,
+                guard.worker(),
+                accepted_at,
+            )
+        };
+
+        ready(match FromStream::from_mio_with_info(req, info) {
             Ok(stream) => {
                 let f = self.service.call(stream);
-                actix_rt::spawn(async move {
-                    let _ = f.await;
-                    drop(guard);
-                });
+
+                match self.outcome {
+                    Some(ref hook) => {
+                        let hook = hook.clone();
+                        let name = self.name.clone();
+
+                        actix_rt::spawn(async move {
+                            match catch_panic(f).await {
+                                Ok(Ok(_)) => hook(&name, &ConnectionOutcome::Success),
+                                Ok(Err(err)) => {
+                                    let err = ServiceError::new(&err);
+                                    hook(&name, &ConnectionOutcome::Error(err));
+                                }
+                                Err(payload) => {
+                                    let msg = panic_message(&*payload);
+                                    hook(&name, &ConnectionOutcome::Panic(msg));
+                                }
+                            }
+                            drop(guard);
+                        });
+                    }
+                    None => {
+                        actix_rt::spawn(async move {
+                            let _ = f.await;
+                            drop(guard);
+                        });
+                    }
+                }
+
                 Ok(())
             }
             Err(err) => {
@@ -89,26 +155,34 @@ pub(crate) struct StreamNewService<F: ServerServiceFactory<Io>, Io: FromStream>
     name: String,
     inner: F,
     token: usize,
-    addr: SocketAddr,
-    _t: PhantomData<Io>,
+    conn: ConnectionOptions,
+    outcome: Option<OutcomeHook>,
+    // streams are only created on workers, so `Io` does not need to be `Send`
+    _t: PhantomData<fn() -> Io>,
 }
 
 impl<F, Io> StreamNewService<F, Io>
 where
     F: ServerServiceFactory<Io>,
-    Io: FromStream + Send + 'static,
+    Io: FromStream + 'static,
 {
-    pub(crate) fn create(
+    pub(crate) fn create(name: String, token: usize, inner: F) -> Box<dyn InternalServiceFactory> {
+        Self::create_with_options(name, token, inner, ConnectionOptions::default())
+    }
+
+    /// Creates service factory whose services set `conn` options on each accepted connection.
+    pub(crate) fn create_with_options(
         name: String,
         token: usize,
         inner: F,
-        addr: SocketAddr,
+        conn: ConnectionOptions,
     ) -> Box<dyn InternalServiceFactory> {
         Box::new(Self {
             name,
             token,
             inner,
-            addr,
+            conn,
+            outcome: None,
             _t: PhantomData,
         })
     }
@@ -117,7 +191,7 @@ where
 impl<F, Io> InternalServiceFactory for StreamNewService<F, Io>
 where
     F: ServerServiceFactory<Io>,
-    Io: FromStream + Send + 'static,
+    Io: FromStream + 'static,
 {
     fn name(&self, _: usize) -> &str {
         &self.name
@@ -128,24 +202,77 @@ where
             name: self.name.clone(),
             inner: self.inner.clone(),
             token: self.token,
-            addr: self.addr,
+            conn: self.conn.clone(),
+            outcome: self.outcome.clone(),
             _t: PhantomData,
         })
     }
 
     fn create(&self) -> LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>> {
+        let name = Arc::from(&*self.name);
         let token = self.token;
+        let conn = self.conn.clone();
+        let outcome = self.outcome.clone();
         let fut = self.inner.create().new_service(());
         Box::pin(async move {
             match fut.await {
                 Ok(inner) => {
-                    let service = Box::new(StreamService::new(inner)) as _;
+                    let service =
+                        Box::new(StreamService::new(inner, name, token, conn, outcome)) as _;
                     Ok((token, service))
                 }
                 Err(_) => Err(()),
             }
         })
     }
+
+    fn set_outcome_hook(&mut self, hook: OutcomeHook) {
+        self.outcome = Some(hook);
+    }
+}
+
+/// Stands in for the service of a listener removed from a running server.
+///
+/// Keeps the tokens of remaining services stable. Connections still on their way to the removed
+/// service are closed.
+pub(crate) struct ClosedServiceFactory {
+    name: String,
+    token: usize,
+}
+
+impl ClosedServiceFactory {
+    pub(crate) fn create(name: String, token: usize) -> Box<dyn InternalServiceFactory> {
+        Box::new(Self { name, token })
+    }
+}
+
+impl InternalServiceFactory for ClosedServiceFactory {
+    fn name(&self, _: usize) -> &str {
+        &self.name
+    }
+
+    fn clone_factory(&self) -> Box<dyn InternalServiceFactory> {
+        Self::create(self.name.clone(), self.token)
+    }
+
+    fn create(&self) -> LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>> {
+        let service = Box::new(ClosedService) as _;
+        Box::pin(ready(Ok((self.token, service))))
+    }
+}
+
+pub(crate) struct ClosedService;
+
+impl Service<(WorkerCounterGuard, MioStream, Instant)> for ClosedService {
+    type Response = ();
+    type Error = ();
+    type Future = Ready<Result<(), ()>>;
+
+    actix_service::always_ready!();
+
+    fn call(&self, _: (WorkerCounterGuard, MioStream, Instant)) -> Self::Future {
+        ready(Ok(()))
+    }
 }
 
 impl<F, T, I> ServerServiceFactory<I> for F
@@ -0,0 +1,186 @@
+//! Graceful shutdown notification for services running on a worker.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+thread_local! {
+    static WORKER_SHUTDOWN: RefCell<Option<WorkerShutdown>> = const { RefCell::new(None) };
+}
+
+/// Returns a future that resolves once the worker running the current thread starts shutting
+/// down.
+///
+/// Services can wait on it to wind down long-lived connections during a graceful shutdown, e.g.
+/// by sending a goaway frame and closing the connection before the [shutdown timeout] passes.
+/// The future also resolves on a forced shutdown. On threads that do not run a worker, it never
+/// resolves.
+///
+/// # Examples
+/// ```
+/// use actix_rt::net::TcpStream;
+/// use actix_server::{worker_shutdown, Server};
+/// use actix_service::fn_service;
+/// use tokio::io::AsyncWriteExt as _;
+///
+/// # fn run() -> std::io::Result<()> {
+/// let srv = Server::build().bind("stream", ("127.0.0.1", 8080), || {
+///     fn_service(|mut stream: TcpStream| async move {
+///         tokio::select! {
+///             // serve the connection until it is closed ...
+///             res = stream.readable() => res,
+///             // ... or the worker shuts down
+///             _ = worker_shutdown() => stream.write_all(b"goaway").await,
+///         }
+///     })
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [shutdown timeout]: crate::ServerBuilder::shutdown_timeout()
+pub fn worker_shutdown() -> WorkerShutdown {
+    WORKER_SHUTDOWN
+        .with(|shutdown| shutdown.borrow().clone())
+        .unwrap_or_else(WorkerShutdown::new)
+}
+
+/// Future that resolves once a worker starts shutting down.
+///
+/// Returned by [`worker_shutdown()`]. It can be moved to other threads and cloned; all clones
+/// resolve at the same time.
+#[derive(Debug)]
+pub struct WorkerShutdown {
+    state: Arc<ShutdownState>,
+    /// Key of this future's waker, once it has been polled.
+    key: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct ShutdownState {
+    fired: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+#[derive(Debug, Default)]
+struct Wakers {
+    next_key: usize,
+    wakers: HashMap<usize, Waker>,
+}
+
+impl WorkerShutdown {
+    fn new() -> Self {
+        Self {
+            state: Arc::default(),
+            key: None,
+        }
+    }
+
+    /// Creates the shutdown notification of the worker running on the current thread.
+    pub(crate) fn install() -> Self {
+        let shutdown = Self::new();
+        WORKER_SHUTDOWN.with(|current| *current.borrow_mut() = Some(shutdown.clone()));
+        shutdown
+    }
+
+    /// Resolves all futures of this worker.
+    pub(crate) fn notify(&self) {
+        if self.state.fired.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let wakers = std::mem::take(&mut self.state.wakers.lock().unwrap().wakers);
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    /// Returns true if the worker has started shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.state.fired.load(Ordering::Acquire)
+    }
+}
+
+impl Clone for WorkerShutdown {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            key: None,
+        }
+    }
+}
+
+impl Future for WorkerShutdown {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.is_shutting_down() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = this.state.wakers.lock().unwrap();
+
+        // checked again with the lock held, as wakers are taken after the flag is set
+        if this.is_shutting_down() {
+            return Poll::Ready(());
+        }
+
+        let key = *this.key.get_or_insert_with(|| {
+            wakers.next_key += 1;
+            wakers.next_key
+        });
+
+        wakers.wakers.insert(key, cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+impl Drop for WorkerShutdown {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.state.wakers.lock().unwrap().wakers.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+
+    #[test]
+    fn notify_resolves_clones() {
+        let shutdown = WorkerShutdown::new();
+        let mut first = shutdown.clone();
+        let mut second = shutdown.clone();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut first).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut second).poll(&mut cx).is_pending());
+        assert_eq!(shutdown.state.wakers.lock().unwrap().wakers.len(), 2);
+
+        drop(second);
+        assert_eq!(shutdown.state.wakers.lock().unwrap().wakers.len(), 1);
+
+        shutdown.notify();
+        assert!(first.is_shutting_down());
+        assert!(Pin::new(&mut first).poll(&mut cx).is_ready());
+        assert!(Pin::new(&mut shutdown.clone()).poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn not_a_worker() {
+        assert!(!worker_shutdown().is_shutting_down());
+    }
+}
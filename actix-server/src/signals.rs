@@ -1,18 +1,20 @@
 use std::{
     fmt,
-    future::Future,
-    pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
 use tracing::trace;
 
-/// Types of process signals.
-// #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(dead_code)] // variants are never constructed on non-unix
-pub(crate) enum SignalKind {
-    /// `SIGINT`
+use crate::ServerHandle;
+
+/// Process signals the server can act on.
+///
+/// See [`ServerBuilder::signal()`](crate::ServerBuilder::signal()).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Signal {
+    /// `SIGINT`, or Ctrl-C on Windows.
     Int,
 
     /// `SIGTERM`
@@ -20,36 +22,124 @@ pub(crate) enum SignalKind {
 
     /// `SIGQUIT`
     Quit,
+
+    /// `SIGHUP`
+    Hup,
+
+    /// `SIGUSR1`
+    Usr1,
+
+    /// `SIGUSR2`
+    Usr2,
 }
 
-impl fmt::Display for SignalKind {
+impl fmt::Display for Signal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
-            SignalKind::Int => "SIGINT",
-            SignalKind::Term => "SIGTERM",
-            SignalKind::Quit => "SIGQUIT",
+            Signal::Int => "SIGINT",
+            Signal::Term => "SIGTERM",
+            Signal::Quit => "SIGQUIT",
+            Signal::Hup => "SIGHUP",
+            Signal::Usr1 => "SIGUSR1",
+            Signal::Usr2 => "SIGUSR2",
         })
     }
 }
 
+/// Action taken by the server when it receives a [`Signal`].
+///
+/// See [`ServerBuilder::signal()`](crate::ServerBuilder::signal()).
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum SignalAction {
+    /// Start a graceful shutdown.
+    ///
+    /// Receiving a stop signal again while workers are still finishing their connections forces
+    /// the shutdown.
+    GracefulStop,
+
+    /// Start a forced shutdown.
+    ForceStop,
+
+    /// Pause accepting connections.
+    Pause,
+
+    /// Resume accepting connections.
+    Resume,
+
+    /// Hand listeners off to a new process, see
+    /// [`ServerHandle::handoff()`](crate::ServerHandle::handoff()).
+    #[cfg(unix)]
+    Handoff,
+
+    /// Call a function with a handle to the server.
+    ///
+    /// Runs on the task driving the server, so it must not block. Create with
+    /// [`SignalAction::callback()`].
+    Callback(Arc<dyn Fn(&ServerHandle) + Send + Sync>),
+
+    /// Listen to the signal but do nothing, overriding its default action.
+    Ignore,
+}
+
+impl SignalAction {
+    /// Creates action calling `f` with a handle to the server.
+    pub fn callback<F>(f: F) -> Self
+    where
+        F: Fn(&ServerHandle) + Send + Sync + 'static,
+    {
+        SignalAction::Callback(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for SignalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignalAction::GracefulStop => f.write_str("GracefulStop"),
+            SignalAction::ForceStop => f.write_str("ForceStop"),
+            SignalAction::Pause => f.write_str("Pause"),
+            SignalAction::Resume => f.write_str("Resume"),
+            #[cfg(unix)]
+            SignalAction::Handoff => f.write_str("Handoff"),
+            SignalAction::Callback(_) => f.write_str("Callback(..)"),
+            SignalAction::Ignore => f.write_str("Ignore"),
+        }
+    }
+}
+
+/// Actions taken on signals when none are configured.
+pub(crate) fn default_actions() -> Vec<(Signal, SignalAction)> {
+    vec![
+        (Signal::Int, SignalAction::ForceStop),
+        (Signal::Term, SignalAction::GracefulStop),
+        (Signal::Quit, SignalAction::ForceStop),
+    ]
+}
+
 /// Process signal listener.
 pub(crate) struct Signals {
     #[cfg(not(unix))]
-    signals: futures_core::future::BoxFuture<'static, std::io::Result<()>>,
+    signals: Option<futures_core::future::BoxFuture<'static, std::io::Result<()>>>,
 
     #[cfg(unix)]
-    signals: Vec<(SignalKind, actix_rt::signal::unix::Signal)>,
+    signals: Vec<(Signal, actix_rt::signal::unix::Signal)>,
 }
 
 impl Signals {
-    /// Constructs an OS signal listening future.
-    pub(crate) fn new() -> Self {
+    /// Constructs an OS signal listener for `signals`.
+    ///
+    /// On Windows, only Ctrl-C ([`Signal::Int`]) is listened to.
+    pub(crate) fn new(signals: impl Iterator<Item = Signal>) -> Self {
         trace!("setting up OS signal listener");
 
         #[cfg(not(unix))]
         {
+            let mut signals = signals;
+
             Signals {
-                signals: Box::pin(actix_rt::signal::ctrl_c()),
+                signals: signals
+                    .any(|sig| sig == Signal::Int)
+                    .then(|| Box::pin(actix_rt::signal::ctrl_c()) as _),
             }
         }
 
@@ -57,17 +147,19 @@ impl Signals {
         {
             use actix_rt::signal::unix;
 
-            let sig_map = [
-                (unix::SignalKind::interrupt(), SignalKind::Int),
-                (unix::SignalKind::terminate(), SignalKind::Term),
-                (unix::SignalKind::quit(), SignalKind::Quit),
-            ];
-
-            let signals = sig_map
-                .iter()
-                .filter_map(|(kind, sig)| {
-                    unix::signal(*kind)
-                        .map(|tokio_sig| (*sig, tokio_sig))
+            let signals = signals
+                .filter_map(|sig| {
+                    let kind = match sig {
+                        Signal::Int => unix::SignalKind::interrupt(),
+                        Signal::Term => unix::SignalKind::terminate(),
+                        Signal::Quit => unix::SignalKind::quit(),
+                        Signal::Hup => unix::SignalKind::hangup(),
+                        Signal::Usr1 => unix::SignalKind::user_defined1(),
+                        Signal::Usr2 => unix::SignalKind::user_defined2(),
+                    };
+
+                    unix::signal(kind)
+                        .map(|tokio_sig| (sig, tokio_sig))
                         .map_err(|e| {
                             tracing::error!(
                                 "can not initialize stream handler for {:?} err: {}",
@@ -82,15 +174,25 @@ impl Signals {
             Signals { signals }
         }
     }
-}
 
-impl Future for Signals {
-    type Output = SignalKind;
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Resolves to the next signal received.
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Signal> {
         #[cfg(not(unix))]
         {
-            self.signals.as_mut().poll(cx).map(|_| SignalKind::Int)
+            use std::future::Future as _;
+
+            let fut = match self.signals {
+                Some(ref mut fut) => fut,
+                None => return Poll::Pending,
+            };
+
+            if fut.as_mut().poll(cx).is_ready() {
+                // listen for the next Ctrl-C
+                *fut = Box::pin(actix_rt::signal::ctrl_c());
+                return Poll::Ready(Signal::Int);
+            }
+
+            Poll::Pending
         }
 
         #[cfg(unix)]
@@ -1,7 +1,7 @@
 pub(crate) use std::net::{
     SocketAddr as StdSocketAddr, TcpListener as StdTcpListener, ToSocketAddrs,
 };
-use std::{fmt, io};
+use std::{any::Any, fmt, io};
 
 use actix_rt::net::TcpStream;
 pub(crate) use mio::net::TcpListener as MioTcpListener;
@@ -11,12 +11,119 @@ pub(crate) use {
     mio::net::UnixListener as MioUnixListener, std::os::unix::net::UnixListener as StdUnixListener,
 };
 
-use crate::builder::MpTcp;
+use crate::{builder::MpTcp, socket_options::SocketOptions, ConnectionAddr};
+
+/// Listener of a custom socket type, served with
+/// [`ServerBuilder::listen_custom()`](crate::ServerBuilder::listen_custom()).
+///
+/// The listener is registered with the poll of the accept thread for readable events, after which
+/// [`accept()`](Self::accept()) is called until it returns an error of kind
+/// [`WouldBlock`](io::ErrorKind::WouldBlock). Accepted streams are distributed to workers like
+/// connections of any other listener and handed to the service as they are; converting them
+/// into asynchronous streams is up to the service, as it runs on the runtime of its worker.
+///
+/// # Examples
+/// ```
+/// use std::io;
+///
+/// use actix_server::{ConnectionAddr, Listener, ListenerStream};
+/// use mio::{
+///     event::Source,
+///     net::{TcpListener, TcpStream},
+///     Interest, Registry, Token,
+/// };
+///
+/// struct MyListener(TcpListener);
+///
+/// struct MyStream(TcpStream);
+///
+/// impl ListenerStream for MyStream {
+///     fn peer_addr(&self) -> ConnectionAddr {
+///         self.0
+///             .peer_addr()
+///             .map_or(ConnectionAddr::Unknown, ConnectionAddr::Tcp)
+///     }
+/// }
+///
+/// impl Listener for MyListener {
+///     type Stream = MyStream;
+///
+///     fn accept(&mut self) -> io::Result<MyStream> {
+///         let (stream, _) = self.0.accept()?;
+///         stream.set_nodelay(true)?;
+///         Ok(MyStream(stream))
+///     }
+/// }
+///
+/// impl Source for MyListener {
+///     fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+///         self.0.register(registry, token, interests)
+///     }
+///
+///     fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+///         self.0.reregister(registry, token, interests)
+///     }
+///
+///     fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+///         self.0.deregister(registry)
+///     }
+/// }
+/// ```
+pub trait Listener: Source + Send + 'static {
+    /// Stream of an accepted connection.
+    type Stream: ListenerStream;
+
+    /// Accepts a new connection.
+    ///
+    /// Errors of kind `ConnectionRefused`, `ConnectionAborted` and `ConnectionReset` only drop the
+    /// connection being accepted. Other errors pause the listener for a short while.
+    fn accept(&mut self) -> io::Result<Self::Stream>;
+
+    /// Returns local address of the listener, used in logs.
+    fn local_addr(&self) -> ConnectionAddr {
+        ConnectionAddr::Unknown
+    }
+}
+
+/// Stream of a connection accepted by a custom [`Listener`].
+///
+/// The addresses are used for [`ConnectionInfo`](crate::ConnectionInfo) and IP filters, so
+/// connections without a TCP peer address are never rejected by
+/// [`ServerBuilder::ip_filter()`](crate::ServerBuilder::ip_filter()).
+pub trait ListenerStream: Send + Sized + 'static {
+    /// Returns local address of the connection.
+    fn local_addr(&self) -> ConnectionAddr {
+        ConnectionAddr::Unknown
+    }
+
+    /// Returns address of the peer.
+    fn peer_addr(&self) -> ConnectionAddr {
+        ConnectionAddr::Unknown
+    }
+}
+
+/// Object safe counterpart of [`Listener`].
+pub(crate) trait DynListener: Source + Send {
+    fn accept(&mut self) -> io::Result<MioStream>;
+
+    fn local_addr(&self) -> ConnectionAddr;
+}
+
+impl<L: Listener> DynListener for L {
+    fn accept(&mut self) -> io::Result<MioStream> {
+        Listener::accept(self).map(|stream| MioStream::Custom(CustomStream(Box::new(stream))))
+    }
+
+    fn local_addr(&self) -> ConnectionAddr {
+        Listener::local_addr(self)
+    }
+}
 
 pub(crate) enum MioListener {
     Tcp(MioTcpListener),
     #[cfg(unix)]
     Uds(MioUnixListener),
+    Custom(Box<dyn DynListener>),
 }
 
 impl MioListener {
@@ -31,16 +138,36 @@ impl MioListener {
                 .local_addr()
                 .map(SocketAddr::Uds)
                 .unwrap_or(SocketAddr::Unknown),
+            MioListener::Custom(ref lst) => SocketAddr::Custom(lst.local_addr()),
         }
     }
 
-    pub(crate) fn accept(&self) -> io::Result<MioStream> {
+    pub(crate) fn accept(&mut self) -> io::Result<MioStream> {
         match *self {
             MioListener::Tcp(ref lst) => lst.accept().map(|(stream, _)| MioStream::Tcp(stream)),
             #[cfg(unix)]
             MioListener::Uds(ref lst) => lst.accept().map(|(stream, _)| MioStream::Uds(stream)),
+            MioListener::Custom(ref mut lst) => lst.accept(),
         }
     }
+
+    /// Returns FD of the listener, or `None` for custom listeners, which can not be handed off.
+    #[cfg(unix)]
+    pub(crate) fn as_raw_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        use std::os::unix::io::AsRawFd as _;
+
+        match *self {
+            MioListener::Tcp(ref lst) => Some(lst.as_raw_fd()),
+            MioListener::Uds(ref lst) => Some(lst.as_raw_fd()),
+            MioListener::Custom(_) => None,
+        }
+    }
+}
+
+impl<L: Listener> From<L> for MioListener {
+    fn from(lst: L) -> Self {
+        MioListener::Custom(Box::new(lst))
+    }
 }
 
 impl Source for MioListener {
@@ -54,6 +181,7 @@ impl Source for MioListener {
             MioListener::Tcp(ref mut lst) => lst.register(registry, token, interests),
             #[cfg(unix)]
             MioListener::Uds(ref mut lst) => lst.register(registry, token, interests),
+            MioListener::Custom(ref mut lst) => lst.register(registry, token, interests),
         }
     }
 
@@ -67,6 +195,7 @@ impl Source for MioListener {
             MioListener::Tcp(ref mut lst) => lst.reregister(registry, token, interests),
             #[cfg(unix)]
             MioListener::Uds(ref mut lst) => lst.reregister(registry, token, interests),
+            MioListener::Custom(ref mut lst) => lst.reregister(registry, token, interests),
         }
     }
 
@@ -85,6 +214,7 @@ impl Source for MioListener {
                 }
                 res
             }
+            MioListener::Custom(ref mut lst) => lst.deregister(registry),
         }
     }
 }
@@ -108,6 +238,7 @@ impl fmt::Debug for MioListener {
             MioListener::Tcp(ref lst) => write!(f, "{:?}", lst),
             #[cfg(unix)]
             MioListener::Uds(ref lst) => write!(f, "{:?}", lst),
+            MioListener::Custom(ref lst) => write!(f, "CustomListener({:?})", lst.local_addr()),
         }
     }
 }
@@ -118,6 +249,7 @@ impl fmt::Display for MioListener {
             MioListener::Tcp(ref lst) => write!(f, "{:?}", lst),
             #[cfg(unix)]
             MioListener::Uds(ref lst) => write!(f, "{:?}", lst),
+            MioListener::Custom(ref lst) => write!(f, "CustomListener({:?})", lst.local_addr()),
         }
     }
 }
@@ -127,6 +259,7 @@ pub(crate) enum SocketAddr {
     Tcp(StdSocketAddr),
     #[cfg(unix)]
     Uds(mio::net::SocketAddr),
+    Custom(ConnectionAddr),
 }
 
 impl fmt::Display for SocketAddr {
@@ -136,6 +269,7 @@ impl fmt::Display for SocketAddr {
             Self::Tcp(ref addr) => write!(f, "{}", addr),
             #[cfg(unix)]
             Self::Uds(ref addr) => write!(f, "{:?}", addr),
+            Self::Custom(ref addr) => write!(f, "{:?}", addr),
         }
     }
 }
@@ -147,6 +281,7 @@ impl fmt::Debug for SocketAddr {
             Self::Tcp(ref addr) => write!(f, "{:?}", addr),
             #[cfg(unix)]
             Self::Uds(ref addr) => write!(f, "{:?}", addr),
+            Self::Custom(ref addr) => write!(f, "{:?}", addr),
         }
     }
 }
@@ -156,11 +291,105 @@ pub enum MioStream {
     Tcp(mio::net::TcpStream),
     #[cfg(unix)]
     Uds(mio::net::UnixStream),
+    Custom(CustomStream),
+}
+
+impl MioStream {
+    /// Returns IP address of the peer, or `None` for Unix domain sockets.
+    pub(crate) fn peer_ip(&self) -> Option<std::net::IpAddr> {
+        match *self {
+            MioStream::Tcp(ref stream) => stream.peer_addr().ok().map(|addr| addr.ip()),
+            #[cfg(unix)]
+            MioStream::Uds(_) => None,
+            MioStream::Custom(ref stream) => match stream.0.peer_addr() {
+                ConnectionAddr::Tcp(addr) => Some(addr.ip()),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns error for a stream converted into a type of another listener.
+    fn mismatch() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "accepted stream does not match the stream type of the service",
+        )
+    }
+}
+
+/// Stream accepted by a custom [`Listener`].
+pub struct CustomStream(Box<dyn DynStream>);
+
+impl CustomStream {
+    pub(crate) fn local_addr(&self) -> ConnectionAddr {
+        self.0.local_addr()
+    }
+
+    pub(crate) fn peer_addr(&self) -> ConnectionAddr {
+        self.0.peer_addr()
+    }
+}
+
+impl fmt::Debug for CustomStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomStream")
+            .field("local_addr", &self.0.local_addr())
+            .field("peer_addr", &self.0.peer_addr())
+            .finish()
+    }
+}
+
+/// Object safe counterpart of [`ListenerStream`].
+trait DynStream: Send {
+    fn local_addr(&self) -> ConnectionAddr;
+
+    fn peer_addr(&self) -> ConnectionAddr;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<S: ListenerStream> DynStream for S {
+    fn local_addr(&self) -> ConnectionAddr {
+        ListenerStream::local_addr(self)
+    }
+
+    fn peer_addr(&self) -> ConnectionAddr {
+        ListenerStream::peer_addr(self)
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 
 /// Helper trait for converting a Mio stream into a Tokio stream.
 pub trait FromStream: Sized {
     fn from_mio(sock: MioStream) -> io::Result<Self>;
+
+    /// Converts an accepted stream, for types that also carry metadata of the connection.
+    ///
+    /// `info` is only called by types that use it.
+    fn from_mio_with_info(
+        sock: MioStream,
+        info: impl FnOnce(&MioStream) -> crate::ConnectionInfo,
+    ) -> io::Result<Self> {
+        let _ = info;
+        Self::from_mio(sock)
+    }
+}
+
+impl<S: ListenerStream> FromStream for S {
+    fn from_mio(sock: MioStream) -> io::Result<Self> {
+        match sock {
+            MioStream::Custom(stream) => stream
+                .0
+                .into_any()
+                .downcast()
+                .map(|stream| *stream)
+                .map_err(|_| MioStream::mismatch()),
+            _ => Err(MioStream::mismatch()),
+        }
+    }
 }
 
 #[cfg(windows)]
@@ -178,6 +407,7 @@ mod win_impl {
                     // SAFETY: This is an in-place conversion from Mio stream to Tokio stream.
                     TcpStream::from_std(unsafe { FromRawSocket::from_raw_socket(raw) })
                 }
+                MioStream::Custom(_) => Err(MioStream::mismatch()),
             }
         }
     }
@@ -200,9 +430,7 @@ mod unix_impl {
                     // SAFETY: This is an in-place conversion from Mio stream to Tokio stream.
                     TcpStream::from_std(unsafe { FromRawFd::from_raw_fd(raw) })
                 }
-                MioStream::Uds(_) => {
-                    panic!("Should not happen, bug in server impl");
-                }
+                MioStream::Uds(_) | MioStream::Custom(_) => Err(MioStream::mismatch()),
             }
         }
     }
@@ -211,7 +439,7 @@ mod unix_impl {
     impl FromStream for UnixStream {
         fn from_mio(sock: MioStream) -> io::Result<Self> {
             match sock {
-                MioStream::Tcp(_) => panic!("Should not happen, bug in server impl"),
+                MioStream::Tcp(_) | MioStream::Custom(_) => Err(MioStream::mismatch()),
                 MioStream::Uds(mio) => {
                     let raw = IntoRawFd::into_raw_fd(mio);
                     // SAFETY: This is an in-place conversion from Mio stream to Tokio stream.
@@ -220,13 +448,45 @@ mod unix_impl {
             }
         }
     }
+
+    // io-uring operations on a non-blocking socket fail with `WouldBlock` instead of waiting for
+    // readiness, so accepted sockets are switched back to blocking mode first.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    impl FromStream for tokio_uring::net::TcpStream {
+        fn from_mio(sock: MioStream) -> io::Result<Self> {
+            match sock {
+                MioStream::Tcp(mio) => {
+                    let raw = IntoRawFd::into_raw_fd(mio);
+                    // SAFETY: This is an in-place conversion from Mio stream to tokio-uring stream.
+                    let std = unsafe { std::net::TcpStream::from_raw_fd(raw) };
+                    std.set_nonblocking(false)?;
+                    Ok(tokio_uring::net::TcpStream::from_std(std))
+                }
+                MioStream::Uds(_) | MioStream::Custom(_) => Err(MioStream::mismatch()),
+            }
+        }
+    }
 }
 
 pub(crate) fn create_mio_tcp_listener(
     addr: StdSocketAddr,
     backlog: u32,
     mptcp: &MpTcp,
+    options: &SocketOptions,
 ) -> io::Result<MioTcpListener> {
+    let socket = create_tcp_socket(addr, mptcp, false, options)?;
+    socket.listen(options.listen_backlog(backlog) as i32)?;
+
+    Ok(MioTcpListener::from_std(StdTcpListener::from(socket)))
+}
+
+/// Creates a non-blocking TCP socket bound to `addr`, ready to listen.
+fn create_tcp_socket(
+    addr: StdSocketAddr,
+    mptcp: &MpTcp,
+    reuse_port: bool,
+    options: &SocketOptions,
+) -> io::Result<socket2::Socket> {
     use socket2::{Domain, Protocol, Socket, Type};
 
     #[cfg(not(target_os = "linux"))]
@@ -250,12 +510,115 @@ pub(crate) fn create_mio_tcp_listener(
         Err(err) => return Err(err),
     };
 
-    socket.set_reuse_address(true)?;
+    options.apply_listener(&socket, addr)?;
+
+    if reuse_port {
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        socket.set_reuse_port(true)?;
+
+        #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SO_REUSEPORT is not supported on this platform",
+        ));
+    }
+
     socket.set_nonblocking(true)?;
     socket.bind(&addr.into())?;
-    socket.listen(backlog as i32)?;
 
-    Ok(MioTcpListener::from_std(StdTcpListener::from(socket)))
+    Ok(socket)
+}
+
+/// TCP listener that every worker binds and accepts from by itself in `SO_REUSEPORT` mode.
+#[derive(Debug, Clone)]
+pub(crate) struct ReusePortListener {
+    pub(crate) addr: StdSocketAddr,
+    backlog: u32,
+    mptcp: MpTcp,
+    options: SocketOptions,
+
+    /// Counters shared by the listeners of all workers.
+    pub(crate) counters: std::sync::Arc<crate::stats::ListenerCounters>,
+
+    /// Access control shared by the listeners with the same name; set once the server starts.
+    pub(crate) access: Option<std::sync::Arc<crate::access::ListenerAccess>>,
+
+    /// Whether workers accept with multishot io-uring accept instead of polling the listener.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    pub(crate) uring: bool,
+
+    /// Bound, non-listening socket that reserves the address while the server is running.
+    ///
+    /// It never receives connections itself since only listening sockets are part of the
+    /// `SO_REUSEPORT` group.
+    _reservation: std::sync::Arc<socket2::Socket>,
+}
+
+impl ReusePortListener {
+    /// Reserves `addr` for workers to bind to, resolving port 0 to an actual port.
+    pub(crate) fn reserve(
+        addr: StdSocketAddr,
+        backlog: u32,
+        mptcp: &MpTcp,
+        options: &SocketOptions,
+    ) -> io::Result<Self> {
+        let reservation = create_tcp_socket(addr, mptcp, true, options)?;
+
+        let addr = reservation.local_addr()?.as_socket().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "bound socket has no IP address")
+        })?;
+
+        Ok(Self {
+            addr,
+            backlog: options.listen_backlog(backlog),
+            mptcp: mptcp.clone(),
+            options: options.clone(),
+            counters: Default::default(),
+            access: None,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            uring: false,
+            _reservation: std::sync::Arc::new(reservation),
+        })
+    }
+
+    /// Binds a new listener that is part of this address's `SO_REUSEPORT` group.
+    pub(crate) fn bind(&self) -> io::Result<StdTcpListener> {
+        let socket = create_tcp_socket(self.addr, &self.mptcp, true, &self.options)?;
+        socket.listen(self.backlog as i32)?;
+        Ok(StdTcpListener::from(socket))
+    }
+}
+
+/// Takes ownership of an already listening socket FD, detecting whether it is TCP or UDS.
+///
+/// # Safety
+/// `fd` must be an open socket that is not owned by anything else in this process.
+#[cfg(unix)]
+pub(crate) unsafe fn mio_listener_from_raw_fd(
+    fd: std::os::unix::io::RawFd,
+) -> io::Result<MioListener> {
+    use std::os::unix::io::FromRawFd as _;
+
+    use socket2::{Socket, Type};
+
+    let socket = Socket::from_raw_fd(fd);
+
+    if socket.r#type()? != Type::STREAM {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("file descriptor {fd} is not a stream socket"),
+        ));
+    }
+
+    // inherited FDs are left open across exec; do not leak them any further
+    socket.set_cloexec(true)?;
+    socket.set_nonblocking(true)?;
+
+    if socket.local_addr()?.is_unix() {
+        Ok(MioListener::from(StdUnixListener::from(socket)))
+    } else {
+        Ok(MioListener::from(StdTcpListener::from(socket)))
+    }
 }
 
 #[cfg(test)]
@@ -269,12 +632,35 @@ mod tests {
         assert_eq!(format!("{}", addr), "127.0.0.1:8080");
 
         let addr: StdSocketAddr = "127.0.0.1:0".parse().unwrap();
-        let lst = create_mio_tcp_listener(addr, 128, &MpTcp::Disabled).unwrap();
+        let lst =
+            create_mio_tcp_listener(addr, 128, &MpTcp::Disabled, &SocketOptions::new()).unwrap();
         let lst = MioListener::Tcp(lst);
         assert!(format!("{:?}", lst).contains("TcpListener"));
         assert!(format!("{}", lst).contains("127.0.0.1"));
     }
 
+    #[test]
+    fn custom_stream() {
+        #[derive(Debug)]
+        struct Custom;
+
+        impl ListenerStream for Custom {}
+
+        #[derive(Debug)]
+        struct Other;
+
+        impl ListenerStream for Other {}
+
+        let custom = || MioStream::Custom(CustomStream(Box::new(Custom)));
+        assert!(Custom::from_mio(custom()).is_ok());
+
+        let err = Other::from_mio(custom()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = TcpStream::from_mio(custom()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
     #[test]
     #[cfg(unix)]
     fn uds() {
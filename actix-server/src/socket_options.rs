@@ -0,0 +1,339 @@
+//! Socket options applied to listeners and accepted connections.
+
+use std::{io, time::Duration};
+
+use socket2::{SockRef, Socket, TcpKeepalive};
+
+use crate::socket::{MioStream, StdSocketAddr};
+
+/// Socket options of a TCP listener and of the connections it accepts.
+///
+/// Passed to [`ServerBuilder::bind_with_options()`](crate::ServerBuilder::bind_with_options()).
+/// Listener options are set when the listener is bound; accepted connections inherit most of them.
+/// Connection options ([`nodelay()`](Self::nodelay()) and the keepalive settings) are set on each
+/// accepted connection before it is passed to its service.
+///
+/// Options marked as Linux only make binding fail with an `Unsupported` error on other platforms.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_server::SocketOptions;
+///
+/// let options = SocketOptions::new()
+///     .backlog(4096)
+///     .nodelay(true)
+///     .keepalive(Duration::from_secs(60))
+///     .keepalive_interval(Duration::from_secs(10))
+///     .keepalive_retries(3)
+///     .recv_buffer_size(256 * 1024);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SocketOptions {
+    backlog: Option<u32>,
+    reuse_address: bool,
+    only_v6: Option<bool>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    defer_accept: Option<Duration>,
+    fastopen: Option<u32>,
+    bind_device: Option<String>,
+    freebind: bool,
+    conn: ConnectionOptions,
+}
+
+impl SocketOptions {
+    /// Creates options that only set `SO_REUSEADDR` on listeners, as [`bind()`] does.
+    ///
+    /// [`bind()`]: crate::ServerBuilder::bind()
+    pub fn new() -> Self {
+        Self {
+            backlog: None,
+            reuse_address: true,
+            only_v6: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            defer_accept: None,
+            fastopen: None,
+            bind_device: None,
+            freebind: false,
+            conn: ConnectionOptions::default(),
+        }
+    }
+
+    /// Sets maximum number of pending connections, overriding
+    /// [`ServerBuilder::backlog()`](crate::ServerBuilder::backlog()).
+    pub fn backlog(mut self, num: u32) -> Self {
+        self.backlog = Some(num);
+        self
+    }
+
+    /// Sets whether `SO_REUSEADDR` is set on listeners. Enabled by default.
+    pub fn reuse_address(mut self, reuse: bool) -> Self {
+        self.reuse_address = reuse;
+        self
+    }
+
+    /// Sets `IPV6_V6ONLY` on IPv6 listeners, making them refuse IPv4 connections.
+    ///
+    /// By default, the system setting is used.
+    pub fn only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Sets size of the send buffer (`SO_SNDBUF`) of listeners and their connections.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets size of the receive buffer (`SO_RCVBUF`) of listeners and their connections.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets `TCP_DEFER_ACCEPT`, only accepting connections once data has arrived on them or
+    /// `timeout` has passed. Linux only.
+    pub fn defer_accept(mut self, timeout: Duration) -> Self {
+        self.defer_accept = Some(timeout);
+        self
+    }
+
+    /// Enables TCP Fast Open (`TCP_FASTOPEN`) with a queue of at most `queue_len` pending
+    /// connections. Linux only.
+    pub fn fastopen(mut self, queue_len: u32) -> Self {
+        self.fastopen = Some(queue_len);
+        self
+    }
+
+    /// Binds listeners to network interface `interface` with `SO_BINDTODEVICE`. Linux only.
+    pub fn bind_device(mut self, interface: impl Into<String>) -> Self {
+        self.bind_device = Some(interface.into());
+        self
+    }
+
+    /// Sets `IP_FREEBIND`, allowing listeners to bind to addresses that are not (yet) assigned to
+    /// a local interface. Linux only.
+    pub fn freebind(mut self, freebind: bool) -> Self {
+        self.freebind = freebind;
+        self
+    }
+
+    /// Sets `TCP_NODELAY` on accepted connections, disabling Nagle's algorithm.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.conn.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Enables `SO_KEEPALIVE` on accepted connections, sending the first probe after a connection
+    /// has been idle for `idle`.
+    pub fn keepalive(mut self, idle: Duration) -> Self {
+        self.conn.keepalive = Some(idle);
+        self
+    }
+
+    /// Sets time between keepalive probes. Linux only.
+    ///
+    /// Only used if keepalive is enabled with [`keepalive()`](Self::keepalive()).
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.conn.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Sets number of unanswered keepalive probes after which a connection is dropped. Linux only.
+    ///
+    /// Only used if keepalive is enabled with [`keepalive()`](Self::keepalive()).
+    pub fn keepalive_retries(mut self, retries: u32) -> Self {
+        self.conn.keepalive_retries = Some(retries);
+        self
+    }
+
+    /// Returns backlog of listeners, or `default` if not set.
+    pub(crate) fn listen_backlog(&self, default: u32) -> u32 {
+        self.backlog.unwrap_or(default)
+    }
+
+    /// Returns options to set on accepted connections.
+    pub(crate) fn connection(&self) -> &ConnectionOptions {
+        &self.conn
+    }
+
+    /// Sets listener options on `socket` before it is bound to `addr`.
+    pub(crate) fn apply_listener(&self, socket: &Socket, addr: StdSocketAddr) -> io::Result<()> {
+        socket.set_reuse_address(self.reuse_address)?;
+
+        if let Some(only_v6) = self.only_v6 {
+            if addr.is_ipv6() {
+                socket.set_only_v6(only_v6)?;
+            }
+        }
+
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        self.apply_linux_listener(socket, addr)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn apply_linux_listener(&self, socket: &Socket, addr: StdSocketAddr) -> io::Result<()> {
+        if let Some(ref interface) = self.bind_device {
+            socket.bind_device(Some(interface.as_bytes()))?;
+        }
+
+        if self.freebind {
+            if addr.is_ipv6() {
+                socket.set_freebind_ipv6(true)?;
+            } else {
+                socket.set_freebind(true)?;
+            }
+        }
+
+        if let Some(timeout) = self.defer_accept {
+            let secs = timeout.as_secs().try_into().unwrap_or(libc::c_int::MAX);
+            set_tcp_option(socket, libc::TCP_DEFER_ACCEPT, secs)?;
+        }
+
+        if let Some(queue_len) = self.fastopen {
+            let queue_len = queue_len.try_into().unwrap_or(libc::c_int::MAX);
+            set_tcp_option(socket, libc::TCP_FASTOPEN, queue_len)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn apply_linux_listener(&self, _: &Socket, _: StdSocketAddr) -> io::Result<()> {
+        let linux_only = [
+            ("SO_BINDTODEVICE", self.bind_device.is_some()),
+            ("IP_FREEBIND", self.freebind),
+            ("TCP_DEFER_ACCEPT", self.defer_accept.is_some()),
+            ("TCP_FASTOPEN", self.fastopen.is_some()),
+        ];
+
+        match linux_only.iter().find(|(_, set)| *set) {
+            Some((name, _)) => Err(unsupported(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options set on each accepted connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionOptions {
+    nodelay: Option<bool>,
+    keepalive: Option<Duration>,
+    keepalive_interval: Option<Duration>,
+    keepalive_retries: Option<u32>,
+}
+
+impl ConnectionOptions {
+    /// Sets options on accepted connection `stream`. Unix domain sockets and streams of custom
+    /// listeners are left untouched.
+    pub(crate) fn apply(&self, stream: &MioStream) -> io::Result<()> {
+        let stream = match *stream {
+            MioStream::Tcp(ref stream) => stream,
+            #[cfg(unix)]
+            MioStream::Uds(_) => return Ok(()),
+            MioStream::Custom(_) => return Ok(()),
+        };
+
+        if let Some(nodelay) = self.nodelay {
+            stream.set_nodelay(nodelay)?;
+        }
+
+        if let Some(idle) = self.keepalive {
+            let keepalive = self.tcp_keepalive(idle)?;
+            with_sock_ref(stream, |sock| sock.set_tcp_keepalive(&keepalive))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn tcp_keepalive(&self, idle: Duration) -> io::Result<TcpKeepalive> {
+        let mut keepalive = TcpKeepalive::new().with_time(idle);
+
+        if let Some(interval) = self.keepalive_interval {
+            keepalive = keepalive.with_interval(interval);
+        }
+
+        if let Some(retries) = self.keepalive_retries {
+            keepalive = keepalive.with_retries(retries);
+        }
+
+        Ok(keepalive)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn tcp_keepalive(&self, idle: Duration) -> io::Result<TcpKeepalive> {
+        if self.keepalive_interval.is_some() || self.keepalive_retries.is_some() {
+            return Err(unsupported("keepalive interval and retries"));
+        }
+
+        Ok(TcpKeepalive::new().with_time(idle))
+    }
+}
+
+/// Calls `f` with a socket reference to `stream`, which does not implement `AsFd` itself.
+#[cfg(unix)]
+fn with_sock_ref<T>(stream: &mio::net::TcpStream, f: impl FnOnce(SockRef<'_>) -> T) -> T {
+    use std::os::unix::io::{AsRawFd as _, BorrowedFd};
+
+    // SAFETY: `stream` keeps the file descriptor open while it is borrowed.
+    let fd = unsafe { BorrowedFd::borrow_raw(stream.as_raw_fd()) };
+    f(SockRef::from(&fd))
+}
+
+/// Calls `f` with a socket reference to `stream`, which does not implement `AsSocket` itself.
+#[cfg(windows)]
+fn with_sock_ref<T>(stream: &mio::net::TcpStream, f: impl FnOnce(SockRef<'_>) -> T) -> T {
+    use std::os::windows::io::{AsRawSocket as _, BorrowedSocket};
+
+    // SAFETY: `stream` keeps the socket open while it is borrowed.
+    let socket = unsafe { BorrowedSocket::borrow_raw(stream.as_raw_socket()) };
+    f(SockRef::from(&socket))
+}
+
+#[cfg(target_os = "linux")]
+fn set_tcp_option(socket: &Socket, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd as _;
+
+    // SAFETY: `value` is a valid `c_int` for the duration of the call and its size is passed along.
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported(option: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{option} is not supported on this platform"),
+    )
+}
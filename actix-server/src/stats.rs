@@ -0,0 +1,81 @@
+//! Runtime server statistics.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of server statistics.
+///
+/// Returned by [`ServerHandle::stats()`](crate::ServerHandle::stats()).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ServerStats {
+    /// True if accepting connections is paused.
+    pub paused: bool,
+
+    /// Statistics of each worker, ordered by worker index.
+    pub workers: Vec<WorkerStats>,
+
+    /// Statistics of each listener, ordered by token.
+    pub listeners: Vec<ListenerStats>,
+}
+
+/// Statistics of a single worker.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WorkerStats {
+    /// Worker index.
+    pub idx: usize,
+
+    /// Number of connections the worker is currently handling.
+    pub connections: usize,
+
+    /// Number of times the worker was restarted after it faulted.
+    pub restarts: usize,
+
+    /// Whether the worker has missed its heartbeats for longer than the stall timeout, see
+    /// [`ServerBuilder::worker_stall_timeout()`](crate::ServerBuilder::worker_stall_timeout()).
+    pub stalled: bool,
+}
+
+/// Statistics of a single listener.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ListenerStats {
+    /// Token identifying the listener's service.
+    pub token: usize,
+
+    /// Name the listener was bound with.
+    pub name: String,
+
+    /// Number of connections accepted and handed to a worker since the server started.
+    pub accepted: u64,
+
+    /// Number of connections accepted but closed without being handed to a worker since the
+    /// server started.
+    pub rejected: u64,
+}
+
+/// Cumulative connection counters of a listener, shared by all threads accepting from it.
+#[derive(Debug, Default)]
+pub(crate) struct ListenerCounters {
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl ListenerCounters {
+    pub(crate) fn accepted(&self) {
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, token: usize, name: String) -> ListenerStats {
+        ListenerStats {
+            token,
+            name,
+            accepted: self.accepted.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
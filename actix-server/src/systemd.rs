@@ -0,0 +1,325 @@
+//! systemd socket activation and service notifications.
+//!
+//! See `sd_listen_fds(3)` for the activation protocol: systemd passes `LISTEN_FDS` sockets
+//! starting at FD 3, names them in the colon-separated `LISTEN_FDNAMES` and sets `LISTEN_PID` to
+//! the PID of the process they are meant for. These variables are read when the first
+//! `ServerBuilder` is created and, if set, removed. Removing them races with other threads reading
+//! the environment, so a socket-activated process must create its first `ServerBuilder` before it
+//! spawns any threads.
+//!
+//! See `sd_notify(3)` for the notification protocol: state changes are sent as datagrams to the
+//! Unix socket in `NOTIFY_SOCKET`, and `WATCHDOG_USEC` asks for periodic `WATCHDOG=1` pings.
+
+use std::{env, io, os::unix::io::RawFd, process, sync::Mutex, time::Duration};
+
+use socket2::{Domain, SockAddr, Socket, Type};
+use tracing::{error, trace, warn};
+
+use crate::socket::{mio_listener_from_raw_fd, MioListener};
+
+/// First FD passed by systemd.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Name systemd gives to sockets without a `FileDescriptorName=`.
+const UNKNOWN_NAME: &str = "unknown";
+
+/// Activated sockets not yet adopted by a `ServerBuilder`.
+///
+/// Parsed from the environment by [`take_env()`].
+static ACTIVATED: Mutex<Option<Vec<(String, RawFd)>>> = Mutex::new(None);
+
+/// Reads and removes the socket activation environment variables if set, once per process.
+pub(crate) fn take_env() {
+    ACTIVATED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(activated_from_env);
+}
+
+/// Takes all activated sockets named `name`.
+///
+/// Returns a `NotFound` error listing the available names if there are none.
+pub(crate) fn take_activated(name: &str) -> io::Result<Vec<MioListener>> {
+    let mut activated = ACTIVATED.lock().unwrap();
+    let activated = activated.get_or_insert_with(activated_from_env);
+
+    let mut fds = Vec::new();
+    activated.retain(|(fd_name, fd)| {
+        if fd_name == name {
+            fds.push(*fd);
+            false
+        } else {
+            true
+        }
+    });
+
+    if fds.is_empty() {
+        let available = activated
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                r#"no socket named "{}" was passed by systemd (available: {:?})"#,
+                name, available
+            ),
+        ));
+    }
+
+    // all FDs are taken over before checking for errors, so that none is leaked if one fails
+    fds.into_iter()
+        // SAFETY: FDs were passed to this process by systemd and are removed from the activated
+        // list above so they are only ever adopted once.
+        .map(|fd| unsafe { mio_listener_from_raw_fd(fd) })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn activated_from_env() -> Vec<(String, RawFd)> {
+    let pid = env::var("LISTEN_PID").ok();
+    let fds = env::var("LISTEN_FDS").ok();
+    let names = env::var("LISTEN_FDNAMES").ok();
+
+    // like `sd_listen_fds(3)`, do not pass activation variables on to child processes
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        if env::var_os(var).is_some() {
+            env::remove_var(var);
+        }
+    }
+
+    match parse_activated(
+        pid.as_deref(),
+        fds.as_deref(),
+        names.as_deref(),
+        process::id(),
+        LISTEN_FDS_START,
+    ) {
+        Ok(activated) => {
+            trace!("found {} activated sockets", activated.len());
+            activated
+        }
+        Err(err) => {
+            error!("can not read systemd socket activation environment: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Parses the socket activation variables into FD names and numbers.
+fn parse_activated(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    listen_fdnames: Option<&str>,
+    pid: u32,
+    start: RawFd,
+) -> io::Result<Vec<(String, RawFd)>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let (listen_pid, listen_fds) = match (listen_pid, listen_fds) {
+        (Some(listen_pid), Some(listen_fds)) => (listen_pid, listen_fds),
+        _ => return Ok(Vec::new()),
+    };
+
+    let listen_pid = listen_pid
+        .parse::<u32>()
+        .map_err(|_| invalid(format!("invalid LISTEN_PID: {listen_pid:?}")))?;
+
+    // sockets are meant for another process (e.g. our parent)
+    if listen_pid != pid {
+        return Ok(Vec::new());
+    }
+
+    let count = listen_fds
+        .parse::<u16>()
+        .map_err(|_| invalid(format!("invalid LISTEN_FDS: {listen_fds:?}")))?;
+
+    let names = match listen_fdnames {
+        Some(names) => {
+            let names = names.split(':').map(str::to_owned).collect::<Vec<_>>();
+
+            if names.len() != usize::from(count) {
+                return Err(invalid(format!(
+                    "LISTEN_FDNAMES has {} names but LISTEN_FDS is {}",
+                    names.len(),
+                    count
+                )));
+            }
+
+            names
+        }
+        None => vec![UNKNOWN_NAME.to_owned(); usize::from(count)],
+    };
+
+    Ok(names.into_iter().zip(start..).collect())
+}
+
+/// Sends state notifications to the service manager.
+#[derive(Debug)]
+pub(crate) struct Notifier {
+    socket: Socket,
+    addr: SockAddr,
+}
+
+impl Notifier {
+    /// Connects to the socket in `NOTIFY_SOCKET`, if any.
+    pub(crate) fn from_env() -> Option<Self> {
+        let path = env::var_os("NOTIFY_SOCKET")?;
+
+        // abstract socket names are given with a leading `@`
+        let path = match path.to_str().and_then(|path| path.strip_prefix('@')) {
+            Some(name) => format!("\0{name}").into(),
+            None => path,
+        };
+
+        let notifier = SockAddr::unix(path).and_then(|addr| {
+            let socket = Socket::new(Domain::UNIX, Type::DGRAM, None)?;
+            Ok(Notifier { socket, addr })
+        });
+
+        match notifier {
+            Ok(notifier) => Some(notifier),
+            Err(err) => {
+                error!("can not set up systemd notifications: {err}");
+                None
+            }
+        }
+    }
+
+    /// Sends a newline-separated list of `VARIABLE=value` assignments.
+    pub(crate) fn notify(&self, state: &str) {
+        trace!("sending systemd notification: {:?}", state);
+
+        if let Err(err) = self.socket.send_to(state.as_bytes(), &self.addr) {
+            warn!("can not send systemd notification: {err}");
+        }
+    }
+
+    /// Watchdog timeout requested by the service manager, if any.
+    pub(crate) fn watchdog_timeout() -> Option<Duration> {
+        if let Ok(pid) = env::var("WATCHDOG_PID") {
+            if pid.parse::<u32>().ok() != Some(process::id()) {
+                return None;
+            }
+        }
+
+        let usec = env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+        (usec > 0).then(|| Duration::from_micros(usec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net,
+        os::unix::{io::IntoRawFd as _, net::UnixListener},
+    };
+
+    use actix_rt::net::{TcpStream, UnixStream};
+    use actix_service::fn_service;
+
+    use super::*;
+    use crate::Server;
+
+    #[test]
+    fn parse() {
+        let parse = |pid, fds, names| parse_activated(pid, fds, names, 42, 3);
+
+        assert!(parse(None, None, None).unwrap().is_empty());
+        assert!(parse(Some("7"), Some("2"), None).unwrap().is_empty());
+
+        assert_eq!(
+            parse(Some("42"), Some("2"), None).unwrap(),
+            vec![("unknown".to_owned(), 3), ("unknown".to_owned(), 4)],
+        );
+
+        assert_eq!(
+            parse(Some("42"), Some("2"), Some("web:admin")).unwrap(),
+            vec![("web".to_owned(), 3), ("admin".to_owned(), 4)],
+        );
+
+        assert!(parse(Some("42"), Some("2"), Some("web")).is_err());
+        assert!(parse(Some("x"), Some("2"), None).is_err());
+        assert!(parse(Some("42"), Some("x"), None).is_err());
+    }
+
+    #[test]
+    fn close_unadopted_on_error() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_addr = tcp.local_addr().unwrap();
+
+        ACTIVATED
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Vec::new)
+            .extend([
+                ("mixed".to_owned(), file.into_raw_fd()),
+                ("mixed".to_owned(), tcp.into_raw_fd()),
+            ]);
+
+        assert!(take_activated("mixed").is_err());
+
+        // socket after the one that could not be adopted is closed too
+        assert!(net::TcpStream::connect(tcp_addr).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn listen_activated() {
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_addr = tcp.local_addr().unwrap();
+        let spare = net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let uds_path = std::env::temp_dir().join(format!("actix-server-{}.sock", process::id()));
+        let _ = std::fs::remove_file(&uds_path);
+        let uds = UnixListener::bind(&uds_path).unwrap();
+
+        // stand in for systemd passing named FDs
+        ACTIVATED
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Vec::new)
+            .extend([
+                ("web".to_owned(), tcp.into_raw_fd()),
+                ("admin".to_owned(), uds.into_raw_fd()),
+                ("spare".to_owned(), spare.into_raw_fd()),
+            ]);
+
+        let err = Server::build()
+            .listen_uds_activated("spare", || fn_service(|_| async { Ok::<_, ()>(()) }))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = Server::build()
+            .listen_activated("missing", || fn_service(|_| async { Ok::<_, ()>(()) }))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("missing"));
+
+        let srv = Server::build()
+            .workers(1)
+            .disable_signals()
+            .listen_activated("web", || {
+                fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+            })
+            .unwrap()
+            .listen_uds_activated("admin", || {
+                fn_service(|_: UnixStream| async { Ok::<_, ()>(()) })
+            })
+            .unwrap()
+            .run();
+
+        let handle = srv.handle();
+        let srv = actix_rt::spawn(srv);
+
+        TcpStream::connect(tcp_addr).await.unwrap();
+        UnixStream::connect(&uds_path).await.unwrap();
+
+        handle.stop(true).await;
+        srv.await.unwrap().unwrap();
+    }
+}
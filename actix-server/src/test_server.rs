@@ -1,8 +1,11 @@
 use std::{io, net, sync::mpsc, thread};
 
 use actix_rt::{net::TcpStream, System};
+use tokio::io::DuplexStream;
 
-use crate::{Server, ServerBuilder, ServerHandle, ServerServiceFactory};
+use crate::{
+    MemoryConnector, MemoryListener, Server, ServerBuilder, ServerHandle, ServerServiceFactory,
+};
 
 /// A testing server.
 ///
@@ -28,15 +31,53 @@ use crate::{Server, ServerBuilder, ServerHandle, ServerServiceFactory};
 /// ```
 pub struct TestServer;
 
+/// Builder of a [`TestServer`] with any number of listeners.
+///
+/// Created with [`TestServer::build()`] or [`TestServer::build_with()`]. Listeners are bound to
+/// unused addresses right away and looked up by name on the [`TestServerHandle`].
+///
+/// # Examples
+/// ```
+/// use actix_rt::net::TcpStream;
+/// use actix_server::TestServer;
+/// use actix_service::fn_service;
+/// use tokio::io::DuplexStream;
+///
+/// let srv = TestServer::build()
+///     .workers(2)
+///     .listen("tcp", || fn_service(|_: TcpStream| async { Ok::<_, ()>(()) }))
+///     .listen_memory("memory", || fn_service(|_: DuplexStream| async { Ok::<_, ()>(()) }))
+///     .start();
+///
+/// let addr = srv.addr_of("tcp");
+/// let stream = srv.connect_memory_to("memory").unwrap();
+///
+/// assert!(srv.stop());
+/// ```
+pub struct TestServerBuilder {
+    builder: ServerBuilder,
+    workers: usize,
+    listeners: Vec<(String, TestAddr)>,
+}
+
 /// Test server handle.
+///
+/// Dropping the handle stops the server without waiting for connections to finish.
 pub struct TestServerHandle {
-    addr: net::SocketAddr,
+    listeners: Vec<(String, TestAddr)>,
     host: String,
-    port: u16,
     server_handle: ServerHandle,
     thread_handle: Option<thread::JoinHandle<io::Result<()>>>,
 }
 
+/// Where a listener of a test server can be reached.
+enum TestAddr {
+    Tcp(net::SocketAddr),
+    #[cfg(unix)]
+    Uds(std::path::PathBuf),
+    Memory(MemoryConnector),
+}
+
 impl TestServer {
     /// Start new `TestServer` using application factory and default server config.
     pub fn start(factory: impl ServerServiceFactory<TcpStream>) -> TestServerHandle {
@@ -48,37 +89,45 @@ impl TestServer {
         server_builder: ServerBuilder,
         factory: impl ServerServiceFactory<TcpStream>,
     ) -> TestServerHandle {
-        let (tx, rx) = mpsc::channel();
-
-        // run server in separate thread
-        let thread_handle = thread::spawn(move || {
-            let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
-            let local_addr = lst.local_addr().unwrap();
+        Self::build_with(server_builder)
+            .listen("test", factory)
+            .start()
+    }
 
-            System::new().block_on(async {
-                let server = server_builder
-                    .listen("test", lst, factory)
-                    .unwrap()
-                    .workers(1)
-                    .disable_signals()
-                    .run();
-
-                tx.send((server.handle(), local_addr)).unwrap();
-                server.await
-            })
-        });
+    /// Start new `TestServer` serving in-memory connections, using application factory and default
+    /// server config.
+    ///
+    /// No sockets are bound; connect to the server with
+    /// [`connect_memory()`](TestServerHandle::connect_memory()).
+    pub fn start_memory(factory: impl ServerServiceFactory<DuplexStream>) -> TestServerHandle {
+        Self::start_memory_with_builder(Server::build(), factory)
+    }
 
-        let (server_handle, addr) = rx.recv().unwrap();
+    /// Start new `TestServer` serving in-memory connections, using application factory and server
+    /// builder.
+    pub fn start_memory_with_builder(
+        server_builder: ServerBuilder,
+        factory: impl ServerServiceFactory<DuplexStream>,
+    ) -> TestServerHandle {
+        Self::build_with(server_builder)
+            .listen_memory("test", factory)
+            .start()
+    }
 
-        let host = format!("{}", addr.ip());
-        let port = addr.port();
+    /// Create `TestServer` builder using default server config.
+    pub fn build() -> TestServerBuilder {
+        Self::build_with(Server::build())
+    }
 
-        TestServerHandle {
-            addr,
-            host,
-            port,
-            server_handle,
-            thread_handle: Some(thread_handle),
+    /// Create `TestServer` builder using server builder.
+    ///
+    /// OS signal handling is disabled and the number of workers is set by
+    /// [`TestServerBuilder::workers()`].
+    pub fn build_with(server_builder: ServerBuilder) -> TestServerBuilder {
+        TestServerBuilder {
+            builder: server_builder,
+            workers: 1,
+            listeners: Vec::new(),
         }
     }
 
@@ -97,42 +146,295 @@ impl TestServer {
 
         net::TcpListener::from(socket).local_addr().unwrap()
     }
+
+    /// Get unused path for a Unix domain socket in the temporary directory.
+    #[cfg(unix)]
+    pub fn unused_uds_path() -> std::path::PathBuf {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        std::env::temp_dir().join(format!(
+            "actix-server-test-{}-{}.sock",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+}
+
+impl TestServerBuilder {
+    /// Sets number of workers to start, 1 by default.
+    pub fn workers(mut self, num: usize) -> Self {
+        self.workers = num;
+        self
+    }
+
+    /// Adds service listening on an unused local TCP address.
+    pub fn listen<F, N>(mut self, name: N, factory: F) -> Self
+    where
+        F: ServerServiceFactory<TcpStream>,
+        N: AsRef<str>,
+    {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+
+        self.builder = self.builder.listen(name.as_ref(), lst, factory).unwrap();
+        self.listeners
+            .push((name.as_ref().to_owned(), TestAddr::Tcp(addr)));
+        self
+    }
+
+    /// Adds service listening on an unused Unix domain socket path.
+    #[cfg(unix)]
+    pub fn listen_uds<F, N>(mut self, name: N, factory: F) -> Self
+    where
+        F: ServerServiceFactory<actix_rt::net::UnixStream>,
+        N: AsRef<str>,
+    {
+        let path = TestServer::unused_uds_path();
+
+        self.builder = self
+            .builder
+            .bind_uds(name.as_ref(), &path, factory)
+            .unwrap();
+        self.listeners
+            .push((name.as_ref().to_owned(), TestAddr::Uds(path)));
+        self
+    }
+
+    /// Adds service serving in-memory connections.
+    pub fn listen_memory<F, N>(mut self, name: N, factory: F) -> Self
+    where
+        F: ServerServiceFactory<DuplexStream>,
+        N: AsRef<str>,
+    {
+        let lst = MemoryListener::new().unwrap();
+        let connector = lst.connector();
+
+        self.builder = self.builder.listen_custom(name.as_ref(), lst, factory);
+        self.listeners
+            .push((name.as_ref().to_owned(), TestAddr::Memory(connector)));
+        self
+    }
+
+    /// Starts the server and waits until all of its workers have started their services.
+    ///
+    /// # Panics
+    /// Panics if the server fails to start.
+    pub fn start(self) -> TestServerHandle {
+        let TestServerBuilder {
+            builder,
+            workers,
+            listeners,
+        } = self;
+
+        let (tx, rx) = mpsc::channel();
+
+        // run server in separate thread
+        let thread_handle = thread::spawn(move || {
+            System::new().block_on(async {
+                let server = builder.workers(workers).disable_signals().run();
+                let handle = server.handle();
+
+                // the server answers commands only once all workers have started
+                actix_rt::spawn(async move {
+                    if handle.stats().await.is_some() {
+                        let _ = tx.send(handle);
+                    }
+                });
+
+                server.await
+            })
+        });
+
+        let server_handle = match rx.recv() {
+            Ok(handle) => handle,
+            Err(_) => panic!("test server failed to start: {:?}", thread_handle.join()),
+        };
+
+        let host = listeners
+            .iter()
+            .find_map(|(_, addr)| match addr {
+                TestAddr::Tcp(addr) => Some(addr.ip().to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        TestServerHandle {
+            listeners,
+            host,
+            server_handle,
+            thread_handle: Some(thread_handle),
+        }
+    }
 }
 
 impl TestServerHandle {
     /// Test server host.
+    ///
+    /// # Panics
+    /// Panics if the server has no TCP listener.
     pub fn host(&self) -> &str {
+        self.addr();
         &self.host
     }
 
     /// Test server port.
+    ///
+    /// # Panics
+    /// Panics if the server has no TCP listener.
     pub fn port(&self) -> u16 {
-        self.port
+        self.addr().port()
     }
 
     /// Get test server address.
+    ///
+    /// This is the address of the first TCP listener.
+    ///
+    /// # Panics
+    /// Panics if the server has no TCP listener.
     pub fn addr(&self) -> net::SocketAddr {
-        self.addr
+        self.tcp_addr(None)
+            .expect("test server is not listening on a TCP socket")
+    }
+
+    /// Get address of the TCP listener named `name`.
+    ///
+    /// # Panics
+    /// Panics if the server has no TCP listener named `name`.
+    pub fn addr_of(&self, name: &str) -> net::SocketAddr {
+        self.tcp_addr(Some(name))
+            .unwrap_or_else(|| panic!(r#"test server has no TCP listener "{name}""#))
+    }
+
+    /// Get path of the Unix domain socket listener named `name`.
+    ///
+    /// # Panics
+    /// Panics if the server has no UDS listener named `name`.
+    #[cfg(unix)]
+    pub fn uds_path(&self, name: &str) -> &std::path::Path {
+        self.find(Some(name), |addr| match addr {
+            TestAddr::Uds(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!(r#"test server has no UDS listener "{name}""#))
+    }
+
+    /// Stop server gracefully, waiting for workers to finish their connections.
+    ///
+    /// Returns `true` if all connections were finished, or `false` if some were dropped once the
+    /// [shutdown timeout] passed.
+    ///
+    /// [shutdown timeout]: crate::ServerBuilder::shutdown_timeout()
+    pub fn stop(mut self) -> bool {
+        let mut completed = self.server_handle.stop_completed(true);
+        self.join();
+        completed.try_recv().unwrap_or(false)
+    }
+
+    /// Stop server without waiting for connections to finish.
+    fn stop_forced(&mut self) {
+        if self.thread_handle.is_some() {
+            drop(self.server_handle.stop(false));
+            self.join();
+        }
     }
 
-    /// Stop server.
-    fn stop(&mut self) {
-        drop(self.server_handle.stop(false));
+    fn join(&mut self) {
         self.thread_handle.take().unwrap().join().unwrap().unwrap();
     }
 
     /// Connect to server, returning a Tokio `TcpStream`.
+    ///
+    /// Connects to the first TCP listener.
     pub fn connect(&self) -> io::Result<TcpStream> {
-        TcpStream::from_std(net::TcpStream::connect(self.addr)?)
+        Self::connect_tcp(self.tcp_addr(None))
+    }
+
+    /// Connect to the TCP listener named `name`, returning a Tokio `TcpStream`.
+    pub fn connect_to(&self, name: &str) -> io::Result<TcpStream> {
+        Self::connect_tcp(self.tcp_addr(Some(name)))
+    }
+
+    /// Connect to the Unix domain socket listener named `name`.
+    #[cfg(unix)]
+    pub fn connect_uds(&self, name: &str) -> io::Result<actix_rt::net::UnixStream> {
+        let path = self
+            .find(Some(name), |addr| match addr {
+                TestAddr::Uds(path) => Some(path),
+                _ => None,
+            })
+            .ok_or_else(|| not_connected("UDS", Some(name)))?;
+
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        stream.set_nonblocking(true)?;
+        actix_rt::net::UnixStream::from_std(stream)
+    }
+
+    /// Connect to server serving in-memory connections, returning the client half of a
+    /// connection.
+    ///
+    /// Connects to the first in-memory listener.
+    pub fn connect_memory(&self) -> io::Result<DuplexStream> {
+        self.memory_connector(None)?.connect()
+    }
+
+    /// Connect to the in-memory listener named `name`, returning the client half of a connection.
+    pub fn connect_memory_to(&self, name: &str) -> io::Result<DuplexStream> {
+        self.memory_connector(Some(name))?.connect()
+    }
+
+    fn connect_tcp(addr: Option<net::SocketAddr>) -> io::Result<TcpStream> {
+        let addr = addr.ok_or_else(|| not_connected("TCP", None))?;
+
+        let stream = net::TcpStream::connect(addr)?;
+        stream.set_nonblocking(true)?;
+        TcpStream::from_std(stream)
+    }
+
+    fn tcp_addr(&self, name: Option<&str>) -> Option<net::SocketAddr> {
+        self.find(name, |addr| match addr {
+            TestAddr::Tcp(addr) => Some(*addr),
+            _ => None,
+        })
+    }
+
+    fn memory_connector(&self, name: Option<&str>) -> io::Result<&MemoryConnector> {
+        self.find(name, |addr| match addr {
+            TestAddr::Memory(connector) => Some(connector),
+            _ => None,
+        })
+        .ok_or_else(|| not_connected("in-memory", name))
+    }
+
+    /// Finds first listener accepted by `f`, optionally only considering those named `name`.
+    fn find<'a, T>(
+        &'a self,
+        name: Option<&str>,
+        f: impl Fn(&'a TestAddr) -> Option<T>,
+    ) -> Option<T> {
+        self.listeners
+            .iter()
+            .filter(|(lst_name, _)| name.map_or(true, |name| name == lst_name))
+            .find_map(|(_, addr)| f(addr))
     }
 }
 
 impl Drop for TestServerHandle {
     fn drop(&mut self) {
-        self.stop()
+        self.stop_forced()
     }
 }
 
+fn not_connected(kind: &str, name: Option<&str>) -> io::Error {
+    let msg = match name {
+        Some(name) => format!(r#"test server has no {kind} listener "{name}""#),
+        None => format!("test server has no {kind} listener"),
+    };
+
+    io::Error::new(io::ErrorKind::NotConnected, msg)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_service::fn_service;
@@ -149,5 +451,27 @@ mod tests {
     async fn connect_in_actix_runtime() {
         let srv = TestServer::start(|| fn_service(|_sock| async move { Ok::<_, ()>(()) }));
         assert!(srv.connect().is_ok());
+        assert!(srv.connect_memory().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn connect_memory() {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let srv = TestServer::start_memory(|| {
+            fn_service(|mut stream: DuplexStream| async move {
+                let mut buf = [0; 4];
+                stream.read_exact(&mut buf).await?;
+                stream.write_all(&buf).await
+            })
+        });
+        assert!(srv.connect().is_err());
+
+        let mut stream = srv.connect_memory().unwrap();
+        stream.write_all(b"ping").await.unwrap();
+
+        let mut res = Vec::new();
+        stream.read_to_end(&mut res).await.unwrap();
+        assert_eq!(res, b"ping");
     }
 }
@@ -0,0 +1,144 @@
+//! Datagram services that receive a UDP socket per worker.
+
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket as StdUdpSocket},
+    sync::Arc,
+};
+
+use actix_rt::net::UdpSocket;
+use actix_service::{Service, ServiceFactory as BaseServiceFactory};
+use futures_core::future::LocalBoxFuture;
+use tracing::{error, trace};
+
+use crate::socket::ToSocketAddrs;
+
+#[doc(hidden)]
+pub trait UdpServiceFactory: Send + Clone + 'static {
+    type Factory: BaseServiceFactory<UdpSocket, Config = ()>;
+
+    fn create(&self) -> Self::Factory;
+}
+
+impl<F, T> UdpServiceFactory for F
+where
+    F: Fn() -> T + Send + Clone + 'static,
+    T: BaseServiceFactory<UdpSocket, Config = ()>,
+{
+    type Factory = T;
+
+    fn create(&self) -> T {
+        (self)()
+    }
+}
+
+pub(crate) trait InternalUdpServiceFactory: Send {
+    fn name(&self) -> &str;
+
+    fn local_addr(&self) -> SocketAddr;
+
+    fn clone_factory(&self) -> Box<dyn InternalUdpServiceFactory>;
+
+    fn create(&self) -> LocalBoxFuture<'static, Result<BoxedUdpService, ()>>;
+}
+
+/// Started service bound to its socket, run once on the worker's event loop.
+pub(crate) type BoxedUdpService = Box<dyn FnOnce() -> LocalBoxFuture<'static, ()>>;
+
+pub(crate) struct UdpNewService<F> {
+    name: String,
+    inner: F,
+    addr: SocketAddr,
+    socket: Arc<StdUdpSocket>,
+}
+
+impl<F: UdpServiceFactory> UdpNewService<F> {
+    pub(crate) fn create(
+        name: String,
+        inner: F,
+        socket: StdUdpSocket,
+    ) -> io::Result<Box<dyn InternalUdpServiceFactory>> {
+        Ok(Box::new(Self {
+            name,
+            inner,
+            addr: socket.local_addr()?,
+            socket: Arc::new(socket),
+        }))
+    }
+}
+
+impl<F: UdpServiceFactory> InternalUdpServiceFactory for UdpNewService<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn clone_factory(&self) -> Box<dyn InternalUdpServiceFactory> {
+        Box::new(Self {
+            name: self.name.clone(),
+            inner: self.inner.clone(),
+            addr: self.addr,
+            socket: self.socket.clone(),
+        })
+    }
+
+    fn create(&self) -> LocalBoxFuture<'static, Result<BoxedUdpService, ()>> {
+        let name = self.name.clone();
+        let socket = self.socket.try_clone();
+        let fut = self.inner.create().new_service(());
+
+        Box::pin(async move {
+            let socket = socket.map_err(|err| error!("can not clone UDP socket: {err}"))?;
+            let service = fut.await.map_err(|_| ())?;
+
+            let run = move || -> LocalBoxFuture<'static, ()> {
+                Box::pin(async move {
+                    let socket = match UdpSocket::from_std(socket) {
+                        Ok(socket) => socket,
+                        Err(err) => {
+                            error!("can not register UDP socket: {err}");
+                            return;
+                        }
+                    };
+
+                    match service.call(socket).await {
+                        Ok(_) => trace!("datagram service {:?} has finished", name),
+                        Err(_) => error!("datagram service {:?} has failed", name),
+                    }
+                })
+            };
+
+            Ok(Box::new(run) as BoxedUdpService)
+        })
+    }
+}
+
+/// Binds a non-blocking UDP socket to every resolved address.
+pub(crate) fn bind_udp_addr<S: ToSocketAddrs>(addr: S) -> io::Result<Vec<StdUdpSocket>> {
+    let mut opt_err = None;
+    let mut sockets = Vec::new();
+
+    for addr in addr.to_socket_addrs()? {
+        match StdUdpSocket::bind(addr).and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        }) {
+            Ok(socket) => sockets.push(socket),
+            Err(err) => opt_err = Some(err),
+        }
+    }
+
+    if !sockets.is_empty() {
+        Ok(sockets)
+    } else if let Some(err) = opt_err.take() {
+        Err(err)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Can not bind to address.",
+        ))
+    }
+}
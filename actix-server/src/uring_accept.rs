@@ -0,0 +1,374 @@
+//! Multishot accept of workers' own listeners on io-uring.
+
+use std::{
+    io,
+    net::TcpStream,
+    os::unix::io::{AsRawFd, FromRawFd as _, RawFd},
+    task::{Context, Poll},
+};
+
+use io_uring::{cqueue, opcode, types, IoUring};
+use tokio::io::{unix::AsyncFd, Interest};
+use tracing::error;
+
+/// User data of cancel requests; accept requests carry the key of their listener.
+const CANCEL: u64 = u64::MAX;
+
+/// Number of submission queue entries; the completion queue holds twice as many.
+const ENTRIES: u32 = 256;
+
+/// Accepts connections of a worker's own listeners with a multishot accept request each.
+///
+/// Every request keeps accepting connections until it is cancelled, so accepting a connection
+/// takes no syscall of its own. Requests are cancelled while the worker does not accept, e.g.
+/// because it is paused or full; connections accepted before a cancellation completes are still
+/// returned.
+pub(crate) struct UringAccept<L: AsRawFd> {
+    /// Ring FD registered with the worker's reactor once polled; readable while completions are
+    /// queued.
+    fd: Option<AsyncFd<RawFd>>,
+    ring: IoUring,
+    listeners: Vec<Entry<L>>,
+}
+
+struct Entry<L> {
+    key: u64,
+    lst: L,
+    state: State,
+    /// Set once the listener is removed; it is dropped when its request ends.
+    removed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Armed,
+    Cancelling,
+}
+
+impl<L: AsRawFd> UringAccept<L> {
+    /// Creates ring for accepting from `listeners`; accepting starts once polled.
+    pub(crate) fn new(listeners: Vec<L>) -> io::Result<Self> {
+        let listeners = listeners
+            .into_iter()
+            .enumerate()
+            .map(|(key, lst)| Entry {
+                key: key as u64,
+                lst,
+                state: State::Idle,
+                removed: false,
+            })
+            .collect();
+
+        Ok(Self {
+            fd: None,
+            ring: IoUring::new(ENTRIES)?,
+            listeners,
+        })
+    }
+
+    /// Cancels armed requests until polled for accepting again.
+    pub(crate) fn stop(&mut self) -> io::Result<()> {
+        self.submit(false)
+    }
+
+    /// Removes listeners matching `f`, closing connections they accept until cancelled.
+    pub(crate) fn remove(&mut self, f: impl Fn(&L) -> bool) {
+        self.listeners
+            .retain(|entry| entry.state != State::Idle || !f(&entry.lst));
+
+        for entry in &mut self.listeners {
+            if f(&entry.lst) {
+                entry.removed = true;
+            }
+        }
+    }
+
+    /// Polls for an accepted connection, with requests armed while `accepting`.
+    pub(crate) fn poll_accept(
+        &mut self,
+        cx: &mut Context<'_>,
+        accepting: bool,
+    ) -> Poll<io::Result<(&L, TcpStream)>> {
+        if self.fd.is_none() {
+            self.fd = Some(AsyncFd::with_interest(
+                self.ring.as_raw_fd(),
+                Interest::READABLE,
+            )?);
+        }
+
+        loop {
+            self.submit(accepting)?;
+
+            if let Some(res) = self.next_completion() {
+                let (key, stream) = res?;
+                let entry = self.listeners.iter().find(|entry| entry.key == key);
+                // completions of removed listeners are not returned
+                let entry = entry.expect("accepted from unknown listener");
+                return Poll::Ready(Ok((&entry.lst, stream)));
+            }
+
+            let fd = self.fd.as_ref().unwrap();
+
+            match fd.poll_read_ready(cx) {
+                Poll::Ready(Ok(mut guard)) => guard.clear_ready(),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Stops accepting, returning the listeners with the connections accepted until then.
+    pub(crate) fn close(mut self) -> Vec<(L, Vec<TcpStream>)> {
+        let mut accepted = self.cancel_all();
+
+        self.listeners
+            .drain(..)
+            .filter(|entry| !entry.removed)
+            .map(|entry| {
+                let (streams, rest): (Vec<_>, Vec<_>) =
+                    accepted.drain(..).partition(|(key, _)| *key == entry.key);
+                accepted = rest;
+
+                let streams = streams.into_iter().map(|(_, stream)| stream).collect();
+                (entry.lst, streams)
+            })
+            .collect()
+    }
+
+    /// Arms or cancels requests depending on `accepting` and submits them.
+    fn submit(&mut self, accepting: bool) -> io::Result<()> {
+        let mut sq = self.ring.submission();
+        let mut submitted = false;
+
+        for entry in &mut self.listeners {
+            let (sqe, state) = match entry.state {
+                State::Idle if accepting && !entry.removed => {
+                    let fd = types::Fd(entry.lst.as_raw_fd());
+                    let sqe = opcode::AcceptMulti::new(fd)
+                        .flags(libc::SOCK_CLOEXEC)
+                        .build()
+                        .user_data(entry.key);
+
+                    (sqe, State::Armed)
+                }
+
+                State::Armed if !accepting || entry.removed => {
+                    let sqe = opcode::AsyncCancel::new(entry.key)
+                        .build()
+                        .user_data(CANCEL);
+
+                    (sqe, State::Cancelling)
+                }
+
+                _ => continue,
+            };
+
+            // SAFETY: requests only refer to the listener's FD, and listeners are kept until their
+            // request has ended.
+            unsafe { sq.push(&sqe) }.map_err(|_| {
+                io::Error::new(io::ErrorKind::Other, "io-uring submission queue is full")
+            })?;
+
+            entry.state = state;
+            submitted = true;
+        }
+
+        drop(sq);
+
+        if submitted {
+            self.ring.submit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the next completion of an accept request, skipping cancellations.
+    fn next_completion(&mut self) -> Option<io::Result<(u64, TcpStream)>> {
+        loop {
+            let cqe = self.ring.completion().next()?;
+            let key = cqe.user_data();
+
+            if key == CANCEL {
+                continue;
+            }
+
+            let res = cqe.result();
+            // SAFETY: a non-negative result of an accept request is the FD of a new connection.
+            let stream = (res >= 0).then(|| unsafe { TcpStream::from_raw_fd(res) });
+
+            let pos = self
+                .listeners
+                .iter()
+                .position(|entry| entry.key == key)
+                .expect("completion of unknown accept request");
+
+            // the request has ended, e.g. once cancelled, after an error or when completions
+            // overflowed; it is armed again on the next submit
+            if !cqueue::more(cqe.flags()) {
+                self.listeners[pos].state = State::Idle;
+
+                if self.listeners[pos].removed {
+                    self.listeners.remove(pos);
+                    continue;
+                }
+            }
+
+            if self.listeners[pos].removed {
+                continue;
+            }
+
+            match stream {
+                Some(stream) => return Some(Ok((key, stream))),
+                None if res == -libc::ECANCELED => continue,
+                None => return Some(Err(io::Error::from_raw_os_error(-res))),
+            }
+        }
+    }
+
+    /// Cancels all requests and waits for them to end, returning the connections accepted until
+    /// then.
+    fn cancel_all(&mut self) -> Vec<(u64, TcpStream)> {
+        let mut accepted = Vec::new();
+
+        loop {
+            if let Err(err) = self.submit(false) {
+                error!("can not stop accepting with io-uring: {err}");
+                break;
+            }
+
+            while let Some(res) = self.next_completion() {
+                if let Ok(conn) = res {
+                    accepted.push(conn);
+                }
+            }
+
+            if self
+                .listeners
+                .iter()
+                .all(|entry| entry.state == State::Idle)
+            {
+                break;
+            }
+
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    error!("can not stop accepting with io-uring: {err}");
+                    break;
+                }
+            }
+        }
+
+        accepted
+    }
+}
+
+impl<L: AsRawFd> Drop for UringAccept<L> {
+    fn drop(&mut self) {
+        // requests must end before their listeners are closed; connections accepted in the
+        // meantime are closed
+        if self
+            .listeners
+            .iter()
+            .any(|entry| entry.state != State::Idle)
+        {
+            drop(self.cancel_all());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, time::Duration};
+
+    use futures_util::future::poll_fn;
+
+    use super::*;
+
+    /// Polls for a connection until `timeout` passes.
+    async fn accept(
+        uring: &mut UringAccept<TcpListener>,
+        accepting: bool,
+        timeout: Duration,
+    ) -> Option<TcpStream> {
+        let accept = poll_fn(|cx| {
+            uring
+                .poll_accept(cx, accepting)
+                .map_ok(|(_, stream)| stream)
+        });
+        actix_rt::time::timeout(timeout, accept)
+            .await
+            .ok()
+            .map(Result::unwrap)
+    }
+
+    #[test]
+    fn accept_lifecycle() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = lst.local_addr().unwrap();
+            let mut uring = UringAccept::new(vec![lst]).unwrap();
+
+            let long = Duration::from_secs(5);
+            let short = Duration::from_millis(200);
+
+            // one request accepts many connections
+            let _c1 = std::net::TcpStream::connect(addr).unwrap();
+            let _c2 = std::net::TcpStream::connect(addr).unwrap();
+            assert!(accept(&mut uring, true, long).await.is_some());
+            assert!(accept(&mut uring, true, long).await.is_some());
+
+            // cancelled request leaves connections queued on the listener
+            assert!(accept(&mut uring, false, short).await.is_none());
+            let _c3 = std::net::TcpStream::connect(addr).unwrap();
+            assert!(accept(&mut uring, false, short).await.is_none());
+
+            // armed again
+            assert!(accept(&mut uring, true, long).await.is_some());
+
+            // queued connections are left on the returned listener
+            assert!(accept(&mut uring, false, short).await.is_none());
+            let _c4 = std::net::TcpStream::connect(addr).unwrap();
+            let mut closed = uring.close();
+            assert_eq!(closed.len(), 1);
+            let (lst, accepted) = closed.pop().unwrap();
+            assert!(accepted.is_empty());
+            assert!(lst.accept().is_ok());
+        });
+    }
+
+    #[test]
+    fn remove_listener() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let lst1 = TcpListener::bind("127.0.0.1:0").unwrap();
+            let lst2 = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr1 = lst1.local_addr().unwrap();
+            let addr2 = lst2.local_addr().unwrap();
+            let mut uring = UringAccept::new(vec![lst1, lst2]).unwrap();
+
+            let short = Duration::from_millis(200);
+            assert!(accept(&mut uring, true, short).await.is_none());
+
+            uring.remove(|lst| lst.local_addr().unwrap() == addr1);
+            assert!(accept(&mut uring, true, short).await.is_none());
+            assert_eq!(uring.listeners.len(), 1);
+
+            let _c1 = std::net::TcpStream::connect(addr2).unwrap();
+            assert!(accept(&mut uring, true, Duration::from_secs(5))
+                .await
+                .is_some());
+        });
+    }
+}
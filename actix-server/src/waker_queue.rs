@@ -5,8 +5,9 @@ use std::{
 };
 
 use mio::{Registry, Token as MioToken, Waker};
+use tokio::sync::oneshot;
 
-use crate::worker::WorkerHandleAccept;
+use crate::{accept::ServerSocketInfo, worker::WorkerHandleAccept};
 
 /// Waker token for `mio::Poll` instance.
 pub(crate) const WAKER_TOKEN: MioToken = MioToken(usize::MAX);
@@ -62,7 +63,7 @@ impl WakerQueue {
 
     /// Reset the waker queue so it does not grow infinitely.
     pub(crate) fn reset(queue: &mut VecDeque<WakerInterest>) {
-        std::mem::swap(&mut VecDeque::<WakerInterest>::with_capacity(16), queue);
+        *queue = VecDeque::<WakerInterest>::with_capacity(16);
     }
 }
 
@@ -71,14 +72,33 @@ impl WakerQueue {
 /// These interests should not be confused with `mio::Interest` and mostly not I/O related
 pub(crate) enum WakerInterest {
     /// `WorkerAvailable` is an interest from `Worker` notifying `Accept` there is a worker
-    /// available and can accept new tasks.
-    WorkerAvailable(usize),
+    /// available and can accept new tasks. It carries the index and generation of the worker, as
+    /// connections of a replaced worker may still finish after its replacement started.
+    WorkerAvailable(usize, u64),
     /// `Pause`, `Resume`, `Stop` Interest are from `ServerBuilder` future. It listens to
     /// `ServerCommand` and notify `Accept` to do exactly these tasks.
     Pause,
     Resume,
     Stop,
+    /// `Detach` is like `Stop` but leaves listeners registered. Used when listeners were handed
+    /// off to a new process so that their UDS paths are not removed.
+    #[cfg(unix)]
+    Detach,
     /// `Worker` is an interest that is triggered after a worker faults. This is determined by
     /// trying to send work to it. `Accept` would be waked up and add the new `WorkerHandleAccept`.
     Worker(WorkerHandleAccept),
+    /// `RemoveWorker` is an interest from `ServerBuilder` future to stop sending connections to
+    /// the worker with given index. Its handle is sent back so that it is only dropped once the
+    /// worker was told to stop.
+    RemoveWorker(usize, oneshot::Sender<WorkerHandleAccept>),
+    /// `WorkerStalled` is an interest from `ServerBuilder` future to stop sending connections to
+    /// the worker with given index until `WorkerRecovered` is received, as it missed heartbeats.
+    WorkerStalled(usize),
+    WorkerRecovered(usize),
+    /// `Bind` is an interest from `ServerBuilder` future carrying listeners added to the running
+    /// server. Their services are already started on every worker.
+    Bind(Vec<ServerSocketInfo>),
+    /// `Unbind` is an interest from `ServerBuilder` future to stop accepting on and close the
+    /// listeners with given tokens. The sender is notified once they are closed.
+    Unbind(Vec<usize>, oneshot::Sender<()>),
 }
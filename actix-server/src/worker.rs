@@ -1,11 +1,12 @@
 use std::{
+    collections::VecDeque,
     future::Future,
     io, mem,
     num::NonZeroUsize,
     pin::Pin,
     rc::Rc,
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     task::{Context, Poll},
@@ -13,11 +14,14 @@ use std::{
 };
 
 use actix_rt::{
+    net::TcpListener,
     spawn,
+    task::JoinHandle,
     time::{sleep, Instant, Sleep},
     Arbiter, ArbiterHandle, System,
 };
 use futures_core::{future::LocalBoxFuture, ready};
+use local_waker::LocalWaker;
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     oneshot,
@@ -25,11 +29,21 @@ use tokio::sync::{
 use tracing::{error, info, trace};
 
 use crate::{
-    service::{BoxedServerService, InternalServiceFactory},
-    socket::MioStream,
+    accept::connection_error,
+    access::ListenerAccess,
+    limits::IpPermit,
+    restart::{FaultHistory, RestartLimitAction, RestartPolicy},
+    service::{BoxedServerService, ClosedService, ClosedServiceFactory, InternalServiceFactory},
+    shutdown::WorkerShutdown,
+    socket::{MioStream, ReusePortListener, StdTcpListener},
+    stats::ListenerCounters,
+    udp::{BoxedUdpService, InternalUdpServiceFactory},
     waker_queue::{WakerInterest, WakerQueue},
 };
 
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+use crate::uring_accept::UringAccept;
+
 /// Stop worker message. Returns `true` on successful graceful shutdown
 /// and `false` if some connections still alive when shutdown execute.
 pub(crate) struct Stop {
@@ -37,26 +51,62 @@ pub(crate) struct Stop {
     tx: oneshot::Sender<bool>,
 }
 
+/// Messages sent from server to worker.
+pub(crate) enum WorkerCommand {
+    Stop(Stop),
+
+    /// Stop accepting connections on the worker's own listeners.
+    Pause,
+
+    /// Resume accepting connections on the worker's own listeners.
+    Resume,
+
+    /// Start a service for a listener added to the running server.
+    ///
+    /// Returns `true` if the service was created.
+    AddService {
+        factory: Box<dyn InternalServiceFactory>,
+        tx: oneshot::Sender<bool>,
+    },
+
+    /// Replace the service of a listener removed from the running server with a closed one.
+    RemoveService {
+        token: usize,
+        tx: oneshot::Sender<()>,
+    },
+}
+
 #[derive(Debug)]
 pub(crate) struct Conn {
     pub io: MioStream,
     pub token: usize,
+    /// Counts the connection towards the per-IP limits of its listener.
+    pub permit: Option<IpPermit>,
+    pub accepted_at: std::time::Instant,
 }
 
 /// Create accept and server worker handles.
 fn handle_pair(
     idx: usize,
     conn_tx: UnboundedSender<Conn>,
-    stop_tx: UnboundedSender<Stop>,
+    cmd_tx: UnboundedSender<WorkerCommand>,
     counter: Counter,
+    heartbeat: Heartbeat,
 ) -> (WorkerHandleAccept, WorkerHandleServer) {
     let accept = WorkerHandleAccept {
         idx,
         conn_tx,
-        counter,
+        counter: counter.clone(),
     };
 
-    let server = WorkerHandleServer { idx, stop_tx };
+    let server = WorkerHandleServer {
+        idx,
+        cmd_tx,
+        counter: counter.clone(),
+        heartbeat,
+        restarts: 0,
+        stalled: false,
+    };
 
     (accept, server)
 }
@@ -78,17 +128,23 @@ fn handle_pair(
 ///
 /// Hence, a wake up would only happen after `Accept` increment it to limit.
 /// And a decrement to limit always wake up `Accept`.
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub(crate) struct Counter {
     counter: Arc<AtomicUsize>,
     limit: usize,
+    /// Tells workers started with the same index apart, e.g. a stalled worker from its
+    /// replacement.
+    generation: u64,
 }
 
 impl Counter {
     pub(crate) fn new(limit: usize) -> Self {
+        static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
         Self {
             counter: Arc::new(AtomicUsize::new(1)),
             limit,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -107,11 +163,51 @@ impl Counter {
     pub(crate) fn total(&self) -> usize {
         self.counter.load(Ordering::SeqCst) - 1
     }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Check if counter has reached its limit.
+    pub(crate) fn is_full(&self) -> bool {
+        self.total() >= self.limit
+    }
+}
+
+/// Timestamp of a worker's last heartbeat, shared with the server.
+///
+/// A worker beats periodically from a task on its own event loop, so a missed heartbeat means the
+/// worker's thread is blocked or gone.
+#[derive(Debug, Clone)]
+pub(crate) struct Heartbeat {
+    epoch: Instant,
+    last: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub(crate) fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Record a heartbeat now.
+    pub(crate) fn beat(&self) {
+        let now = self.epoch.elapsed().as_millis() as u64;
+        self.last.store(now, Ordering::Relaxed);
+    }
+
+    /// Time since last heartbeat.
+    pub(crate) fn elapsed(&self) -> Duration {
+        let last = Duration::from_millis(self.last.load(Ordering::Relaxed));
+        self.epoch.elapsed().saturating_sub(last)
+    }
 }
 
 pub(crate) struct WorkerCounter {
     idx: usize,
-    inner: Rc<(WakerQueue, Counter)>,
+    inner: Rc<(WakerQueue, Counter, LocalWaker)>,
 }
 
 impl Clone for WorkerCounter {
@@ -127,27 +223,60 @@ impl WorkerCounter {
     pub(crate) fn new(idx: usize, waker_queue: WakerQueue, counter: Counter) -> Self {
         Self {
             idx,
-            inner: Rc::new((waker_queue, counter)),
+            inner: Rc::new((waker_queue, counter, LocalWaker::new())),
         }
     }
 
     #[inline(always)]
     pub(crate) fn guard(&self) -> WorkerCounterGuard {
-        WorkerCounterGuard(self.clone())
+        WorkerCounterGuard(self.clone(), None)
+    }
+
+    fn inc(&self) {
+        self.inner.1.inc();
     }
 
     fn total(&self) -> usize {
         self.inner.1.total()
     }
+
+    /// Check if worker has reached its connection limit, registering `cx` to be woken up once it
+    /// drops below it again.
+    fn poll_full(&self, cx: &mut Context<'_>) -> bool {
+        if self.inner.1.is_full() {
+            self.inner.2.register(cx.waker());
+            // connections may have finished in the meantime
+            self.inner.1.is_full()
+        } else {
+            false
+        }
+    }
 }
 
-pub(crate) struct WorkerCounterGuard(WorkerCounter);
+pub(crate) struct WorkerCounterGuard(WorkerCounter, Option<IpPermit>);
+
+impl WorkerCounterGuard {
+    /// Returns index of the worker the connection is counted by.
+    pub(crate) fn worker(&self) -> usize {
+        self.0.idx
+    }
+
+    /// Keeps `permit` until the connection is closed.
+    fn with_permit(mut self, permit: Option<IpPermit>) -> Self {
+        self.1 = permit;
+        self
+    }
+}
 
 impl Drop for WorkerCounterGuard {
     fn drop(&mut self) {
-        let (waker_queue, counter) = &*self.0.inner;
+        let (waker_queue, counter, waker) = &*self.0.inner;
         if counter.dec() {
-            waker_queue.wake(WakerInterest::WorkerAvailable(self.0.idx));
+            waker_queue.wake(WakerInterest::WorkerAvailable(
+                self.0.idx,
+                counter.generation(),
+            ));
+            waker.wake();
         }
     }
 }
@@ -173,25 +302,79 @@ impl WorkerHandleAccept {
         self.conn_tx.send(conn).map_err(|msg| msg.0)
     }
 
+    /// Generation of the worker, telling it apart from other workers with the same index.
+    pub(crate) fn generation(&self) -> u64 {
+        self.counter.generation()
+    }
+
     #[inline(always)]
     pub(crate) fn inc_counter(&self) -> bool {
         self.counter.inc()
     }
+
+    /// Number of connections currently handled by the worker.
+    pub(crate) fn connections(&self) -> usize {
+        self.counter.total()
+    }
+
+    /// Check if the worker has reached its connection limit.
+    pub(crate) fn is_full(&self) -> bool {
+        self.counter.is_full()
+    }
 }
 
-/// Handle to worker than can send stop message to worker.
+/// Handle to worker than can send commands to worker.
 ///
 /// Held by [ServerBuilder](crate::builder::ServerBuilder).
 #[derive(Debug)]
 pub(crate) struct WorkerHandleServer {
     pub(crate) idx: usize,
-    stop_tx: UnboundedSender<Stop>,
+    cmd_tx: UnboundedSender<WorkerCommand>,
+    counter: Counter,
+    heartbeat: Heartbeat,
+    /// Number of times the worker at this index was restarted.
+    pub(crate) restarts: usize,
+    /// Set while the worker has missed its heartbeats for longer than the stall timeout.
+    pub(crate) stalled: bool,
 }
 
 impl WorkerHandleServer {
+    /// Number of connections currently handled by the worker.
+    pub(crate) fn connections(&self) -> usize {
+        self.counter.total()
+    }
+
+    /// Time since the worker's last heartbeat.
+    pub(crate) fn since_heartbeat(&self) -> Duration {
+        self.heartbeat.elapsed()
+    }
+
     pub(crate) fn stop(&self, graceful: bool) -> oneshot::Receiver<bool> {
         let (tx, rx) = oneshot::channel();
-        let _ = self.stop_tx.send(Stop { graceful, tx });
+        let _ = self.cmd_tx.send(WorkerCommand::Stop(Stop { graceful, tx }));
+        rx
+    }
+
+    pub(crate) fn pause(&self) {
+        let _ = self.cmd_tx.send(WorkerCommand::Pause);
+    }
+
+    pub(crate) fn resume(&self) {
+        let _ = self.cmd_tx.send(WorkerCommand::Resume);
+    }
+
+    pub(crate) fn add_service(
+        &self,
+        factory: Box<dyn InternalServiceFactory>,
+    ) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.cmd_tx.send(WorkerCommand::AddService { factory, tx });
+        rx
+    }
+
+    pub(crate) fn remove_service(&self, token: usize) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self.cmd_tx.send(WorkerCommand::RemoveService { token, tx });
         rx
     }
 }
@@ -203,18 +386,85 @@ pub(crate) struct ServerWorker {
     // UnboundedReceiver<Conn> should always be the first field.
     // It must be dropped as soon as ServerWorker dropping.
     conn_rx: UnboundedReceiver<Conn>,
-    stop_rx: UnboundedReceiver<Stop>,
+    cmd_rx: UnboundedReceiver<WorkerCommand>,
     counter: WorkerCounter,
-    services: Box<[WorkerService]>,
-    factories: Box<[Box<dyn InternalServiceFactory>]>,
+    services: Vec<WorkerService>,
+    factories: Vec<Box<dyn InternalServiceFactory>>,
+    /// Services of listeners added at runtime, in token order, that are still being created.
+    pending: VecDeque<PendingService>,
     state: WorkerState,
     shutdown_timeout: Duration,
+    restart_policy: RestartPolicy,
+    /// Listeners owned by this worker in `SO_REUSEPORT` mode.
+    listeners: Vec<WorkerListener>,
+    /// Listeners owned by this worker that are accepted from with io-uring.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    uring: Option<UringAccept<WorkerListener<StdTcpListener>>>,
+    /// Deadline before accepting from own listeners again after an error.
+    accept_timeout: Option<Pin<Box<Sleep>>>,
+    paused: bool,
+    /// Running datagram services.
+    udp: Vec<JoinHandle<()>>,
+    /// Notifies services when the worker starts shutting down.
+    shutdown_signal: WorkerShutdown,
+}
+
+/// A listener bound and accepted from by the worker itself.
+struct WorkerListener<L = TcpListener> {
+    token: usize,
+    lst: L,
+    counters: Arc<ListenerCounters>,
+    access: Option<Arc<ListenerAccess>>,
+}
+
+impl<L> WorkerListener<L> {
+    /// Checks access of a connection accepted from this listener.
+    ///
+    /// The counter is incremented for every connection returned, as `Accept` would do.
+    fn conn(
+        &self,
+        counter: &WorkerCounter,
+        stream: mio::net::TcpStream,
+        addr: std::net::SocketAddr,
+    ) -> Option<Conn> {
+        let permit = match self.access.as_ref().map(|acc| acc.check(addr.ip())) {
+            Some(Ok(permit)) => permit,
+            Some(Err(())) => {
+                self.counters.rejected();
+                return None;
+            }
+            None => None,
+        };
+
+        counter.inc();
+        self.counters.accepted();
+
+        Some(Conn {
+            io: MioStream::Tcp(stream),
+            token: self.token,
+            permit,
+            accepted_at: std::time::Instant::now(),
+        })
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+impl std::os::unix::io::AsRawFd for WorkerListener<StdTcpListener> {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.lst.as_raw_fd()
+    }
+}
+
+struct PendingService {
+    fut: LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>>,
+    tx: oneshot::Sender<bool>,
 }
 
 struct WorkerService {
     factory_idx: usize,
     status: WorkerServiceStatus,
     service: BoxedServerService,
+    faults: FaultHistory,
 }
 
 impl WorkerService {
@@ -224,9 +474,10 @@ impl WorkerService {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum WorkerServiceStatus {
     Available,
+    #[default]
     Unavailable,
     Failed,
     Restarting,
@@ -234,9 +485,30 @@ enum WorkerServiceStatus {
     Stopped,
 }
 
-impl Default for WorkerServiceStatus {
-    fn default() -> Self {
-        Self::Unavailable
+/// Async callback run on a worker thread with the index of the worker.
+pub(crate) type WorkerHook = Arc<dyn Fn(usize) -> LocalBoxFuture<'static, ()> + Send + Sync>;
+
+/// Lifecycle hooks of workers passed down from server builder.
+#[derive(Clone, Default)]
+pub(crate) struct WorkerHooks {
+    /// Run before the worker's services are created.
+    pub(crate) start: Option<WorkerHook>,
+
+    /// Run after the worker's services have drained.
+    pub(crate) stop: Option<WorkerHook>,
+}
+
+impl WorkerHooks {
+    async fn run_start(&self, idx: usize) {
+        if let Some(ref hook) = self.start {
+            hook(idx).await;
+        }
+    }
+
+    async fn run_stop(&self, idx: usize) {
+        if let Some(ref hook) = self.stop {
+            hook(idx).await;
+        }
     }
 }
 
@@ -246,6 +518,8 @@ pub(crate) struct ServerWorkerConfig {
     shutdown_timeout: Duration,
     max_blocking_threads: usize,
     max_concurrent_connections: usize,
+    heartbeat_interval: Option<Duration>,
+    restart_policy: RestartPolicy,
 }
 
 impl Default for ServerWorkerConfig {
@@ -259,6 +533,8 @@ impl Default for ServerWorkerConfig {
             shutdown_timeout: Duration::from_secs(30),
             max_blocking_threads,
             max_concurrent_connections: 25600,
+            heartbeat_interval: None,
+            restart_policy: RestartPolicy::default(),
         }
     }
 }
@@ -275,22 +551,56 @@ impl ServerWorkerConfig {
     pub(crate) fn shutdown_timeout(&mut self, dur: Duration) {
         self.shutdown_timeout = dur;
     }
+
+    pub(crate) fn restart_policy(&mut self, policy: RestartPolicy) {
+        self.restart_policy = policy;
+    }
+
+    pub(crate) fn get_restart_policy(&self) -> RestartPolicy {
+        self.restart_policy
+    }
+
+    /// Make workers beat at least this often. Heartbeats are disabled by default.
+    pub(crate) fn heartbeat_interval(&mut self, dur: Duration) {
+        self.heartbeat_interval = Some(match self.heartbeat_interval {
+            Some(current) => current.min(dur),
+            None => dur,
+        });
+    }
 }
 
 impl ServerWorker {
     pub(crate) fn start(
         idx: usize,
         factories: Vec<Box<dyn InternalServiceFactory>>,
+        udp_factories: Vec<Box<dyn InternalUdpServiceFactory>>,
+        listeners: &[(usize, ReusePortListener)],
         waker_queue: WakerQueue,
         config: ServerWorkerConfig,
+        hooks: WorkerHooks,
     ) -> io::Result<(WorkerHandleAccept, WorkerHandleServer)> {
         trace!("starting server worker {}", idx);
 
         let (tx1, conn_rx) = unbounded_channel();
-        let (tx2, stop_rx) = unbounded_channel();
+        let (tx2, cmd_rx) = unbounded_channel();
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let (uring_listeners, listeners): (Vec<_>, Vec<_>) =
+            listeners.iter().partition(|(_, lst)| lst.uring);
+
+        // bind worker's own listeners here so errors are reported to the caller
+        let listeners = bind_listeners(listeners)?;
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let uring = if uring_listeners.is_empty() {
+            None
+        } else {
+            Some(UringAccept::new(bind_listeners(uring_listeners)?)?)
+        };
 
         let counter = Counter::new(config.max_concurrent_connections);
-        let pair = handle_pair(idx, tx1, tx2, counter.clone());
+        let heartbeat = Heartbeat::new();
+        let pair = handle_pair(idx, tx1, tx2, counter.clone(), heartbeat.clone());
 
         // get actix system context if it is set
         let actix_system = System::try_current();
@@ -328,12 +638,15 @@ impl ServerWorker {
                     .name(format!("actix-server worker {}", idx))
                     .spawn(move || {
                         let (worker_stopped_tx, worker_stopped_rx) = oneshot::channel();
+                        let shutdown_signal = WorkerShutdown::install();
 
                         // local set for running service init futures and worker services
                         let ls = tokio::task::LocalSet::new();
 
                         // init services using existing Tokio runtime (so probably on main thread)
                         let services = rt_handle.block_on(ls.run_until(async {
+                            hooks.run_start(idx).await;
+
                             let mut services = Vec::new();
 
                             for (idx, factory) in factories.iter().enumerate() {
@@ -350,10 +663,12 @@ impl ServerWorker {
                                 }
                             }
 
-                            Ok(services)
+                            let udp_services = create_udp_services(&udp_factories).await?;
+
+                            Ok((services, udp_services))
                         }));
 
-                        let services = match services {
+                        let (services, udp_services) = match services {
                             Ok(services) => {
                                 factory_tx.send(Ok(())).unwrap();
                                 services
@@ -367,19 +682,37 @@ impl ServerWorker {
                         let worker_services = wrap_worker_services(services);
 
                         let worker_fut = async move {
+                            spawn_heartbeat(heartbeat, config.heartbeat_interval);
+                            let udp = spawn_udp_services(udp_services);
+
                             // spawn to make sure ServerWorker runs as non boxed future.
                             spawn(async move {
-                                ServerWorker {
+                                let stopped = ServerWorker {
                                     conn_rx,
-                                    stop_rx,
-                                    services: worker_services.into_boxed_slice(),
+                                    cmd_rx,
+                                    services: worker_services,
                                     counter: WorkerCounter::new(idx, waker_queue, counter),
-                                    factories: factories.into_boxed_slice(),
+                                    factories,
+                                    pending: VecDeque::new(),
                                     state: WorkerState::default(),
                                     shutdown_timeout: config.shutdown_timeout,
+                                    restart_policy: config.restart_policy,
+                                    listeners: register_listeners(listeners),
+                                    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+                                    uring,
+                                    accept_timeout: None,
+                                    paused: false,
+                                    udp,
+                                    shutdown_signal,
                                 }
                                 .await;
 
+                                hooks.run_stop(idx).await;
+
+                                if let Some(stopped) = stopped {
+                                    stopped.notify();
+                                }
+
                                 // wake up outermost task waiting for shutdown
                                 worker_stopped_tx.send(()).unwrap();
                             });
@@ -433,6 +766,9 @@ impl ServerWorker {
                 arbiter.spawn(async move {
                     // spawn_local to run !Send future tasks.
                     spawn(async move {
+                        let shutdown_signal = WorkerShutdown::install();
+                        hooks.run_start(idx).await;
+
                         let mut services = Vec::new();
 
                         for (idx, factory) in factories.iter().enumerate() {
@@ -453,19 +789,52 @@ impl ServerWorker {
                             }
                         }
 
+                        let udp_services = match create_udp_services(&udp_factories).await {
+                            Ok(udp_services) => udp_services,
+                            Err(err) => {
+                                Arbiter::current().stop();
+                                factory_tx.send(Err(err)).unwrap();
+                                return;
+                            }
+                        };
+
                         factory_tx.send(Ok(())).unwrap();
 
                         let worker_services = wrap_worker_services(services);
 
+                        spawn_heartbeat(heartbeat, config.heartbeat_interval);
+                        let udp = spawn_udp_services(udp_services);
+
                         // spawn to make sure ServerWorker runs as non boxed future.
-                        spawn(ServerWorker {
-                            conn_rx,
-                            stop_rx,
-                            services: worker_services.into_boxed_slice(),
-                            counter: WorkerCounter::new(idx, waker_queue, counter),
-                            factories: factories.into_boxed_slice(),
-                            state: Default::default(),
-                            shutdown_timeout: config.shutdown_timeout,
+                        spawn(async move {
+                            // stop arbiter once worker is done, even if it panicked
+                            let _arbiter = StopArbiter;
+
+                            let stopped = ServerWorker {
+                                conn_rx,
+                                cmd_rx,
+                                services: worker_services,
+                                counter: WorkerCounter::new(idx, waker_queue, counter),
+                                factories,
+                                pending: VecDeque::new(),
+                                state: Default::default(),
+                                shutdown_timeout: config.shutdown_timeout,
+                                restart_policy: config.restart_policy,
+                                listeners: register_listeners(listeners),
+                                #[cfg(all(target_os = "linux", feature = "io-uring"))]
+                                uring,
+                                accept_timeout: None,
+                                paused: false,
+                                udp,
+                                shutdown_signal,
+                            }
+                            .await;
+
+                            hooks.run_stop(idx).await;
+
+                            if let Some(stopped) = stopped {
+                                stopped.notify();
+                            }
                         });
                     });
                 });
@@ -473,23 +842,145 @@ impl ServerWorker {
         };
 
         // wait for service factories initialization
-        factory_rx.recv().unwrap()?;
+        factory_rx.recv().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "worker exited while starting its services",
+            )
+        })??;
 
         Ok(pair)
     }
 
-    fn restart_service(&mut self, idx: usize, factory_id: usize) {
-        let factory = &self.factories[factory_id];
-        trace!("service {:?} failed, restarting", factory.name(idx));
+    /// Restarts a failed service after the backoff of the restart policy.
+    ///
+    /// Returns false if the service exceeded its restart limit and the worker should exit.
+    fn restart_service(&mut self, idx: usize, factory_id: usize) -> bool {
+        let name = self.factories[factory_id].name(idx);
+        let fault = self
+            .restart_policy
+            .fault(&mut self.services[idx].faults, Instant::now());
+
+        if fault.limit_reached
+            && self.restart_policy.limit_action() != RestartLimitAction::KeepTrying
+        {
+            error!(
+                "service {:?} exceeded its restart limit; stopping worker",
+                name
+            );
+            return false;
+        }
+
+        trace!("service {:?} failed, restarting in {:?}", name, fault.delay);
         self.services[idx].status = WorkerServiceStatus::Restarting;
         self.state = WorkerState::Restarting(Restart {
             factory_id,
             token: idx,
-            fut: factory.create(),
+            delay: Box::pin(sleep(fault.delay)),
+            fut: None,
         });
+
+        true
+    }
+
+    fn call_service(&mut self, msg: Conn) {
+        let guard = self.counter.guard().with_permit(msg.permit);
+        let _ = self.services[msg.token]
+            .service
+            .call((guard, msg.io, msg.accepted_at))
+            .into_inner();
+    }
+
+    /// Adds services of listeners added at runtime once they are created, in token order.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) {
+        while let Some(pending) = self.pending.front_mut() {
+            let res = match pending.fut.as_mut().poll(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return,
+            };
+
+            let pending = self.pending.pop_front().unwrap();
+            let token = self.services.len();
+
+            let service = match res {
+                Ok((token_new, service)) => {
+                    assert_eq!(token, token_new);
+                    trace!(
+                        "service {:?} has been added",
+                        self.factories[token].name(token)
+                    );
+                    let _ = pending.tx.send(true);
+                    service
+                }
+                Err(_) => {
+                    error!(
+                        "can not add {:?} service",
+                        self.factories[token].name(token)
+                    );
+                    let _ = pending.tx.send(false);
+
+                    // keep the token taken so services added later line up
+                    self.close_factory(token);
+                    Box::new(ClosedService) as BoxedServerService
+                }
+            };
+
+            self.services.push(WorkerService {
+                factory_idx: token,
+                status: WorkerServiceStatus::Unavailable,
+                service,
+                faults: FaultHistory::default(),
+            });
+        }
+    }
+
+    /// Replaces the service of a listener removed at runtime with a closed one.
+    ///
+    /// Connections already handed to the old service are left to finish.
+    fn remove_service(&mut self, token: usize) {
+        trace!("removing service {:?}", self.factories[token].name(token));
+
+        self.close_factory(token);
+        self.listeners.retain(|lst| lst.token != token);
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = self.uring.as_mut() {
+            uring.remove(|lst| lst.token == token);
+        }
+
+        if let Some(srv) = self.services.get_mut(token) {
+            srv.created(Box::new(ClosedService));
+        }
+    }
+
+    fn close_factory(&mut self, token: usize) {
+        let name = self.factories[token].name(token).to_owned();
+        self.factories[token] = ClosedServiceFactory::create(name, token);
     }
 
     fn shutdown(&mut self, force: bool) {
+        // stop accepting on own listeners; connections still queued on them are taken over first,
+        // unless they are about to be dropped anyway
+        if force {
+            self.listeners.clear();
+
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            {
+                self.uring = None;
+            }
+        } else {
+            let conns = self.close_listeners();
+            self.call_queued(conns);
+        }
+
+        // let services wind down their connections
+        self.shutdown_signal.notify();
+
+        // datagram services otherwise run until the worker stops
+        if force {
+            self.udp.drain(..).for_each(|handle| handle.abort());
+        }
+
         self.services
             .iter_mut()
             .filter(|srv| srv.status == WorkerServiceStatus::Available)
@@ -502,6 +993,206 @@ impl ServerWorker {
             });
     }
 
+    /// Serves connections taken from the worker's own listeners, dropping those whose service is
+    /// not available.
+    fn call_queued(&mut self, conns: Vec<Conn>) {
+        for conn in conns {
+            if self.services[conn.token].status == WorkerServiceStatus::Available {
+                self.call_service(conn);
+            } else {
+                // the counter was incremented when the connection was taken
+                let guard = self.counter.guard();
+                drop((conn, guard));
+            }
+        }
+    }
+
+    /// Closes the worker's own listeners, returning the connections that were still queued on them.
+    ///
+    /// Closing a listener resets the connections queued on it, since the kernel does not move them
+    /// to other listeners of the `SO_REUSEPORT` group. Accepting them right before closing leaves
+    /// only a small window for new ones to be reset.
+    fn close_listeners(&mut self) -> Vec<Conn> {
+        let mut conns = Vec::new();
+
+        let listeners = mem::take(&mut self.listeners)
+            .into_iter()
+            .filter_map(|lst| match lst.lst.into_std() {
+                Ok(std_lst) => Some(WorkerListener {
+                    token: lst.token,
+                    lst: std_lst,
+                    counters: lst.counters,
+                    access: lst.access,
+                }),
+                Err(err) => {
+                    error!("can not take queued connections: {err}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // connections accepted by io-uring until it stopped come before those still queued
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let listeners = {
+            let mut listeners = listeners;
+
+            for (lst, streams) in self
+                .uring
+                .take()
+                .map(|uring| uring.close())
+                .unwrap_or_default()
+            {
+                for stream in streams {
+                    if let Some(conn) = uring_conn(&lst, &self.counter, stream) {
+                        conns.push(conn);
+                    }
+                }
+
+                listeners.push(lst);
+            }
+
+            listeners
+        };
+
+        for lst in listeners {
+            loop {
+                match lst.lst.accept() {
+                    Ok((stream, addr)) => {
+                        // accepted sockets do not inherit non-blocking mode
+                        if let Err(err) = stream.set_nonblocking(true) {
+                            error!("can not take queued connection: {err}");
+                            continue;
+                        }
+
+                        let stream = mio::net::TcpStream::from_std(stream);
+
+                        if let Some(conn) = lst.conn(&self.counter, stream, addr) {
+                            conns.push(conn);
+                        }
+                    }
+                    Err(ref err) if connection_error(err) => continue,
+                    // no more queued connections, or the listener is broken
+                    Err(_) => break,
+                }
+            }
+        }
+
+        conns
+    }
+
+    /// Accepts a connection from the worker's own listeners.
+    ///
+    /// The counter is incremented for every accepted connection, as `Accept` would do.
+    fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<Conn> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if self.uring.is_some() {
+            if let Poll::Ready(conn) = self.poll_uring_accept(cx) {
+                return Poll::Ready(conn);
+            }
+        }
+
+        if self.paused || self.listeners.is_empty() || self.counter.poll_full(cx) {
+            return Poll::Pending;
+        }
+
+        if let Some(timeout) = self.accept_timeout.as_mut() {
+            ready!(timeout.as_mut().poll(cx));
+            self.accept_timeout = None;
+        }
+
+        for idx in 0..self.listeners.len() {
+            let lst = &self.listeners[idx];
+
+            loop {
+                match lst.lst.poll_accept(cx) {
+                    Poll::Ready(Ok((stream, addr))) => {
+                        let stream = match stream.into_std() {
+                            Ok(stream) => mio::net::TcpStream::from_std(stream),
+                            Err(err) => {
+                                error!("can not take accepted connection: {err}");
+                                continue;
+                            }
+                        };
+
+                        if let Some(conn) = lst.conn(&self.counter, stream, addr) {
+                            return Poll::Ready(conn);
+                        }
+                    }
+                    Poll::Ready(Err(ref err)) if connection_error(err) => continue,
+                    Poll::Ready(Err(err)) => {
+                        error!("error accepting connection: {err}");
+
+                        // sleep after error; register interest by polling the timer once
+                        let mut timeout = Box::pin(sleep(Duration::from_millis(500)));
+                        let _ = timeout.as_mut().poll(cx);
+                        self.accept_timeout = Some(timeout);
+
+                        return Poll::Pending;
+                    }
+                    Poll::Pending => break,
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+
+    /// Stops accepting with io-uring while services are not available.
+    fn stop_uring_accept(&mut self) {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = self.uring.as_mut() {
+            if let Err(err) = uring.stop() {
+                error!("can not stop accepting with io-uring: {err}");
+            }
+        }
+    }
+
+    /// Takes a connection accepted by io-uring.
+    ///
+    /// Accept requests are only armed while the worker accepts connections, but connections
+    /// accepted before a request is cancelled are still returned.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    fn poll_uring_accept(&mut self, cx: &mut Context<'_>) -> Poll<Conn> {
+        let accepting = !self.paused
+            && !self.counter.poll_full(cx)
+            && match self.accept_timeout.as_mut() {
+                Some(timeout) => timeout.as_mut().poll(cx).is_ready(),
+                None => true,
+            };
+
+        if accepting {
+            self.accept_timeout = None;
+        }
+
+        let uring = match self.uring.as_mut() {
+            Some(uring) => uring,
+            None => return Poll::Pending,
+        };
+
+        loop {
+            match uring.poll_accept(cx, accepting) {
+                Poll::Ready(Ok((lst, stream))) => {
+                    if let Some(conn) = uring_conn(lst, &self.counter, stream) {
+                        return Poll::Ready(conn);
+                    }
+                }
+                Poll::Ready(Err(ref err)) if connection_error(err) => continue,
+                Poll::Ready(Err(err)) => {
+                    error!("error accepting connection: {err}");
+
+                    // sleep after error; register interest by polling the timer once
+                    let mut timeout = Box::pin(sleep(Duration::from_millis(500)));
+                    let _ = timeout.as_mut().poll(cx);
+                    self.accept_timeout = Some(timeout);
+
+                    // cancel the remaining requests until the timer fires
+                    return self.poll_uring_accept(cx);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
     fn check_readiness(&mut self, cx: &mut Context<'_>) -> Result<bool, (usize, usize)> {
         let mut ready = true;
         for (idx, srv) in self.services.iter_mut().enumerate() {
@@ -545,8 +1236,10 @@ impl ServerWorker {
     }
 }
 
+#[derive(Default)]
 enum WorkerState {
     Available,
+    #[default]
     Unavailable,
     Restarting(Restart),
     Shutdown(Shutdown),
@@ -555,7 +1248,9 @@ enum WorkerState {
 struct Restart {
     factory_id: usize,
     token: usize,
-    fut: LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>>,
+    /// Backoff before the service is created again.
+    delay: Pin<Box<Sleep>>,
+    fut: Option<LocalBoxFuture<'static, Result<(usize, BoxedServerService), ()>>>,
 }
 
 /// State necessary for server shutdown.
@@ -570,49 +1265,104 @@ struct Shutdown {
     tx: oneshot::Sender<bool>,
 }
 
-impl Default for WorkerState {
-    fn default() -> Self {
-        Self::Unavailable
+/// Outcome of a worker's shutdown, reported to the server once the worker is done.
+pub(crate) struct Stopped {
+    tx: oneshot::Sender<bool>,
+    graceful: bool,
+}
+
+impl Stopped {
+    fn notify(self) {
+        let _ = self.tx.send(self.graceful);
     }
 }
 
-impl Drop for ServerWorker {
+/// Stops the arbiter of the current thread when dropped.
+struct StopArbiter;
+
+impl Drop for StopArbiter {
     fn drop(&mut self) {
         Arbiter::try_current().as_ref().map(ArbiterHandle::stop);
     }
 }
 
 impl Future for ServerWorker {
-    type Output = ();
+    /// Outcome of the shutdown, if it was requested by the server.
+    type Output = Option<Stopped>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.as_mut().get_mut();
 
-        // `StopWorker` message handler
-        if let Poll::Ready(Some(Stop { graceful, tx })) = this.stop_rx.poll_recv(cx) {
-            let num = this.counter.total();
-            if num == 0 {
-                info!("shutting down idle worker");
-                let _ = tx.send(true);
-                return Poll::Ready(());
-            } else if graceful {
-                info!("graceful worker shutdown; finishing {} connections", num);
-                this.shutdown(false);
-
-                this.state = WorkerState::Shutdown(Shutdown {
-                    timer: Box::pin(sleep(Duration::from_secs(1))),
-                    start_from: Instant::now(),
-                    tx,
-                });
-            } else {
-                info!("force shutdown worker, closing {} connections", num);
-                this.shutdown(true);
+        // `WorkerCommand` message handler
+        let mut server_gone = false;
+        while let Poll::Ready(cmd) = this.cmd_rx.poll_recv(cx) {
+            let cmd = match cmd {
+                Some(cmd) => cmd,
+                None => {
+                    server_gone = true;
+                    break;
+                }
+            };
+
+            match cmd {
+                WorkerCommand::Stop(Stop { graceful, tx }) => {
+                    // connections still queued on own listeners keep an idle worker running
+                    let queued = if graceful {
+                        this.close_listeners()
+                    } else {
+                        Vec::new()
+                    };
+
+                    let num = this.counter.total();
+                    if num == 0 {
+                        info!("shutting down idle worker");
+                        return Poll::Ready(Some(Stopped { tx, graceful: true }));
+                    } else if graceful {
+                        info!("graceful worker shutdown; finishing {} connections", num);
+
+                        // connections already sent to this worker are served, not dropped
+                        if let WorkerState::Available = this.state {
+                            while let Poll::Ready(Some(msg)) = this.conn_rx.poll_recv(cx) {
+                                this.call_service(msg);
+                            }
+                        }
 
-                let _ = tx.send(false);
-                return Poll::Ready(());
+                        this.call_queued(queued);
+                        this.shutdown(false);
+
+                        this.state = WorkerState::Shutdown(Shutdown {
+                            timer: Box::pin(sleep(Duration::from_secs(1))),
+                            start_from: Instant::now(),
+                            tx,
+                        });
+                    } else {
+                        info!("force shutdown worker, closing {} connections", num);
+                        this.shutdown(true);
+
+                        return Poll::Ready(Some(Stopped {
+                            tx,
+                            graceful: false,
+                        }));
+                    }
+                }
+                WorkerCommand::Pause => this.paused = true,
+                WorkerCommand::Resume => this.paused = false,
+                WorkerCommand::AddService { factory, tx } => {
+                    this.pending.push_back(PendingService {
+                        fut: factory.create(),
+                        tx,
+                    });
+                    this.factories.push(factory);
+                }
+                WorkerCommand::RemoveService { token, tx } => {
+                    this.remove_service(token);
+                    let _ = tx.send(());
+                }
             }
         }
 
+        this.poll_pending(cx);
+
         match this.state {
             WorkerState::Unavailable => match this.check_readiness(cx) {
                 Ok(true) => {
@@ -621,7 +1371,9 @@ impl Future for ServerWorker {
                 }
                 Ok(false) => Poll::Pending,
                 Err((token, idx)) => {
-                    this.restart_service(token, idx);
+                    if !this.restart_service(token, idx) {
+                        return Poll::Ready(None);
+                    }
                     self.poll(cx)
                 }
             },
@@ -630,13 +1382,26 @@ impl Future for ServerWorker {
                 let factory_id = restart.factory_id;
                 let token = restart.token;
 
-                let (token_new, service) =
-                    ready!(restart.fut.as_mut().poll(cx)).unwrap_or_else(|_| {
-                        panic!(
-                            "Can not restart {:?} service",
+                ready!(restart.delay.as_mut().poll(cx));
+
+                let factory = &this.factories[factory_id];
+                let fut = restart.fut.get_or_insert_with(|| factory.create());
+
+                let (token_new, service) = match ready!(fut.as_mut().poll(cx)) {
+                    Ok(res) => res,
+                    Err(_) => {
+                        error!(
+                            "can not restart {:?} service",
                             this.factories[factory_id].name(token)
-                        )
-                    });
+                        );
+
+                        // a failed re-creation is another fault of the service
+                        if !this.restart_service(token, factory_id) {
+                            return Poll::Ready(None);
+                        }
+                        return self.poll(cx);
+                    }
+                };
 
                 assert_eq!(token, token_new);
 
@@ -665,23 +1430,27 @@ impl Future for ServerWorker {
 
                 if this.counter.total() == 0 {
                     // graceful shutdown
-                    if let WorkerState::Shutdown(shutdown) = mem::take(&mut this.state) {
-                        let _ = shutdown.tx.send(true);
+                    match mem::take(&mut this.state) {
+                        WorkerState::Shutdown(shutdown) => Poll::Ready(Some(Stopped {
+                            tx: shutdown.tx,
+                            graceful: true,
+                        })),
+                        _ => unreachable!(),
                     }
-
-                    Poll::Ready(())
                 } else if shutdown.start_from.elapsed() >= this.shutdown_timeout {
                     // timeout forceful shutdown
-                    if let WorkerState::Shutdown(shutdown) = mem::take(&mut this.state) {
-                        let _ = shutdown.tx.send(false);
+                    match mem::take(&mut this.state) {
+                        WorkerState::Shutdown(shutdown) => Poll::Ready(Some(Stopped {
+                            tx: shutdown.tx,
+                            graceful: false,
+                        })),
+                        _ => unreachable!(),
                     }
-
-                    Poll::Ready(())
                 } else {
                     // reset timer and wait for 1 second
                     let time = Instant::now() + Duration::from_secs(1);
                     shutdown.timer.as_mut().reset(time);
-                    shutdown.timer.as_mut().poll(cx)
+                    shutdown.timer.as_mut().poll(cx).map(|()| None)
                 }
             }
 
@@ -691,31 +1460,128 @@ impl Future for ServerWorker {
                     Ok(true) => {}
                     Ok(false) => {
                         trace!("worker is unavailable");
+                        this.stop_uring_accept();
                         this.state = WorkerState::Unavailable;
                         return self.poll(cx);
                     }
                     Err((token, idx)) => {
-                        this.restart_service(token, idx);
+                        this.stop_uring_accept();
+                        if !this.restart_service(token, idx) {
+                            return Poll::Ready(None);
+                        }
                         return self.poll(cx);
                     }
                 }
 
                 // handle incoming io stream
-                match ready!(this.conn_rx.poll_recv(cx)) {
-                    Some(msg) => {
-                        let guard = this.counter.guard();
-                        let _ = this.services[msg.token]
-                            .service
-                            .call((guard, msg.io))
-                            .into_inner();
-                    }
-                    None => return Poll::Ready(()),
+                let msg = match this.conn_rx.poll_recv(cx) {
+                    Poll::Ready(Some(msg)) => msg,
+                    Poll::Ready(None) if server_gone => return Poll::Ready(None),
+                    // The accept thread stops before workers are told to; exiting now would drop
+                    // the connections a graceful stop is about to finish.
+                    Poll::Ready(None) | Poll::Pending => ready!(this.poll_accept(cx)),
                 };
+
+                this.call_service(msg);
             },
         }
     }
 }
 
+/// Bind worker's own listeners.
+fn bind_listeners<'a>(
+    listeners: impl IntoIterator<Item = &'a (usize, ReusePortListener)>,
+) -> io::Result<Vec<WorkerListener<StdTcpListener>>> {
+    listeners
+        .into_iter()
+        .map(|(token, lst)| {
+            Ok(WorkerListener {
+                token: *token,
+                lst: lst.bind()?,
+                counters: lst.counters.clone(),
+                access: lst.access.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Checks access of a connection accepted by io-uring, which does not report peer addresses.
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+fn uring_conn(
+    lst: &WorkerListener<StdTcpListener>,
+    counter: &WorkerCounter,
+    stream: std::net::TcpStream,
+) -> Option<Conn> {
+    let addr = match stream.peer_addr() {
+        Ok(addr) => addr,
+        // connection was reset in the meantime
+        Err(_) => return None,
+    };
+
+    lst.conn(counter, mio::net::TcpStream::from_std(stream), addr)
+}
+
+/// Register worker's own listeners with the current worker's event loop.
+fn register_listeners(listeners: Vec<WorkerListener<StdTcpListener>>) -> Vec<WorkerListener> {
+    listeners
+        .into_iter()
+        .filter_map(|lst| match TcpListener::from_std(lst.lst) {
+            Ok(tokio_lst) => Some(WorkerListener {
+                token: lst.token,
+                lst: tokio_lst,
+                counters: lst.counters,
+                access: lst.access,
+            }),
+            Err(err) => {
+                error!("can not register worker listener: {err}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Start datagram services of a worker, in the order of their factories.
+async fn create_udp_services(
+    factories: &[Box<dyn InternalUdpServiceFactory>],
+) -> io::Result<Vec<BoxedUdpService>> {
+    let mut services = Vec::with_capacity(factories.len());
+
+    for factory in factories {
+        match factory.create().await {
+            Ok(svc) => services.push(svc),
+            Err(_) => {
+                error!(
+                    "can not start worker: datagram service {:?}",
+                    factory.name()
+                );
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("can not start datagram service {:?}", factory.name()),
+                ));
+            }
+        }
+    }
+
+    Ok(services)
+}
+
+/// Run started datagram services on the current worker's event loop.
+fn spawn_udp_services(services: Vec<BoxedUdpService>) -> Vec<JoinHandle<()>> {
+    services.into_iter().map(|run| spawn(run())).collect()
+}
+
+/// Beat `heartbeat` every `interval` from a task on the current worker's event loop.
+fn spawn_heartbeat(heartbeat: Heartbeat, interval: Option<Duration>) {
+    if let Some(interval) = interval {
+        spawn(async move {
+            loop {
+                heartbeat.beat();
+                sleep(interval).await;
+            }
+        });
+    }
+}
+
 fn wrap_worker_services(services: Vec<(usize, usize, BoxedServerService)>) -> Vec<WorkerService> {
     services
         .into_iter()
@@ -725,6 +1591,7 @@ fn wrap_worker_services(services: Vec<(usize, usize, BoxedServerService)>) -> Ve
                 factory_idx: idx,
                 service,
                 status: WorkerServiceStatus::Unavailable,
+                faults: FaultHistory::default(),
             });
             services
         })
@@ -0,0 +1,175 @@
+#![cfg(unix)]
+
+use std::{
+    io::{BufRead as _, BufReader, Write as _},
+    net,
+    os::unix::{fs::PermissionsExt as _, net::UnixStream},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::Server;
+use actix_service::fn_service;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn admin_commands() {
+    let addr = unused_addr();
+    let path = std::env::temp_dir().join(format!("actix-server-admin-{}.sock", std::process::id()));
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn({
+        let path = path.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .admin_socket(path)?
+                    .bind("test", addr, || {
+                        fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                    })?
+                    .run();
+
+                tx.send(()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let conn = UnixStream::connect(&path).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut lines = BufReader::new(&conn).lines();
+
+    let mut command = |cmd: &str| {
+        (&conn).write_all(format!("{cmd}\n").as_bytes()).unwrap();
+        lines.next().unwrap().unwrap()
+    };
+
+    assert_eq!(command("pause"), "ok");
+    assert_eq!(
+        command("stats"),
+        r#"{"paused":true,"workers":[{"idx":0,"connections":0,"restarts":0,"stalled":false}],"listeners":[{"token":0,"name":"test","accepted":0,"rejected":0}]}"#
+    );
+    assert_eq!(command("resume"), "ok");
+    assert!(command("stats").starts_with(r#"{"paused":false,"#));
+    assert!(command("reload").starts_with("error unknown command"));
+    assert_eq!(command("stop"), "ok");
+
+    h.join().unwrap().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn clients_served_concurrently() {
+    let addr = unused_addr();
+    let path = std::env::temp_dir().join(format!(
+        "actix-server-admin-concurrent-{}.sock",
+        std::process::id()
+    ));
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn({
+        let path = path.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .admin_socket(path)?
+                    .bind("test", addr, || {
+                        fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    // an idle client does not hold up the next one
+    let _idle = UnixStream::connect(&path).unwrap();
+
+    let conn = UnixStream::connect(&path).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    (&conn).write_all(b"stats\n").unwrap();
+    let mut line = String::new();
+    BufReader::new(&conn).read_line(&mut line).unwrap();
+    assert!(line.starts_with(r#"{"paused":false,"#));
+
+    drop(srv.stop(true));
+    h.join().unwrap().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn keep_replaced_socket_file() {
+    use std::os::unix::net::UnixListener;
+
+    let addr = unused_addr();
+    let path = std::env::temp_dir().join(format!(
+        "actix-server-admin-replaced-{}.sock",
+        std::process::id()
+    ));
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn({
+        let path = path.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .admin_socket(path)?
+                    .bind("test", addr, || {
+                        fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    // another server takes over the path, like the new process of a handoff
+    std::fs::remove_file(&path).unwrap();
+    let successor = UnixListener::bind(&path).unwrap();
+    successor.set_nonblocking(true).unwrap();
+
+    drop(srv.stop(true));
+    h.join().unwrap().unwrap();
+
+    // the stopped server neither removed nor connected to the new socket
+    assert!(path.exists());
+    assert_eq!(
+        successor.accept().unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,139 @@
+use std::{
+    io::Read as _,
+    net,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{Connection, ConnectionAddr, Server};
+use actix_service::fn_service;
+use tokio::io::AsyncWriteExt as _;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn tcp_connection_info() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let (info_tx, info_rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind_connection("info", addr, move || {
+                    let info_tx = info_tx.clone();
+
+                    fn_service(move |mut conn: Connection<TcpStream>| {
+                        let info_tx = info_tx.clone();
+
+                        async move {
+                            info_tx.send(conn.info().clone()).unwrap();
+                            conn.write_all(b"ok").await
+                        }
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut res = [0; 2];
+    conn.read_exact(&mut res).unwrap();
+    assert_eq!(&res, b"ok");
+
+    let info = info_rx.recv().unwrap();
+    assert_eq!(info.listener_name(), "info");
+    assert_eq!(info.listener_token(), 0);
+    assert_eq!(info.worker(), 0);
+    assert!(info.accepted_at() <= Instant::now());
+    assert_eq!(info.local_addr(), &ConnectionAddr::Tcp(addr));
+    assert_eq!(
+        info.peer_addr(),
+        &ConnectionAddr::Tcp(conn.local_addr().unwrap())
+    );
+    #[cfg(unix)]
+    assert_eq!(info.peer_cred(), None);
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_connection_info() {
+    use std::os::unix::net::UnixStream as StdUnixStream;
+
+    use actix_rt::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("actix-server-conn-{}.sock", std::process::id()));
+    let (tx, rx) = mpsc::channel();
+    let (info_tx, info_rx) = mpsc::channel();
+
+    let h = thread::spawn({
+        let path = path.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .bind_uds_connection("uds", path, move || {
+                        let info_tx = info_tx.clone();
+
+                        fn_service(move |mut conn: Connection<UnixStream>| {
+                            let info_tx = info_tx.clone();
+
+                            async move {
+                                info_tx.send(conn.info().clone()).unwrap();
+                                conn.write_all(b"ok").await
+                            }
+                        })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mut conn = StdUnixStream::connect(&path).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut res = [0; 2];
+    conn.read_exact(&mut res).unwrap();
+    assert_eq!(&res, b"ok");
+
+    let info = info_rx.recv().unwrap();
+    assert_eq!(info.listener_name(), "uds");
+    assert_eq!(info.local_addr(), &ConnectionAddr::Uds(Some(path.clone())));
+    assert_eq!(info.peer_addr(), &ConnectionAddr::Uds(None));
+
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        info.peer_cred().map(|cred| cred.pid),
+        Some(std::process::id() as i32)
+    );
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,113 @@
+use std::{
+    io::{self, Read as _, Write as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_server::{ConnectionAddr, Listener, ListenerStream, Server};
+use actix_service::fn_service;
+use mio::{event::Source, net::TcpStream, Interest, Registry, Token};
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+struct TestListener(mio::net::TcpListener);
+
+struct TestStream(TcpStream);
+
+impl ListenerStream for TestStream {
+    fn peer_addr(&self) -> ConnectionAddr {
+        self.0
+            .peer_addr()
+            .map_or(ConnectionAddr::Unknown, ConnectionAddr::Tcp)
+    }
+}
+
+impl Listener for TestListener {
+    type Stream = TestStream;
+
+    fn accept(&mut self) -> io::Result<TestStream> {
+        let (stream, _) = self.0.accept()?;
+        Ok(TestStream(stream))
+    }
+
+    fn local_addr(&self) -> ConnectionAddr {
+        self.0
+            .local_addr()
+            .map_or(ConnectionAddr::Unknown, ConnectionAddr::Tcp)
+    }
+}
+
+impl Source for TestListener {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.0.register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.0.reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        self.0.deregister(registry)
+    }
+}
+
+#[test]
+fn custom_listener() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let lst = TestListener(mio::net::TcpListener::bind(addr)?);
+
+            let srv = Server::build()
+                .workers(2)
+                .disable_signals()
+                .listen_custom("custom", lst, || {
+                    fn_service(|TestStream(mut stream): TestStream| async move {
+                        // small enough to fit in the send buffer of a non-blocking socket
+                        stream.write_all(b"custom")
+                    })
+                })
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    for _ in 0..3 {
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut res = Vec::new();
+        conn.read_to_end(&mut res).unwrap();
+        assert_eq!(res, b"custom");
+    }
+
+    let stats = actix_rt::System::new().block_on(srv.stats()).unwrap();
+    assert_eq!(stats.listeners[0].name, "custom");
+    assert_eq!(stats.listeners[0].accepted, 3);
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
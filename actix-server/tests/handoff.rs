@@ -0,0 +1,146 @@
+#![cfg(unix)]
+
+use std::{
+    fs, io,
+    path::Path,
+    process::{self, Command},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_server::Server;
+use actix_service::fn_service;
+
+#[test]
+fn handoff_to_new_process() {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .handoff_timeout(5)
+                .handoff_command(|| {
+                    let mut cmd = Command::new("bash");
+                    cmd.arg("-c").arg(
+                        r#"case "$ACTIX_SERVER_LISTENERS" in handoff=*) printf 1 >&"$ACTIX_SERVER_READY_FD";; esac"#,
+                    );
+                    cmd
+                })
+                .bind("handoff", "127.0.0.1:0", || {
+                    fn_service(|_| async { Ok::<_, ()>(()) })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    actix_rt::System::new().block_on(async {
+        srv.handoff().await.unwrap();
+    });
+
+    // old server shuts down after the new process reported readiness
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn failed_handoff_keeps_server_running() {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .handoff_command(|| Command::new("true"))
+                .bind("handoff", "127.0.0.1:0", || {
+                    fn_service(|_| async { Ok::<_, ()>(()) })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    actix_rt::System::new().block_on(async {
+        let err = srv.handoff().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(!h.is_finished());
+
+        srv.stop(true).await;
+    });
+
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn pending_handoff_does_not_block_server() {
+    let pid_file = std::env::temp_dir().join(format!("actix-server-handoff-{}.pid", process::id()));
+    let _ = fs::remove_file(&pid_file);
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn({
+        let pid_file = pid_file.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .handoff_timeout(1)
+                    .handoff_command(move || {
+                        // never reports readiness
+                        let mut cmd = Command::new("bash");
+                        cmd.arg("-c")
+                            .arg(r#"echo $$ > "$0"; exec sleep 30"#)
+                            .arg(&pid_file);
+                        cmd
+                    })
+                    .bind("handoff", "127.0.0.1:0", || {
+                        fn_service(|_| async { Ok::<_, ()>(()) })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+
+    actix_rt::System::new().block_on(async {
+        let handoff = srv.handoff();
+
+        // commands are handled while the new process is starting
+        let start = Instant::now();
+        srv.stats().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let err = srv.handoff().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        let err = handoff.await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    });
+
+    // new process was killed and reaped
+    let pid = fs::read_to_string(&pid_file).unwrap();
+    assert!(!Path::new(&format!("/proc/{}", pid.trim())).exists());
+    let _ = fs::remove_file(&pid_file);
+
+    assert!(!h.is_finished());
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,68 @@
+#![cfg(unix)]
+
+// Inherited listeners are read from the environment when the first `ServerBuilder` is created, so
+// this test has its own binary to set the environment before any other test creates one.
+
+use std::{
+    io::{self, Read as _},
+    net,
+    os::unix::{io::IntoRawFd as _, net::UnixStream},
+    sync::mpsc,
+    thread,
+};
+
+use actix_server::Server;
+use actix_service::fn_service;
+
+#[test]
+fn adopt_inherited_listener() {
+    let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+    let (mut ready_rx, ready_tx) = UnixStream::pair().unwrap();
+
+    std::env::set_var(
+        "ACTIX_SERVER_LISTENERS",
+        format!("inherited={}", lst.into_raw_fd()),
+    );
+    std::env::set_var("ACTIX_SERVER_READY_FD", ready_tx.into_raw_fd().to_string());
+
+    let err = Server::build()
+        .listen_inherited("missing", || fn_service(|_| async { Ok::<_, ()>(()) }))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .listen_inherited("inherited", || fn_service(|_| async { Ok::<_, ()>(()) }))?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    // new process reports readiness once workers are started
+    let mut buf = [0; 1];
+    ready_rx.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"1");
+
+    net::TcpStream::connect(addr).unwrap();
+
+    // inherited listeners can only be adopted once
+    let err = Server::build()
+        .listen_inherited("inherited", || fn_service(|_| async { Ok::<_, ()>(()) }))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    drop(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::{self, Read as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{IpFilter, IpLimits, Server, ServerBuilder, ServerHandle};
+use actix_service::fn_service;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+/// Starts a server greeting each connection and holding it open until the client closes it.
+fn start(
+    config: impl FnOnce(ServerBuilder) -> ServerBuilder + Send + 'static,
+) -> (
+    net::SocketAddr,
+    ServerHandle,
+    thread::JoinHandle<io::Result<()>>,
+) {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = config(Server::build())
+                .workers(1)
+                .disable_signals()
+                .bind("limited", addr, || {
+                    fn_service(|mut io: TcpStream| async move {
+                        io.write_all(b"x").await?;
+                        let mut buf = [0; 1];
+                        let _ = io.read(&mut buf).await;
+                        Ok::<_, io::Error>(())
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    (addr, srv, h)
+}
+
+/// Connects to `addr`, returning the connection if it was greeted rather than closed.
+fn connect(addr: net::SocketAddr) -> Option<net::TcpStream> {
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let mut buf = [0; 1];
+    match conn.read(&mut buf) {
+        Ok(1) => Some(conn),
+        _ => None,
+    }
+}
+
+fn rejected(srv: &ServerHandle) -> u64 {
+    let stats = actix_rt::System::new().block_on(srv.stats()).unwrap();
+    stats.listeners[0].rejected
+}
+
+#[test]
+fn max_connections_per_ip() {
+    let (addr, srv, h) =
+        start(|builder| builder.ip_limits("limited", IpLimits::new().max_connections(2)));
+
+    let first = connect(addr).unwrap();
+    let _second = connect(addr).unwrap();
+    assert!(connect(addr).is_none());
+    assert_eq!(rejected(&srv), 1);
+
+    // closed connections no longer count
+    drop(first);
+    thread::sleep(Duration::from_millis(100));
+    assert!(connect(addr).is_some());
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn connection_rate_per_ip() {
+    let (addr, srv, h) = start(|builder| builder.ip_limits("limited", IpLimits::new().rate(5, 2)));
+
+    assert!(connect(addr).is_some());
+    assert!(connect(addr).is_some());
+    assert!(connect(addr).is_none());
+    assert_eq!(rejected(&srv), 1);
+
+    // bucket is refilled with one token every 200ms
+    thread::sleep(Duration::from_millis(250));
+    assert!(connect(addr).is_some());
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn reload_ip_filter() {
+    let deny_local = IpFilter::new().deny("127.0.0.0/8").unwrap();
+    let (addr, srv, h) = start(move |builder| builder.ip_filter("limited", deny_local));
+
+    assert!(connect(addr).is_none());
+    assert_eq!(rejected(&srv), 1);
+
+    actix_rt::System::new().block_on(async {
+        let allow_local = IpFilter::new().allow("127.0.0.1").unwrap();
+        srv.set_ip_filter("limited", allow_local).await.unwrap();
+
+        let err = srv
+            .set_ip_filter("unknown", IpFilter::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    });
+
+    assert!(connect(addr).is_some());
+    assert_eq!(rejected(&srv), 1);
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
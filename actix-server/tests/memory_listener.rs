@@ -0,0 +1,53 @@
+use std::{sync::mpsc, thread};
+
+use actix_server::{MemoryListener, Server};
+use actix_service::fn_service;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _, DuplexStream};
+
+#[test]
+fn serve_multiple_memory_listeners() {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let lst1 = MemoryListener::new()?;
+            let lst2 = MemoryListener::new()?;
+            let connectors = (lst1.connector(), lst2.connector());
+
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .listen_custom("one", lst1, || {
+                    fn_service(
+                        |mut stream: DuplexStream| async move { stream.write_all(b"one").await },
+                    )
+                })
+                .listen_custom("two", lst2, || {
+                    fn_service(
+                        |mut stream: DuplexStream| async move { stream.write_all(b"two").await },
+                    )
+                })
+                .run();
+
+            tx.send((srv.handle(), connectors)).unwrap();
+            srv.await
+        })
+    });
+
+    let (srv, (conn1, conn2)) = rx.recv().unwrap();
+
+    actix_rt::System::new().block_on(async {
+        // every listener wakes the accept thread up, also after the first connection
+        for _ in 0..3 {
+            for (connector, expected) in [(&conn1, b"one"), (&conn2, b"two")] {
+                let mut stream = connector.connect().unwrap();
+                let mut buf = [0; 3];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, expected);
+            }
+        }
+    });
+
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,87 @@
+use std::{
+    io::{self, Read as _, Write as _},
+    net,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{ConnectionOutcome, Server};
+use actix_service::fn_service;
+use tokio::io::AsyncReadExt as _;
+
+#[test]
+fn connection_outcomes() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+
+    let h = thread::spawn({
+        let outcomes = outcomes.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .on_connection_outcome(move |listener, outcome| {
+                        let outcome = match outcome {
+                            ConnectionOutcome::Success => "success".to_owned(),
+                            ConnectionOutcome::Error(err) => format!("error: {err}"),
+                            ConnectionOutcome::Panic(msg) => format!("panic: {msg:?}"),
+                            _ => unreachable!(),
+                        };
+                        outcomes
+                            .lock()
+                            .unwrap()
+                            .push(format!("{listener} {outcome}"));
+                    })
+                    .bind("outcome", addr, || {
+                        fn_service(|mut io: TcpStream| async move {
+                            let mut buf = [0; 1];
+                            io.read_exact(&mut buf).await?;
+
+                            match &buf {
+                                b"s" => Ok(()),
+                                b"e" => Err(io::Error::new(io::ErrorKind::Other, "failed")),
+                                _ => panic!("boom"),
+                            }
+                        })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    for req in [b"s", b"e", b"p", b"s"] {
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(req).unwrap();
+        let _ = conn.read(&mut [0; 1]);
+    }
+
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(
+        *outcomes.lock().unwrap(),
+        [
+            "outcome success",
+            "outcome error: failed",
+            r#"outcome panic: Some("boom")"#,
+            "outcome success",
+        ]
+    );
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,91 @@
+use std::{
+    io::{self, Read as _, Write as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{ProxyProtocol, ProxyStream, Server, ServerHandle};
+use actix_service::fn_service;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+/// Server replying with the client address from the header followed by the 4-byte payload.
+fn start() -> (
+    net::SocketAddr,
+    ServerHandle,
+    thread::JoinHandle<io::Result<()>>,
+) {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("proxied", addr, || {
+                    ProxyProtocol::new(fn_service(
+                        |mut stream: ProxyStream<TcpStream>| async move {
+                            let mut payload = [0; 4];
+                            stream.read_exact(&mut payload).await?;
+
+                            let source = format!("{:?} ", stream.header().source);
+                            stream.write_all(source.as_bytes()).await?;
+                            stream.write_all(&payload).await
+                        },
+                    ))
+                    .timeout(Duration::from_millis(300))
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    (addr, srv, h)
+}
+
+fn exchange(addr: net::SocketAddr, req: &[u8]) -> String {
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    conn.write_all(req).unwrap();
+
+    let mut res = String::new();
+    let _ = conn.read_to_string(&mut res);
+    res
+}
+
+#[test]
+fn proxy_protocol() {
+    let (addr, srv, h) = start();
+
+    // v1 header and payload in one segment
+    let res = exchange(addr, b"PROXY TCP4 10.1.2.3 10.0.0.1 4000 80\r\nping");
+    assert_eq!(res, "Some(10.1.2.3:4000) ping");
+
+    // v2 header
+    let mut req = b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c".to_vec();
+    req.extend_from_slice(&[192, 0, 2, 1, 192, 0, 2, 2, 0x1f, 0x90, 0x00, 0x50]);
+    req.extend_from_slice(b"pong");
+    let res = exchange(addr, &req);
+    assert_eq!(res, "Some(192.0.2.1:8080) pong");
+
+    // connections without a valid header are closed
+    assert_eq!(exchange(addr, b"GET / HTTP/1.1\r\n\r\n"), "");
+    assert_eq!(exchange(addr, b"PROXY TCP4 10.1.2.3\r\nping"), "");
+
+    // incomplete header times out
+    assert_eq!(exchange(addr, b"PROXY TCP4 10.1.2.3"), "");
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,179 @@
+use std::{
+    io::Read as _,
+    net,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{RestartLimitAction, RestartPolicy, Server, WorkerEvent};
+use actix_service::fn_service;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn stop_server_on_restart_limit() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let policy = RestartPolicy::new()
+                .max_restarts(1, Duration::from_secs(60))
+                .on_limit(RestartLimitAction::StopServer);
+
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .restart_policy(policy)
+                .bind("crash", addr, || {
+                    fn_service(|_: TcpStream| {
+                        // panics while the worker calls the service, taking the worker down
+                        panic!("crash on purpose");
+
+                        #[allow(unreachable_code)]
+                        async {
+                            Ok::<_, ()>(())
+                        }
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    let mut events = srv.worker_events();
+    thread::sleep(Duration::from_millis(300));
+
+    // a dead worker is noticed once a connection is sent to it
+    let start = Instant::now();
+    while !h.is_finished() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "server did not stop"
+        );
+
+        if let Ok(mut conn) = net::TcpStream::connect(addr) {
+            conn.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+            let _ = conn.read(&mut [0; 1]);
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    h.join().unwrap().unwrap();
+
+    let events = std::iter::from_fn(|| events.try_recv().ok()).collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        [
+            WorkerEvent::Died { idx: 0 },
+            WorkerEvent::Restarting {
+                idx: 0,
+                delay: Duration::ZERO
+            },
+            WorkerEvent::Restarted { idx: 0 },
+            WorkerEvent::Died { idx: 0 },
+            WorkerEvent::LimitReached {
+                idx: 0,
+                action: RestartLimitAction::StopServer
+            },
+        ]
+    );
+}
+
+#[test]
+fn restart_service_after_failed_recreation() {
+    use std::{
+        cell::Cell,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    use actix_service::{fn_factory, Service};
+    use futures_core::future::LocalBoxFuture;
+    use tokio::io::AsyncWriteExt as _;
+
+    struct FailOnce(Cell<bool>);
+
+    impl Service<TcpStream> for FailOnce {
+        type Response = ();
+        type Error = ();
+        type Future = LocalBoxFuture<'static, Result<(), ()>>;
+
+        fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            // the first service created fails its first readiness check
+            if self.0.replace(false) {
+                Poll::Ready(Err(()))
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        fn call(&self, mut io: TcpStream) -> Self::Future {
+            Box::pin(async move {
+                io.write_all(b"ok").await.map_err(|_| ())?;
+                Ok(())
+            })
+        }
+    }
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let created = Arc::new(AtomicUsize::new(0));
+    let created2 = created.clone();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("flaky", addr, move || {
+                    let created = created2.clone();
+                    fn_factory(move || {
+                        // the first re-creation of the service fails
+                        let n = created.fetch_add(1, Ordering::SeqCst);
+                        async move {
+                            if n == 1 {
+                                Err(())
+                            } else {
+                                Ok(FailOnce(Cell::new(n == 0)))
+                            }
+                        }
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    let mut events = srv.worker_events();
+
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut buf = [0; 2];
+    conn.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ok");
+    assert_eq!(created.load(Ordering::SeqCst), 3);
+
+    // the service was restarted on the worker, without taking the worker down
+    assert!(events.try_recv().is_err());
+
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,421 @@
+#![cfg(unix)]
+
+use std::{
+    collections::HashSet,
+    net,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use actix_rt::{net::TcpStream, time::sleep};
+use actix_server::{Server, ServerBuilder, ServerHandle};
+use actix_service::fn_service;
+
+/// Runs server on a new thread, returning its handle.
+fn start(
+    builder: impl FnOnce() -> std::io::Result<ServerBuilder> + Send + 'static,
+) -> (ServerHandle, thread::JoinHandle<std::io::Result<()>>) {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = builder()?.run();
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    // workers bind their listeners once the server future is first polled
+    thread::sleep(Duration::from_millis(500));
+
+    (srv, h)
+}
+
+fn wait_for(counter: &AtomicUsize, num: usize) {
+    for _ in 0..100 {
+        if counter.load(Ordering::SeqCst) >= num {
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    panic!(
+        "expected {} connections, got {}",
+        num,
+        counter.load(Ordering::SeqCst)
+    );
+}
+
+#[test]
+fn connections_spread_across_workers() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let served = Arc::new(AtomicUsize::new(0));
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+
+    let (srv, h) = {
+        let served = served.clone();
+        let threads = threads.clone();
+
+        start(move || {
+            Server::build()
+                .workers(2)
+                .disable_signals()
+                .reuse_port()
+                .bind("test", addr, move || {
+                    let served = served.clone();
+                    let threads = threads.clone();
+
+                    fn_service(move |_: TcpStream| {
+                        threads.lock().unwrap().insert(thread::current().id());
+                        served.fetch_add(1, Ordering::SeqCst);
+                        async { Ok::<_, ()>(()) }
+                    })
+                })
+        })
+    };
+
+    let conns = (0..32)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+
+    wait_for(&served, 32);
+    assert_eq!(threads.lock().unwrap().len(), 2);
+
+    drop(conns);
+    drop(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn pause_and_resume() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let served = Arc::new(AtomicUsize::new(0));
+
+    let (srv, h) = {
+        let served = served.clone();
+
+        start(move || {
+            Server::build()
+                .workers(1)
+                .disable_signals()
+                .reuse_port()
+                .bind("test", addr, move || {
+                    let served = served.clone();
+
+                    fn_service(move |_: TcpStream| {
+                        served.fetch_add(1, Ordering::SeqCst);
+                        async { Ok::<_, ()>(()) }
+                    })
+                })
+        })
+    };
+
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    wait_for(&served, 1);
+
+    actix_rt::System::new().block_on(srv.pause());
+
+    // connection waits in the backlog while paused
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(served.load(Ordering::SeqCst), 1);
+
+    actix_rt::System::new().block_on(srv.resume());
+    wait_for(&served, 2);
+
+    drop(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn max_concurrent_connections() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let active = Arc::new(AtomicUsize::new(0));
+
+    let (srv, h) = {
+        let active = active.clone();
+
+        start(move || {
+            Server::build()
+                .workers(1)
+                .max_concurrent_connections(2)
+                .disable_signals()
+                .reuse_port()
+                .bind("test", addr, move || {
+                    let active = active.clone();
+
+                    fn_service(move |_: TcpStream| {
+                        let active = active.clone();
+
+                        async move {
+                            active.fetch_add(1, Ordering::SeqCst);
+                            sleep(Duration::from_millis(500)).await;
+                            active.fetch_sub(1, Ordering::SeqCst);
+                            Ok::<_, ()>(())
+                        }
+                    })
+                })
+        })
+    };
+
+    let _conns = (0..6)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+
+    wait_for(&active, 2);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(active.load(Ordering::SeqCst), 2);
+
+    // remaining connections are accepted as earlier ones finish
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(active.load(Ordering::SeqCst), 2);
+
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn graceful_stop() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let started = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    let (srv, h) = {
+        let started = started.clone();
+        let finished = finished.clone();
+
+        start(move || {
+            Server::build()
+                .workers(1)
+                .disable_signals()
+                .reuse_port()
+                .bind("test", addr, move || {
+                    let started = started.clone();
+                    let finished = finished.clone();
+
+                    fn_service(move |_: TcpStream| {
+                        let finished = finished.clone();
+                        started.fetch_add(1, Ordering::SeqCst);
+
+                        async move {
+                            sleep(Duration::from_millis(300)).await;
+                            finished.fetch_add(1, Ordering::SeqCst);
+                            Ok::<_, ()>(())
+                        }
+                    })
+                })
+        })
+    };
+
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    wait_for(&started, 1);
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+
+    h.join().unwrap().unwrap();
+
+    // address is released once the server stopped
+    net::TcpListener::bind(addr).unwrap();
+}
+
+#[test]
+fn scale_down_serves_queued_connections() {
+    use std::{cell::Cell, io::Read as _};
+
+    use tokio::io::AsyncWriteExt as _;
+
+    thread_local! {
+        static WORKER_IDX: Cell<usize> = const { Cell::new(0) };
+        static BLOCKED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (srv, h) = start(move || {
+        Server::build()
+            .workers(2)
+            .disable_signals()
+            .reuse_port()
+            .on_worker_start(|idx| async move { WORKER_IDX.with(|cell| cell.set(idx)) })
+            .bind("test", addr, || {
+                fn_service(|mut stream: TcpStream| async move {
+                    let idx = WORKER_IDX.with(Cell::get);
+
+                    // block the worker that is removed so connections queue on its listener
+                    if idx == 1 && !BLOCKED.with(|blocked| blocked.replace(true)) {
+                        thread::sleep(Duration::from_millis(1500));
+                    }
+
+                    stream.write_all(idx.to_string().as_bytes()).await
+                })
+            })
+    });
+
+    let connect = || {
+        let conn = net::TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        conn
+    };
+
+    let respond = |mut conn: net::TcpStream| {
+        let mut res = String::new();
+        conn.read_to_string(&mut res).map(|_| res)
+    };
+
+    // find a connection of worker 1 and one queued behind it
+    let mut waiting = Vec::new();
+    for attempt in 0.. {
+        assert!(attempt < 100, "no connection reached worker 1");
+
+        if waiting.len() == 2 {
+            break;
+        }
+
+        let conn = connect();
+        let mut probe = conn.try_clone().unwrap();
+
+        if probe.read(&mut [0; 1]).is_err() {
+            waiting.push(conn);
+        }
+    }
+
+    actix_rt::System::new()
+        .block_on(srv.set_workers(1))
+        .unwrap();
+
+    for conn in waiting {
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(respond(conn).unwrap(), "1");
+    }
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn graceful_stop_serves_connections_queued_on_idle_worker() {
+    use std::io::Read as _;
+
+    use tokio::io::AsyncWriteExt as _;
+
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (srv, h) = start(move || {
+        Server::build()
+            .workers(1)
+            .disable_signals()
+            .reuse_port()
+            .bind("test", addr, || {
+                fn_service(|mut stream: TcpStream| async move { stream.write_all(b"ok").await })
+            })
+    });
+
+    actix_rt::System::new().block_on(srv.pause());
+
+    // worker has no connections while this one waits in the backlog
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    actix_rt::System::new().block_on(srv.stop(true));
+
+    let mut res = Vec::new();
+    conn.read_to_end(&mut res).unwrap();
+    assert_eq!(res, b"ok");
+
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn graceful_stop_drops_connections_queued_for_failed_service() {
+    use std::{
+        cell::Cell,
+        task::{Context, Poll},
+        time::Instant,
+    };
+
+    use actix_server::RestartPolicy;
+    use actix_service::{fn_factory, Service};
+    use futures_core::future::LocalBoxFuture;
+
+    struct Failing(Cell<bool>);
+
+    impl Service<TcpStream> for Failing {
+        type Response = ();
+        type Error = ();
+        type Future = LocalBoxFuture<'static, Result<(), ()>>;
+
+        fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            // fails its first readiness check, leaving the service waiting for its restart
+            if self.0.replace(false) {
+                Poll::Ready(Err(()))
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        fn call(&self, _: TcpStream) -> Self::Future {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (srv, h) = start(move || {
+        Server::build()
+            .workers(1)
+            .disable_signals()
+            .shutdown_timeout(10)
+            .restart_policy(
+                RestartPolicy::new().backoff(Duration::from_secs(30), Duration::from_secs(30)),
+            )
+            .reuse_port()
+            .bind("test", addr, || {
+                fn_factory(|| async { Ok::<_, ()>(Failing(Cell::new(true))) })
+            })
+    });
+
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    // the queued connection is dropped without holding up the shutdown
+    let start = Instant::now();
+    actix_rt::System::new().block_on(srv.stop(true));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    h.join().unwrap().unwrap();
+}
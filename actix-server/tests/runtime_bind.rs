@@ -0,0 +1,145 @@
+use std::{io, net, sync::mpsc, thread, time::Duration};
+
+use actix_rt::{net::TcpStream, time::sleep};
+use actix_server::{Server, ServerHandle};
+use actix_service::{fn_factory, fn_service};
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Service factory writing `byte` to every connection after `delay`.
+fn reply(
+    byte: u8,
+    delay: Duration,
+) -> impl Fn() -> actix_service::boxed::BoxServiceFactory<(), TcpStream, (), (), ()> + Clone {
+    move || {
+        actix_service::boxed::factory(fn_service(move |mut io: TcpStream| async move {
+            sleep(delay).await;
+            io.write_all(&[byte]).await.map_err(|_| ())
+        }))
+    }
+}
+
+async fn read_byte(addr: net::SocketAddr) -> io::Result<u8> {
+    let mut conn = TcpStream::connect(addr).await?;
+    let mut buf = [0; 1];
+    conn.read_exact(&mut buf).await?;
+    Ok(buf[0])
+}
+
+fn start() -> (
+    net::SocketAddr,
+    ServerHandle,
+    thread::JoinHandle<io::Result<()>>,
+) {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(2)
+                .disable_signals()
+                .bind("static", addr, reply(b's', Duration::ZERO))?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    (addr, rx.recv().unwrap(), h)
+}
+
+#[test]
+fn bind_and_unbind() {
+    let (static_addr, srv, h) = start();
+    let addr = unused_addr();
+
+    actix_rt::System::new().block_on(async {
+        srv.bind("dynamic", addr, reply(b'd', Duration::from_millis(300)))
+            .await
+            .unwrap();
+
+        assert_eq!(read_byte(addr).await.unwrap(), b'd');
+        assert_eq!(read_byte(static_addr).await.unwrap(), b's');
+
+        let stats = srv.stats().await.unwrap();
+        let names = stats
+            .listeners
+            .iter()
+            .map(|lst| lst.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["static", "dynamic"]);
+
+        // connection in progress is left to finish
+        let in_flight = actix_rt::spawn(read_byte(addr));
+        sleep(Duration::from_millis(100)).await;
+
+        srv.unbind("dynamic").await.unwrap();
+
+        assert_eq!(in_flight.await.unwrap().unwrap(), b'd');
+        assert!(TcpStream::connect(addr).await.is_err());
+        assert_eq!(read_byte(static_addr).await.unwrap(), b's');
+
+        let err = srv.unbind("dynamic").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        // address can be bound again
+        srv.bind("dynamic", addr, reply(b'D', Duration::ZERO))
+            .await
+            .unwrap();
+        assert_eq!(read_byte(addr).await.unwrap(), b'D');
+
+        srv.stop(true).await;
+    });
+
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn bind_failing_service() {
+    let (static_addr, srv, h) = start();
+    let addr = unused_addr();
+
+    actix_rt::System::new().block_on(async {
+        let err = srv
+            .bind("failing", addr, || {
+                fn_factory(|| async {
+                    Err::<actix_service::boxed::BoxService<TcpStream, (), ()>, _>(())
+                })
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        // listener is closed and other services are unaffected
+        assert!(TcpStream::connect(addr).await.is_err());
+        assert_eq!(read_byte(static_addr).await.unwrap(), b's');
+
+        let stats = srv.stats().await.unwrap();
+        assert_eq!(stats.listeners.len(), 1);
+
+        // address is already in use
+        let err = srv
+            .bind("in-use", static_addr, reply(b'x', Duration::ZERO))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        srv.stop(true).await;
+
+        let err = srv
+            .bind("stopped", addr, reply(b'x', Duration::ZERO))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    });
+
+    h.join().unwrap().unwrap();
+}
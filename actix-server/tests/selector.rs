@@ -0,0 +1,153 @@
+use std::{
+    io, net,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle, ThreadId},
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{
+    LeastConnections, PowerOfTwoChoices, Server, ServerHandle, WorkerSelector, Workers,
+};
+use actix_service::fn_service;
+use tokio::io::AsyncReadExt as _;
+
+/// Worker threads that served each connection, in order.
+type Served = Arc<Mutex<Vec<ThreadId>>>;
+
+/// Starts a server with 2 workers where each connection is held open until the client closes it.
+fn start(
+    selector: impl WorkerSelector,
+) -> (
+    net::SocketAddr,
+    ServerHandle,
+    Served,
+    JoinHandle<io::Result<()>>,
+) {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let served = Arc::new(Mutex::new(Vec::new()));
+    let (tx, rx) = mpsc::channel();
+
+    let h = {
+        let served = served.clone();
+
+        thread::spawn(move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(2)
+                    .disable_signals()
+                    .worker_selector(selector)
+                    .bind("test", addr, move || {
+                        let served = served.clone();
+
+                        fn_service(move |mut io: TcpStream| {
+                            served.lock().unwrap().push(thread::current().id());
+
+                            async move {
+                                let mut buf = [0; 1];
+                                let _ = io.read(&mut buf).await;
+                                Ok::<_, ()>(())
+                            }
+                        })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        })
+    };
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    (addr, srv, served, h)
+}
+
+fn wait_for(served: &Mutex<Vec<ThreadId>>, num: usize) {
+    for _ in 0..100 {
+        if served.lock().unwrap().len() >= num {
+            // let connection counters settle
+            thread::sleep(Duration::from_millis(50));
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    panic!("connection {} was not served", num);
+}
+
+#[test]
+fn least_connections() {
+    let (addr, srv, served, h) = start(LeastConnections::default());
+
+    // keep one worker busy with a long-lived connection
+    let _long = net::TcpStream::connect(addr).unwrap();
+    wait_for(&served, 1);
+    let busy = served.lock().unwrap()[0];
+
+    // short-lived connections all go to the idle worker
+    for num in 2..=6 {
+        let conn = net::TcpStream::connect(addr).unwrap();
+        wait_for(&served, num);
+        drop(conn);
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let served = served.lock().unwrap();
+    assert!(served[1..].iter().all(|id| *id != busy));
+
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn power_of_two_choices() {
+    let (addr, srv, served, h) = start(PowerOfTwoChoices::new());
+
+    let conns = (0..4)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+    wait_for(&served, 4);
+
+    // with only two workers, both are compared for every connection
+    let served_by = served.lock().unwrap().clone();
+    let first = served_by.iter().filter(|id| **id == served_by[0]).count();
+    assert_eq!(first, 2);
+
+    drop(conns);
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn custom_selector() {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    struct Counting(Arc<AtomicUsize>, LeastConnections);
+
+    impl WorkerSelector for Counting {
+        fn select(&mut self, workers: &Workers<'_>) -> usize {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(workers.len(), 2);
+            self.1.select(workers)
+        }
+    }
+
+    let (addr, srv, served, h) = start(Counting(counter.clone(), LeastConnections::default()));
+
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    wait_for(&served, 1);
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+    drop(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
@@ -487,6 +487,15 @@ async fn worker_restart() {
     assert_eq!("3", id);
     stream.shutdown().await.unwrap();
 
+    let stats = srv.stats().await.unwrap();
+    let restarts = stats
+        .workers
+        .iter()
+        .map(|wrk| wrk.restarts)
+        .collect::<Vec<_>>();
+    assert_eq!(restarts, [0, 1]);
+    assert_eq!(stats.listeners[0].accepted, 6);
+
     let _ = srv.stop(false);
     h.join().unwrap().unwrap();
 }
@@ -0,0 +1,118 @@
+use std::{io, net, sync::mpsc, thread, time::Duration};
+
+use actix_rt::{net::TcpStream, time::sleep};
+use actix_server::{Server, ServerHandle};
+use actix_service::fn_service;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+fn start() -> (
+    net::SocketAddr,
+    ServerHandle,
+    thread::JoinHandle<io::Result<()>>,
+) {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind("test", addr, || {
+                    fn_service(|mut io: TcpStream| async move {
+                        // client sends the delay in tenths of a second
+                        let mut buf = [0; 1];
+                        io.read_exact(&mut buf).await.map_err(|_| ())?;
+                        sleep(Duration::from_millis(buf[0] as u64 * 100)).await;
+                        io.write_all(b"x").await.map_err(|_| ())
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    (addr, srv, h)
+}
+
+async fn request(addr: net::SocketAddr, delay: u8) -> io::Result<()> {
+    let mut conn = TcpStream::connect(addr).await?;
+    conn.write_all(&[delay]).await?;
+    let mut buf = [0; 1];
+    conn.read_exact(&mut buf).await?;
+    Ok(())
+}
+
+async fn worker_indexes(srv: &ServerHandle) -> Vec<usize> {
+    let stats = srv.stats().await.unwrap();
+    stats.workers.iter().map(|wrk| wrk.idx).collect()
+}
+
+#[test]
+fn scale_up_and_down() {
+    let (addr, srv, h) = start();
+
+    actix_rt::System::new().block_on(async {
+        srv.set_workers(3).await.unwrap();
+        assert_eq!(worker_indexes(&srv).await, [0, 1, 2]);
+
+        for _ in 0..6 {
+            request(addr, 0).await.unwrap();
+        }
+
+        // connections held by removed workers are finished
+        let in_flight = (0..3)
+            .map(|_| actix_rt::spawn(request(addr, 5)))
+            .collect::<Vec<_>>();
+        sleep(Duration::from_millis(200)).await;
+
+        srv.set_workers(1).await.unwrap();
+        assert_eq!(worker_indexes(&srv).await, [0]);
+
+        request(addr, 0).await.unwrap();
+
+        for conn in in_flight {
+            conn.await.unwrap().unwrap();
+        }
+
+        // indexes are reused once removed workers have stopped
+        sleep(Duration::from_millis(1500)).await;
+        srv.set_workers(2).await.unwrap();
+        assert_eq!(worker_indexes(&srv).await, [0, 1]);
+        request(addr, 0).await.unwrap();
+
+        srv.stop(true).await;
+    });
+
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn invalid_worker_count() {
+    let (_addr, srv, h) = start();
+
+    actix_rt::System::new().block_on(async {
+        let err = srv.set_workers(0).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = srv.set_workers(513).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        assert_eq!(worker_indexes(&srv).await, [0]);
+
+        srv.stop(true).await;
+
+        let err = srv.set_workers(2).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    });
+
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,101 @@
+#![cfg(unix)]
+
+use std::{
+    net,
+    process::Command,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::{net::TcpStream, time::sleep};
+use actix_server::{Server, Signal, SignalAction};
+use actix_service::fn_service;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+fn kill(signal: &str) {
+    let status = Command::new("kill")
+        .args([signal, &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+// all signal tests share one test so that signals are not received by servers of other tests
+#[test]
+fn signal_actions() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let (hup_tx, hup_rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .shutdown_timeout(60)
+                .signal(
+                    Signal::Hup,
+                    SignalAction::callback(move |_| hup_tx.send(()).unwrap()),
+                )
+                .signal(Signal::Usr1, SignalAction::Pause)
+                .signal(Signal::Usr2, SignalAction::Resume)
+                .bind("test", addr, || {
+                    fn_service(|_: TcpStream| async {
+                        // keeps the connection open for longer than the test waits for
+                        sleep(Duration::from_secs(60)).await;
+                        Ok::<_, ()>(())
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    kill("-HUP");
+    hup_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let stats = || actix_rt::System::new().block_on(srv.stats()).unwrap();
+    let paused = || stats().paused;
+
+    kill("-USR1");
+    thread::sleep(Duration::from_millis(100));
+    assert!(paused());
+
+    kill("-USR2");
+    thread::sleep(Duration::from_millis(100));
+    assert!(!paused());
+
+    // graceful shutdown waits for the open connection ...
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    while stats().workers[0].connections == 0 {
+        thread::sleep(Duration::from_millis(10));
+    }
+    kill("-TERM");
+    thread::sleep(Duration::from_millis(500));
+    assert!(!h.is_finished());
+
+    // ... until another stop signal forces it
+    let start = Instant::now();
+    kill("-TERM");
+
+    while !h.is_finished() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "shutdown was not forced"
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    h.join().unwrap().unwrap();
+}
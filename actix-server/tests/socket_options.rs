@@ -0,0 +1,92 @@
+use std::{
+    io::{self, Read as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{Server, SocketOptions};
+use actix_service::fn_service;
+use socket2::SockRef;
+use tokio::io::AsyncWriteExt as _;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Replies with whether `TCP_NODELAY` and `SO_KEEPALIVE` are set on the accepted connection.
+async fn report(mut io: TcpStream) -> io::Result<()> {
+    let nodelay = io.nodelay()?;
+    let keepalive = SockRef::from(&io).keepalive()?;
+
+    io.write_all(&[nodelay as u8, keepalive as u8]).await
+}
+
+#[test]
+fn connection_options() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let options = SocketOptions::new()
+                .nodelay(true)
+                .keepalive(Duration::from_secs(60))
+                .recv_buffer_size(64 * 1024);
+
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .bind_with_options("options", addr, options, || fn_service(report))?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut res = [0; 2];
+    conn.read_exact(&mut res).unwrap();
+    assert_eq!(res, [1, 1]);
+
+    actix_rt::System::new().block_on(async {
+        // connections of listeners bound at runtime get their options too
+        let addr = unused_addr();
+        let options = SocketOptions::new().nodelay(true);
+        srv.bind_with_options("runtime", addr, options, || fn_service(report))
+            .await
+            .unwrap();
+
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut res = [0; 2];
+        conn.read_exact(&mut res).unwrap();
+        assert_eq!(res, [1, 0]);
+
+        srv.stop(false).await;
+    });
+
+    h.join().unwrap().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn invalid_listener_option() {
+    let options = SocketOptions::new().bind_device("no-such-device0");
+
+    let res = Server::build().bind_with_options("invalid", "127.0.0.1:0", options, || {
+        fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+    });
+
+    assert!(res.is_err());
+}
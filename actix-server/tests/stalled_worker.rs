@@ -0,0 +1,210 @@
+use std::{
+    io::{Read as _, Write as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{Server, ServerHandle, WorkerEvent};
+use actix_service::fn_service;
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    sync::broadcast,
+};
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Starts server whose service blocks its worker for `block` when the client sends `b` and holds
+/// the connection for 3s without blocking when it sends `h`.
+fn start(
+    addr: net::SocketAddr,
+    workers: usize,
+    block: Duration,
+    replace: bool,
+    max_conns: usize,
+) -> (ServerHandle, thread::JoinHandle<std::io::Result<()>>) {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let mut builder = Server::build()
+                .workers(workers)
+                .disable_signals()
+                .max_concurrent_connections(max_conns)
+                .worker_stall_timeout(Duration::from_millis(200));
+
+            if replace {
+                builder = builder.replace_stalled_workers();
+            }
+
+            let srv = builder
+                .bind("test", addr, move || {
+                    fn_service(move |mut stream: TcpStream| async move {
+                        let mut buf = [0; 1];
+                        stream.read_exact(&mut buf).await?;
+
+                        if buf[0] == b'b' {
+                            // runaway synchronous work blocking the worker's event loop
+                            thread::sleep(block);
+                        } else if buf[0] == b'h' {
+                            actix_rt::time::sleep(Duration::from_secs(3)).await;
+                        }
+
+                        stream.write_all(b"ok").await
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    (srv, h)
+}
+
+fn next_event(events: &mut broadcast::Receiver<WorkerEvent>) -> WorkerEvent {
+    let start = Instant::now();
+
+    loop {
+        if let Ok(event) = events.try_recv() {
+            return event;
+        }
+
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "no worker event received"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn request(addr: net::SocketAddr, byte: u8) -> net::TcpStream {
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    conn.write_all(&[byte]).unwrap();
+    conn
+}
+
+fn assert_ok(mut conn: net::TcpStream) {
+    let mut res = Vec::new();
+    conn.read_to_end(&mut res).unwrap();
+    assert_eq!(res, b"ok");
+}
+
+#[test]
+fn stalled_worker_is_skipped() {
+    let addr = unused_addr();
+    let (srv, h) = start(addr, 2, Duration::from_millis(1500), false, 25_600);
+    let mut events = srv.worker_events();
+    let stats = || actix_rt::System::new().block_on(srv.stats()).unwrap();
+
+    let blocked = request(addr, b'b');
+
+    let idx = match next_event(&mut events) {
+        WorkerEvent::Stalled { idx, since } => {
+            assert!(since > Duration::from_millis(200));
+            idx
+        }
+        event => panic!("unexpected event: {event:?}"),
+    };
+
+    assert!(stats().workers[idx].stalled);
+
+    // connections are served by the other worker in the meantime
+    for _ in 0..4 {
+        let start = Instant::now();
+        assert_ok(request(addr, b'a'));
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    assert_eq!(next_event(&mut events), WorkerEvent::Recovered { idx });
+    assert!(!stats().workers[idx].stalled);
+    assert_ok(blocked);
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn stalled_worker_is_replaced() {
+    let addr = unused_addr();
+    let (srv, h) = start(addr, 1, Duration::from_secs(2), true, 25_600);
+    let mut events = srv.worker_events();
+
+    let _blocked = request(addr, b'b');
+
+    assert!(matches!(
+        next_event(&mut events),
+        WorkerEvent::Stalled { idx: 0, .. }
+    ));
+    assert_eq!(
+        next_event(&mut events),
+        WorkerEvent::Restarting {
+            idx: 0,
+            delay: Duration::ZERO
+        }
+    );
+    assert_eq!(next_event(&mut events), WorkerEvent::Restarted { idx: 0 });
+
+    // the new worker serves connections while the stalled one is still blocked
+    let start = Instant::now();
+    assert_ok(request(addr, b'a'));
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    let stats = actix_rt::System::new().block_on(srv.stats()).unwrap();
+    assert_eq!(stats.workers[0].restarts, 1);
+    assert!(!stats.workers[0].stalled);
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn replaced_worker_does_not_free_replacement() {
+    let addr = unused_addr();
+    let (srv, h) = start(addr, 1, Duration::from_secs(1), true, 2);
+    let mut events = srv.worker_events();
+
+    let mut blocked = request(addr, b'b');
+
+    assert!(matches!(
+        next_event(&mut events),
+        WorkerEvent::Stalled { idx: 0, .. }
+    ));
+    assert!(matches!(
+        next_event(&mut events),
+        WorkerEvent::Restarting { idx: 0, .. }
+    ));
+    assert_eq!(next_event(&mut events), WorkerEvent::Restarted { idx: 0 });
+
+    // fill up the new worker
+    let held = [request(addr, b'h'), request(addr, b'h')];
+    thread::sleep(Duration::from_millis(100));
+
+    // connection of the stalled worker finishes while the new one is still full
+    let mut res = Vec::new();
+    drop(blocked.read_to_end(&mut res));
+    thread::sleep(Duration::from_millis(100));
+
+    let start = Instant::now();
+    assert_ok(request(addr, b'a'));
+    assert!(start.elapsed() > Duration::from_secs(1));
+
+    for conn in held {
+        assert_ok(conn);
+    }
+
+    actix_rt::System::new().block_on(srv.stop(false));
+    h.join().unwrap().unwrap();
+}
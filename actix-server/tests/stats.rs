@@ -0,0 +1,135 @@
+use std::{net, sync::mpsc, thread, time::Duration};
+
+use actix_rt::{net::TcpStream, time::sleep};
+use actix_server::Server;
+use actix_service::fn_service;
+
+#[test]
+fn stats_snapshot() {
+    let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(2)
+                .disable_signals()
+                .bind("idle", "127.0.0.1:0", || {
+                    fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                })?
+                .listen("busy", lst, || {
+                    fn_service(|_: TcpStream| async {
+                        sleep(Duration::from_secs(60)).await;
+                        Ok::<_, ()>(())
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let _conns = (0..3)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(300));
+
+    actix_rt::System::new().block_on(async {
+        let stats = srv.stats().await.unwrap();
+
+        assert!(!stats.paused);
+
+        let listeners = stats
+            .listeners
+            .iter()
+            .map(|lst| (lst.token, lst.name.as_str(), lst.accepted, lst.rejected))
+            .collect::<Vec<_>>();
+        assert_eq!(listeners, [(0, "idle", 0, 0), (1, "busy", 3, 0)]);
+
+        let workers = stats
+            .workers
+            .iter()
+            .map(|wrk| (wrk.idx, wrk.restarts))
+            .collect::<Vec<_>>();
+        assert_eq!(workers, [(0, 0), (1, 0)]);
+
+        let connections = stats
+            .workers
+            .iter()
+            .map(|wrk| wrk.connections)
+            .sum::<usize>();
+        assert_eq!(connections, 3);
+
+        srv.pause().await;
+        assert!(srv.stats().await.unwrap().paused);
+
+        srv.resume().await;
+        assert!(!srv.stats().await.unwrap().paused);
+
+        srv.stop(false).await;
+    });
+
+    h.join().unwrap().unwrap();
+
+    // handle outlives the server
+    let stats = actix_rt::System::new().block_on(srv.stats());
+    assert!(stats.is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn stats_with_reuse_port() {
+    let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+    let reuse_addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .reuse_port()
+                .bind("reuse", reuse_addr, || {
+                    fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                })?
+                .listen("accept", lst, || {
+                    fn_service(|_: TcpStream| async { Ok::<_, ()>(()) })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    net::TcpStream::connect(reuse_addr).unwrap();
+    net::TcpStream::connect(addr).unwrap();
+    net::TcpStream::connect(addr).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    actix_rt::System::new().block_on(async {
+        let stats = srv.stats().await.unwrap();
+
+        let listeners = stats
+            .listeners
+            .iter()
+            .map(|lst| (lst.name.as_str(), lst.accepted))
+            .collect::<Vec<_>>();
+        assert_eq!(listeners, [("reuse", 1), ("accept", 2)]);
+
+        srv.stop(true).await;
+    });
+
+    h.join().unwrap().unwrap();
+}
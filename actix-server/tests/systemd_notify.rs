@@ -0,0 +1,94 @@
+#![cfg(unix)]
+
+use std::{
+    io, net,
+    os::unix::net::UnixDatagram,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::Server;
+use actix_service::fn_service;
+
+fn recv(sock: &UnixDatagram) -> io::Result<String> {
+    let mut buf = [0; 256];
+    let n = sock.recv(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf[..n]).into_owned())
+}
+
+/// Receive notifications until one that is not a watchdog ping.
+fn recv_state(sock: &UnixDatagram) -> String {
+    loop {
+        let msg = recv(sock).unwrap();
+        if msg != "WATCHDOG=1" {
+            return msg;
+        }
+    }
+}
+
+#[test]
+fn notify_lifecycle_and_watchdog() {
+    let path = std::env::temp_dir().join(format!("actix-server-notify-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let sock = UnixDatagram::bind(&path).unwrap();
+    sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    std::env::set_var("NOTIFY_SOCKET", &path);
+    std::env::set_var("WATCHDOG_USEC", "200000");
+    std::env::set_var("WATCHDOG_PID", std::process::id().to_string());
+
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .systemd_notify()
+                .bind("test", addr, || {
+                    fn_service(|_: TcpStream| async {
+                        // block the worker's event loop
+                        thread::sleep(Duration::from_millis(1500));
+                        Ok::<_, ()>(())
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let ready = recv(&sock).unwrap();
+    assert!(ready.starts_with("READY=1\nMAINPID="));
+    assert_eq!(recv(&sock).unwrap(), "WATCHDOG=1");
+
+    // a blocked worker stops the watchdog pings
+    let _conn = net::TcpStream::connect(addr).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    while sock.set_nonblocking(true).is_ok() && recv(&sock).is_ok() {}
+    sock.set_nonblocking(false).unwrap();
+
+    sock.set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let start = Instant::now();
+    assert!(recv(&sock).is_err());
+    assert!(start.elapsed() >= Duration::from_millis(400));
+
+    // pings resume once the worker is responsive again
+    sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(recv(&sock).unwrap(), "WATCHDOG=1");
+
+    drop(srv.stop(true));
+    assert_eq!(recv_state(&sock), "STOPPING=1");
+
+    h.join().unwrap().unwrap();
+}
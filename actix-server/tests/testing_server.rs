@@ -69,5 +69,105 @@ async fn new_with_builder() {
     srv.connect().unwrap();
 
     // connect to alt service defined in custom ServerBuilder
-    TcpStream::from_std(net::TcpStream::connect(alt_addr).unwrap()).unwrap();
+    let alt_conn = net::TcpStream::connect(alt_addr).unwrap();
+    alt_conn.set_nonblocking(true).unwrap();
+    TcpStream::from_std(alt_conn).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn multiple_listeners() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use actix_rt::net::UnixStream;
+    use tokio::io::DuplexStream;
+
+    let created = Arc::new(AtomicUsize::new(0));
+
+    let srv = TestServer::build()
+        .workers(3)
+        .listen("tcp", {
+            let created = created.clone();
+
+            move || {
+                created.fetch_add(1, Ordering::SeqCst);
+                fn_service(|mut stream: TcpStream| async move { stream.write_all(b"tcp").await })
+            }
+        })
+        .listen_uds("uds", {
+            let created = created.clone();
+
+            move || {
+                created.fetch_add(1, Ordering::SeqCst);
+                fn_service(|mut stream: UnixStream| async move { stream.write_all(b"uds").await })
+            }
+        })
+        .listen_memory("memory", {
+            let created = created.clone();
+
+            move || {
+                created.fetch_add(1, Ordering::SeqCst);
+                fn_service(
+                    |mut stream: DuplexStream| async move { stream.write_all(b"memory").await },
+                )
+            }
+        })
+        .start();
+
+    // all workers have started their services once the server is started
+    assert_eq!(created.load(Ordering::SeqCst), 9);
+
+    let path = srv.uds_path("uds").to_owned();
+    assert!(path.exists());
+    assert!(srv.connect_to("uds").is_err());
+
+    actix_rt::System::new().block_on(async {
+        let mut buf = Vec::new();
+        srv.connect_to("tcp")
+            .unwrap()
+            .read_to_end(&mut buf)
+            .await
+            .unwrap();
+        assert_eq!(buf, b"tcp");
+
+        let mut buf = Vec::new();
+        srv.connect_uds("uds")
+            .unwrap()
+            .read_to_end(&mut buf)
+            .await
+            .unwrap();
+        assert_eq!(buf, b"uds");
+
+        let mut buf = Vec::new();
+        srv.connect_memory_to("memory")
+            .unwrap()
+            .read_to_end(&mut buf)
+            .await
+            .unwrap();
+        assert_eq!(buf, b"memory");
+    });
+
+    assert_eq!(srv.addr_of("tcp"), srv.addr());
+    assert!(srv.stop());
+    assert!(!path.exists());
+}
+
+#[test]
+fn stop_drops_connections_after_timeout() {
+    let srv = TestServer::build_with(Server::build().shutdown_timeout(1))
+        .listen_memory("memory", || {
+            fn_service(|mut stream: tokio::io::DuplexStream| async move {
+                // waits for the client, which never writes
+                stream.read_u8().await
+            })
+        })
+        .start();
+
+    let _conn = srv.connect_memory().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    assert!(!srv.stop());
 }
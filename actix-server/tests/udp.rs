@@ -0,0 +1,138 @@
+use std::{
+    io, net,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use actix_rt::net::UdpSocket;
+use actix_server::Server;
+use actix_service::fn_service;
+
+fn unused_addr() -> net::SocketAddr {
+    net::UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+fn client() -> net::UdpSocket {
+    let socket = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    socket
+}
+
+async fn echo(socket: UdpSocket) -> io::Result<()> {
+    let mut buf = [0; 64];
+
+    loop {
+        let (len, peer) = socket.recv_from(&mut buf).await?;
+        socket.send_to(&buf[..len], peer).await?;
+    }
+}
+
+fn roundtrip(socket: &net::UdpSocket, addr: net::SocketAddr, msg: &[u8]) -> io::Result<Vec<u8>> {
+    socket.send_to(msg, addr)?;
+    let mut buf = [0; 64];
+    let (len, _) = socket.recv_from(&mut buf)?;
+    Ok(buf[..len].to_vec())
+}
+
+#[test]
+fn echo_on_every_worker() {
+    let addr = unused_addr();
+    let started = Arc::new(Mutex::new(Vec::<ThreadId>::new()));
+    let (tx, rx) = mpsc::channel();
+
+    let h = {
+        let started = started.clone();
+
+        thread::spawn(move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(2)
+                    .disable_signals()
+                    .bind_udp("echo", addr, move || {
+                        let started = started.clone();
+
+                        fn_service(move |socket: UdpSocket| {
+                            started.lock().unwrap().push(thread::current().id());
+
+                            echo(socket)
+                        })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        })
+    };
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    // service is called once per worker, on the worker's thread
+    let started = started.lock().unwrap().clone();
+    assert_eq!(started.len(), 2);
+    assert_ne!(started[0], started[1]);
+
+    let socket = client();
+    for num in 0..5u8 {
+        assert_eq!(roundtrip(&socket, addr, &[num]).unwrap(), [num]);
+    }
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+
+    // socket is closed once the server has stopped
+    assert!(net::UdpSocket::bind(addr).is_ok());
+}
+
+#[test]
+fn graceful_shutdown_with_streams() {
+    let udp_addr = unused_addr();
+    let tcp_addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .shutdown_timeout(5)
+                .bind("hold", tcp_addr, || {
+                    fn_service(|_io: actix_rt::net::TcpStream| async {
+                        actix_rt::time::sleep(Duration::from_millis(1500)).await;
+                        Ok::<_, ()>(())
+                    })
+                })?
+                .bind_udp("echo", udp_addr, || fn_service(echo))?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let _conn = net::TcpStream::connect(tcp_addr).unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    let stop = thread::spawn(move || actix_rt::System::new().block_on(srv.stop(true)));
+    thread::sleep(Duration::from_millis(300));
+
+    // datagrams are still answered while the worker drains its connections
+    let socket = client();
+    assert_eq!(roundtrip(&socket, udp_addr, b"hi").unwrap(), b"hi");
+
+    stop.join().unwrap();
+    h.join().unwrap().unwrap();
+}
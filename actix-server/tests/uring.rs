@@ -0,0 +1,167 @@
+#![cfg(all(target_os = "linux", feature = "io-uring"))]
+
+use std::{
+    io::{Read as _, Write as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use actix_server::{Server, ServerHandle};
+use actix_service::fn_service;
+use tokio_uring::net::TcpStream;
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn uring_echo() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(2)
+                .disable_signals()
+                .bind_uring("test", addr, || {
+                    fn_service(|stream: TcpStream| async move {
+                        let (res, buf) = stream.read(vec![0; 64]).await;
+                        let n = res?;
+                        let (res, _) = stream.write_all(buf[..n].to_vec()).await;
+                        res
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    for _ in 0..3 {
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(b"uring").unwrap();
+        let mut res = Vec::new();
+        conn.read_to_end(&mut res).unwrap();
+        assert_eq!(res, b"uring");
+    }
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+/// Starts a server with one worker answering every connection with "ok" after `delay`.
+fn start_delayed(
+    addr: net::SocketAddr,
+    delay: Duration,
+    max_conns: usize,
+) -> (ServerHandle, thread::JoinHandle<std::io::Result<()>>) {
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .max_concurrent_connections(max_conns)
+                .disable_signals()
+                .bind_uring("test", addr, move || {
+                    fn_service(move |stream: TcpStream| async move {
+                        actix_rt::time::sleep(delay).await;
+                        let (res, _) = stream.write_all(b"ok".to_vec()).await;
+                        res
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+    (srv, h)
+}
+
+/// Connects to `addr`, returning whether the server answered within `timeout`.
+fn answered(conn: &mut net::TcpStream, timeout: Duration) -> bool {
+    conn.set_read_timeout(Some(timeout)).unwrap();
+    let mut buf = [0; 2];
+    conn.read_exact(&mut buf).is_ok()
+}
+
+#[test]
+fn uring_pause_resume() {
+    let addr = unused_addr();
+    let (srv, h) = start_delayed(addr, Duration::ZERO, 25_600);
+    let rt = actix_rt::System::new();
+
+    rt.block_on(srv.pause());
+
+    // accept request is cancelled; the connection waits on the listener
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    assert!(!answered(&mut conn, Duration::from_millis(500)));
+
+    rt.block_on(srv.resume());
+    assert!(answered(&mut conn, Duration::from_secs(5)));
+
+    rt.block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn uring_connection_limit() {
+    let addr = unused_addr();
+    let (srv, h) = start_delayed(addr, Duration::from_secs(1), 2);
+
+    let mut conns = (0..2)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(200));
+
+    // the worker is full until the first connections are done
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    assert!(!answered(&mut conn, Duration::from_millis(500)));
+
+    for conn in &mut conns {
+        assert!(answered(conn, Duration::from_secs(5)));
+    }
+    assert!(answered(&mut conn, Duration::from_secs(5)));
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+}
+
+#[test]
+fn uring_graceful_stop() {
+    let addr = unused_addr();
+    let (srv, h) = start_delayed(addr, Duration::from_millis(500), 25_600);
+
+    let mut conns = (0..3)
+        .map(|_| net::TcpStream::connect(addr).unwrap())
+        .collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(100));
+
+    // connections accepted before stopping are finished
+    let stop = thread::spawn(move || actix_rt::System::new().block_on(srv.stop(true)));
+
+    for conn in &mut conns {
+        assert!(answered(conn, Duration::from_secs(5)));
+    }
+
+    stop.join().unwrap();
+    h.join().unwrap().unwrap();
+
+    // no listener is left to accept
+    assert!(net::TcpStream::connect(addr).is_err());
+}
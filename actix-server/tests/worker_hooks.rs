@@ -0,0 +1,91 @@
+use std::{
+    cell::Cell,
+    io::Read as _,
+    net,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::Server;
+use actix_service::fn_service;
+use tokio::io::AsyncWriteExt as _;
+
+thread_local! {
+    static WORKER_IDX: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn unused_addr() -> net::SocketAddr {
+    net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn worker_start_and_stop() {
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+
+    let h = thread::spawn({
+        let stopped = stopped.clone();
+
+        move || {
+            actix_rt::System::new().block_on(async {
+                let srv = Server::build()
+                    .workers(2)
+                    .disable_signals()
+                    .on_worker_start(|idx| async move {
+                        actix_rt::time::sleep(Duration::from_millis(10)).await;
+                        WORKER_IDX.with(|cell| cell.set(Some(idx)));
+                    })
+                    .on_worker_stop(move |idx| {
+                        let stopped = stopped.clone();
+
+                        async move {
+                            actix_rt::time::sleep(Duration::from_millis(10)).await;
+                            stopped.lock().unwrap().push(idx);
+                        }
+                    })
+                    .bind("hooks", addr, || {
+                        // services are created after the start hook has run
+                        let idx = WORKER_IDX.with(Cell::get).unwrap();
+
+                        fn_service(move |mut stream: TcpStream| async move {
+                            stream.write_all(&[idx as u8]).await
+                        })
+                    })?
+                    .run();
+
+                tx.send(srv.handle()).unwrap();
+                srv.await
+            })
+        }
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mut workers = (0..2)
+        .map(|_| {
+            let mut conn = net::TcpStream::connect(addr).unwrap();
+            conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let mut res = [0; 1];
+            conn.read_exact(&mut res).unwrap();
+            res[0]
+        })
+        .collect::<Vec<_>>();
+    workers.sort_unstable();
+    assert_eq!(workers, [0, 1]);
+
+    assert!(stopped.lock().unwrap().is_empty());
+
+    actix_rt::System::new().block_on(srv.stop(true));
+    h.join().unwrap().unwrap();
+
+    let mut stopped = stopped.lock().unwrap().clone();
+    stopped.sort_unstable();
+    assert_eq!(stopped, [0, 1]);
+}
@@ -0,0 +1,67 @@
+use std::{
+    io::{self, Read as _},
+    net,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_rt::net::TcpStream;
+use actix_server::{worker_shutdown, Server};
+use actix_service::fn_service;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+#[test]
+fn long_lived_connections_are_notified() {
+    let addr = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        actix_rt::System::new().block_on(async {
+            let srv = Server::build()
+                .workers(1)
+                .disable_signals()
+                .shutdown_timeout(30)
+                .bind("stream", addr, || {
+                    fn_service(|mut io: TcpStream| async move {
+                        io.write_all(b"hello").await?;
+
+                        let mut buf = [0; 1];
+                        tokio::select! {
+                            _ = io.read(&mut buf) => Ok::<_, io::Error>(()),
+                            _ = worker_shutdown() => io.write_all(b"bye").await,
+                        }
+                    })
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+            srv.await
+        })
+    });
+
+    let srv = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let mut conn = net::TcpStream::connect(addr).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut buf = [0; 5];
+    conn.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    let start = Instant::now();
+    let stop = thread::spawn(move || actix_rt::System::new().block_on(srv.stop(true)));
+
+    // the connection is wound down well before the shutdown timeout
+    let mut res = Vec::new();
+    conn.read_to_end(&mut res).unwrap();
+    assert_eq!(res, b"bye");
+
+    stop.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    h.join().unwrap().unwrap();
+}